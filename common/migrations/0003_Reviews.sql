@@ -0,0 +1,34 @@
+CREATE SCHEMA IF NOT EXISTS review;
+
+CREATE TYPE rating AS (
+    overall SMALLINT,
+    reliability SMALLINT,
+    quality SMALLINT,
+    support SMALLINT
+);
+
+    ------------------------- REVIEW -------------------------
+CREATE OR REPLACE FUNCTION create_review_table(
+    table_name TEXT
+)
+RETURNS void AS $$
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE review.%I (
+            id              ulid PRIMARY KEY,
+            parent_id       ulid REFERENCES subject.%I(id) ON DELETE CASCADE NOT NULL,
+            user_id         ulid REFERENCES auth.user(id) NOT NULL,
+            title           VARCHAR(255) NOT NULL,
+            content         TEXT NOT NULL,
+            rating          rating NOT NULL,
+            created_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            updated_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (parent_id, user_id)
+        )',
+        table_name,
+        table_name
+    );
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT create_review_table('website');
@@ -0,0 +1,9 @@
+-- Karma is computed by the application now (see `common::database::reviewable::karma`).
+DROP TRIGGER IF EXISTS update_karma ON subject.website;
+DROP FUNCTION IF EXISTS calculate_karma();
+
+ALTER TABLE subject.website
+    ALTER COLUMN karma SET DEFAULT ROW(0, 0, 0, 0)::karma,
+    ADD COLUMN visits BIGINT NOT NULL DEFAULT 0;
+
+UPDATE subject.website SET karma = ROW(0, 0, 0, 0)::karma WHERE karma IS NULL;
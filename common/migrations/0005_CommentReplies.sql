@@ -0,0 +1,27 @@
+ALTER TABLE comment.website
+    ADD COLUMN reply_to ulid REFERENCES comment.website(id) ON DELETE CASCADE;
+
+CREATE INDEX website_comment_reply_to_idx ON comment.website (reply_to);
+
+CREATE OR REPLACE FUNCTION create_comment_table(
+    table_name TEXT
+)
+RETURNS void AS $$
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE comment.%I (
+            id              ulid PRIMARY KEY,
+            parent_id       ulid REFERENCES subject.%I(id),
+            reply_to        ulid REFERENCES comment.%I(id) ON DELETE CASCADE,
+            user_id ulid REFERENCES auth.user(id) NOT NULL,
+            content            TEXT NOT NULL,
+            created_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            updated_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+        )',
+        table_name,
+        table_name,
+        table_name
+    );
+    EXECUTE format('CREATE INDEX %I ON comment.%I (reply_to)', table_name || '_comment_reply_to_idx', table_name);
+END;
+$$ LANGUAGE plpgsql;
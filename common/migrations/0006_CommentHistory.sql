@@ -0,0 +1,14 @@
+    ------------------------- COMMENT HISTORY -------------------------
+CREATE TABLE comment.edit_history
+(
+    id           ulid PRIMARY KEY,
+    comment_type VARCHAR(50) NOT NULL,
+    comment_id   ulid NOT NULL,
+    editor_id    ulid REFERENCES auth.user (id) ON DELETE SET NULL,
+    -- content of the comment before the edit
+    content      TEXT NOT NULL,
+    is_deletion  BOOLEAN NOT NULL DEFAULT false,
+    edited_at    TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX comment_edit_history_comment_idx ON comment.edit_history (comment_type, comment_id);
@@ -0,0 +1,12 @@
+    ------------------------- COMMENT VOTES -------------------------
+CREATE TABLE comment.vote
+(
+    comment_type VARCHAR(50) NOT NULL,
+    comment_id   ulid NOT NULL,
+    user_id      ulid REFERENCES auth.user (id) ON DELETE CASCADE NOT NULL,
+    value        SMALLINT NOT NULL CHECK (value IN (-1, 1)),
+    created_at   TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (comment_type, comment_id, user_id)
+);
+
+CREATE INDEX comment_vote_comment_idx ON comment.vote (comment_type, comment_id);
@@ -0,0 +1,26 @@
+    ------------------------- SEARCH -------------------------
+CREATE EXTENSION IF NOT EXISTS unaccent;
+
+-- Postgres has no Czech stemmer, so words are only lowercased and stripped of diacritics,
+-- which lets "spolecnost" match "Společnost".
+CREATE TEXT SEARCH CONFIGURATION podela (COPY = simple);
+ALTER TEXT SEARCH CONFIGURATION podela
+    ALTER MAPPING FOR hword, hword_part, word WITH unaccent, simple;
+
+ALTER TABLE subject.website ADD COLUMN search tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('podela', coalesce(name, '')), 'A') ||
+    setweight(to_tsvector('podela', coalesce(domain_name, '')), 'A') ||
+    setweight(to_tsvector('podela', coalesce(description, '')), 'B')
+) STORED;
+
+ALTER TABLE subject.organization ADD COLUMN search tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('podela', coalesce(name, '')), 'A')
+) STORED;
+
+ALTER TABLE auth.user ADD COLUMN search tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('podela', coalesce(name, '')), 'A')
+) STORED;
+
+CREATE INDEX website_search_idx ON subject.website USING GIN (search);
+CREATE INDEX organization_search_idx ON subject.organization USING GIN (search);
+CREATE INDEX user_search_idx ON auth.user USING GIN (search);
@@ -0,0 +1,15 @@
+    ------------------------- API TOKENS -------------------------
+CREATE TABLE auth.api_token
+(
+    id           ulid PRIMARY KEY,
+    user_id      ulid REFERENCES auth.user (id) ON DELETE CASCADE NOT NULL,
+    name         VARCHAR(100) NOT NULL,
+    -- SHA-256 of the secret part, hex-encoded
+    token_hash   CHAR(64) NOT NULL,
+    scopes       TEXT[] NOT NULL DEFAULT '{}',
+    expires_at   TIMESTAMP WITH TIME ZONE,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    created_at   TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX api_token_user_idx ON auth.api_token (user_id);
@@ -0,0 +1,10 @@
+    ------------------------- SESSIONS -------------------------
+-- Sessions are resolved from the database on every request, so they need to know where they come from and when they were last used.
+ALTER TABLE auth.session
+    ADD COLUMN user_agent VARCHAR(512),
+    ADD COLUMN last_seen_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP;
+
+UPDATE auth.session SET last_seen_at = created_at;
+
+CREATE INDEX session_user_idx ON auth.session (user_id);
+CREATE INDEX session_expires_idx ON auth.session (expires_at);
@@ -0,0 +1,32 @@
+    ------------------------- TWO-FACTOR AUTHENTICATION -------------------------
+CREATE TABLE auth.totp
+(
+    user_id        ulid PRIMARY KEY REFERENCES auth.user (id) ON DELETE CASCADE,
+    secret         BYTEA NOT NULL,
+    -- NULL until the user confirms the enrolment with a valid code
+    enabled_at     TIMESTAMP WITH TIME ZONE,
+    -- time step of the last accepted code, a code can't be used twice
+    last_used_step BIGINT,
+    created_at     TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE auth.recovery_code
+(
+    id         ulid PRIMARY KEY,
+    user_id    ulid REFERENCES auth.user (id) ON DELETE CASCADE NOT NULL,
+    -- SHA-256 of the normalized code, hex-encoded
+    code_hash  CHAR(64) NOT NULL,
+    used_at    TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX recovery_code_user_idx ON auth.recovery_code (user_id);
+
+    ------------------------- SETTINGS -------------------------
+-- Site-wide switches admins can change at runtime.
+CREATE TABLE setting
+(
+    key        VARCHAR(64) PRIMARY KEY,
+    value      TEXT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
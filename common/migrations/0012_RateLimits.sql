@@ -0,0 +1,22 @@
+    ------------------------- RATE LIMITS -------------------------
+-- Token buckets used when RATE_LIMIT_STORE=postgres, losing them on a crash is harmless.
+CREATE UNLOGGED TABLE rate_limit
+(
+    key        VARCHAR(256) PRIMARY KEY,
+    tokens     DOUBLE PRECISION NOT NULL,
+    -- whether the last request took a token
+    allowed    BOOLEAN NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX rate_limit_updated_at_idx ON rate_limit (updated_at);
+
+    ------------------------- LOGIN LOCKOUTS -------------------------
+CREATE TABLE auth.lockout
+(
+    user_id         ulid PRIMARY KEY REFERENCES auth.user (id) ON DELETE CASCADE,
+    -- failed logins since the last successful one
+    failures        INTEGER NOT NULL DEFAULT 0,
+    locked_until    TIMESTAMP WITH TIME ZONE,
+    last_failure_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
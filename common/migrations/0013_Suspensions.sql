@@ -0,0 +1,16 @@
+    ------------------------- SUSPENSIONS -------------------------
+-- Suspended users can log in and read, but can't post until the suspension ends or is lifted.
+CREATE TABLE auth.suspension
+(
+    id         ulid PRIMARY KEY,
+    user_id    ulid REFERENCES auth.user (id) ON DELETE CASCADE NOT NULL,
+    reason     TEXT NOT NULL,
+    -- admin who suspended the user
+    issued_by  ulid REFERENCES auth.user (id) ON DELETE SET NULL,
+    -- NULL for a permanent ban
+    expires_at TIMESTAMP WITH TIME ZONE,
+    lifted_at  TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX suspension_user_idx ON auth.suspension (user_id);
@@ -0,0 +1,99 @@
+CREATE SCHEMA IF NOT EXISTS moderation;
+
+CREATE TYPE moderation.report_reason AS ENUM (
+    'Spam',
+    'Defamation',
+    'Harassment',
+    'Illegal',
+    'Other'
+);
+
+CREATE TYPE moderation.report_status AS ENUM (
+    'Open',
+    'Actioned',
+    'Dismissed'
+);
+
+    ------------------------- REPORTS -------------------------
+CREATE TABLE moderation.report
+(
+    id           ulid PRIMARY KEY,
+    -- `comment`, `review` or `subject`
+    target_kind  VARCHAR(50) NOT NULL,
+    -- subject kind the target belongs to, e.g. `website`
+    subject_type VARCHAR(50) NOT NULL,
+    subject_id   ulid NOT NULL,
+    -- same as `subject_id` for reports of the subject itself
+    target_id    ulid NOT NULL,
+    reporter_id  ulid REFERENCES auth.user (id) ON DELETE SET NULL,
+    reason       moderation.report_reason NOT NULL,
+    details      TEXT,
+    status       moderation.report_status NOT NULL DEFAULT 'Open',
+    resolved_by  ulid REFERENCES auth.user (id) ON DELETE SET NULL,
+    -- action the moderator took
+    resolution   VARCHAR(50),
+    resolved_at  TIMESTAMP WITH TIME ZONE,
+    created_at   TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX report_status_idx ON moderation.report (status, created_at);
+-- a user can have only one open report per target
+CREATE UNIQUE INDEX report_open_idx ON moderation.report (target_kind, subject_type, target_id, reporter_id) WHERE status = 'Open';
+
+    ------------------------- SOFT DELETION -------------------------
+-- Removed comments stay in their thread as placeholders so replies keep their context.
+ALTER TABLE comment.website
+    ADD COLUMN removed_at           TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN removed_by_moderator BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE review.website
+    ADD COLUMN hidden_at TIMESTAMP WITH TIME ZONE;
+
+CREATE OR REPLACE FUNCTION create_comment_table(
+    table_name TEXT
+)
+RETURNS void AS $$
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE comment.%I (
+            id                   ulid PRIMARY KEY,
+            parent_id            ulid REFERENCES subject.%I(id),
+            reply_to             ulid REFERENCES comment.%I(id) ON DELETE CASCADE,
+            user_id              ulid REFERENCES auth.user(id) NOT NULL,
+            content              TEXT NOT NULL,
+            removed_at           TIMESTAMP WITH TIME ZONE,
+            removed_by_moderator BOOLEAN NOT NULL DEFAULT false,
+            created_at           TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            updated_at           TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+        )',
+        table_name,
+        table_name,
+        table_name
+    );
+    EXECUTE format('CREATE INDEX %I ON comment.%I (reply_to)', table_name || '_comment_reply_to_idx', table_name);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION create_review_table(
+    table_name TEXT
+)
+RETURNS void AS $$
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE review.%I (
+            id              ulid PRIMARY KEY,
+            parent_id       ulid REFERENCES subject.%I(id) ON DELETE CASCADE NOT NULL,
+            user_id         ulid REFERENCES auth.user(id) NOT NULL,
+            title           VARCHAR(255) NOT NULL,
+            content         TEXT NOT NULL,
+            rating          rating NOT NULL,
+            hidden_at       TIMESTAMP WITH TIME ZONE,
+            created_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            updated_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (parent_id, user_id)
+        )',
+        table_name,
+        table_name
+    );
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,39 @@
+CREATE SCHEMA IF NOT EXISTS audit;
+
+    ------------------------- AUDIT LOG -------------------------
+-- Append-only record of privileged and destructive actions. The actor has no foreign key
+-- so deleting a user neither rewrites nor removes their events.
+CREATE TABLE audit.event
+(
+    id          ulid PRIMARY KEY,
+    -- NULL for actions without a logged-in user, e.g. seeding
+    actor_id    ulid,
+    action      VARCHAR(50) NOT NULL,
+    -- table of the changed row, e.g. `subject.website`
+    target_type VARCHAR(50) NOT NULL,
+    target_id   ulid NOT NULL,
+    -- row before and after the change, NULL when it did not exist
+    before      JSONB,
+    after       JSONB,
+    ip          VARCHAR(45),
+    created_at  TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX audit_event_created_idx ON audit.event (created_at);
+CREATE INDEX audit_event_actor_idx ON audit.event (actor_id);
+CREATE INDEX audit_event_target_idx ON audit.event (target_type, target_id);
+
+CREATE OR REPLACE FUNCTION audit.reject_change()
+RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit events are append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER event_append_only
+    BEFORE UPDATE OR DELETE ON audit.event
+    FOR EACH ROW EXECUTE FUNCTION audit.reject_change();
+
+CREATE TRIGGER event_no_truncate
+    BEFORE TRUNCATE ON audit.event
+    FOR EACH STATEMENT EXECUTE FUNCTION audit.reject_change();
@@ -0,0 +1,22 @@
+CREATE TYPE subject.verification_method AS ENUM (
+    'Dns',
+    'WellKnown'
+);
+
+    ------------------------- WEBSITE VERIFICATION -------------------------
+-- Challenge proving that a user controls the domain of a website. Verified rows record the ownership.
+CREATE TABLE subject.website_verification
+(
+    id          ulid PRIMARY KEY,
+    website_id  ulid REFERENCES subject.website (id) ON DELETE CASCADE NOT NULL,
+    -- owner of the website's organization when the challenge was issued
+    user_id     ulid REFERENCES auth.user (id) ON DELETE CASCADE NOT NULL,
+    -- published in a DNS TXT record or a well-known file
+    token       VARCHAR(64) NOT NULL,
+    -- how the token was found, NULL until verified
+    method      subject.verification_method,
+    verified_at TIMESTAMP WITH TIME ZONE,
+    checked_at  TIMESTAMP WITH TIME ZONE,
+    created_at  TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (website_id, user_id)
+);
@@ -0,0 +1,90 @@
+CREATE TYPE subject.claim_status AS ENUM (
+    'Pending',
+    'Approved',
+    'Rejected'
+);
+
+    ------------------------- ORGANIZATION CLAIMS -------------------------
+-- Request of a user to become the owner of an organization, decided by an admin.
+CREATE TABLE subject.organization_claim
+(
+    id              ulid PRIMARY KEY,
+    organization_id ulid REFERENCES subject.organization (id) ON DELETE CASCADE NOT NULL,
+    user_id         ulid REFERENCES auth.user (id) ON DELETE CASCADE NOT NULL,
+    -- why the user should own the organization, e.g. their role and a company e-mail
+    evidence        TEXT NOT NULL,
+    status          subject.claim_status NOT NULL DEFAULT 'Pending',
+    reviewed_by     ulid REFERENCES auth.user (id) ON DELETE SET NULL,
+    -- shown to the claimant
+    review_note     TEXT,
+    reviewed_at     TIMESTAMP WITH TIME ZONE,
+    created_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX organization_claim_status_idx ON subject.organization_claim (status, created_at);
+-- a user can have only one pending claim per organization
+CREATE UNIQUE INDEX organization_claim_pending_idx ON subject.organization_claim (organization_id, user_id) WHERE status = 'Pending';
+
+    ------------------------- OFFICIAL RESPONSES -------------------------
+-- Comments posted by the verified owner in the name of the organization.
+ALTER TABLE comment.website
+    ADD COLUMN is_official BOOLEAN NOT NULL DEFAULT false;
+
+-- The owner's public answer to a review, one per review.
+ALTER TABLE review.website
+    ADD COLUMN response     TEXT,
+    ADD COLUMN responded_by ulid REFERENCES auth.user (id) ON DELETE SET NULL,
+    ADD COLUMN responded_at TIMESTAMP WITH TIME ZONE;
+
+CREATE OR REPLACE FUNCTION create_comment_table(
+    table_name TEXT
+)
+RETURNS void AS $$
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE comment.%I (
+            id                   ulid PRIMARY KEY,
+            parent_id            ulid REFERENCES subject.%I(id),
+            reply_to             ulid REFERENCES comment.%I(id) ON DELETE CASCADE,
+            user_id              ulid REFERENCES auth.user(id) NOT NULL,
+            content              TEXT NOT NULL,
+            is_official          BOOLEAN NOT NULL DEFAULT false,
+            removed_at           TIMESTAMP WITH TIME ZONE,
+            removed_by_moderator BOOLEAN NOT NULL DEFAULT false,
+            created_at           TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            updated_at           TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+        )',
+        table_name,
+        table_name,
+        table_name
+    );
+    EXECUTE format('CREATE INDEX %I ON comment.%I (reply_to)', table_name || '_comment_reply_to_idx', table_name);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION create_review_table(
+    table_name TEXT
+)
+RETURNS void AS $$
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE review.%I (
+            id              ulid PRIMARY KEY,
+            parent_id       ulid REFERENCES subject.%I(id) ON DELETE CASCADE NOT NULL,
+            user_id         ulid REFERENCES auth.user(id) NOT NULL,
+            title           VARCHAR(255) NOT NULL,
+            content         TEXT NOT NULL,
+            rating          rating NOT NULL,
+            hidden_at       TIMESTAMP WITH TIME ZONE,
+            response        TEXT,
+            responded_by    ulid REFERENCES auth.user(id) ON DELETE SET NULL,
+            responded_at    TIMESTAMP WITH TIME ZONE,
+            created_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            updated_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (parent_id, user_id)
+        )',
+        table_name,
+        table_name
+    );
+END;
+$$ LANGUAGE plpgsql;
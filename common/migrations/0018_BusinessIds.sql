@@ -0,0 +1,7 @@
+    ------------------------- BUSINESS IDENTIFIERS -------------------------
+-- IČO and DIČ are validated by the application, the checks only keep the stored format.
+ALTER TABLE subject.organization
+    ADD COLUMN ico     VARCHAR(8) UNIQUE CHECK (ico ~ '^[0-9]{8}$'),
+    ADD COLUMN dic     VARCHAR(14) UNIQUE CHECK (dic ~ '^CZ[0-9]{8,10}$'),
+    -- registered office, as written in the business registry
+    ADD COLUMN address TEXT;
@@ -0,0 +1,67 @@
+-- The enum only knew six Czech forms. Forms are now rows keyed by country and the code of the form
+-- in the country's statistical code list, so new ones can be added without changing types.
+ALTER TYPE subject.legal_form RENAME TO legal_form_enum;
+
+    ------------------------- LEGAL FORM -------------------------
+-- Names are localized in the Fluent bundles under `legal-form-<country>-<code>`.
+CREATE TABLE subject.legal_form
+(
+    -- ISO 3166-1 alpha-2 code of the country whose law defines the form
+    country      VARCHAR(2) NOT NULL CHECK (country ~ '^[A-Z]{2}$'),
+    -- code of the form in the country's code list, e.g. ČSÚ for Czechia, the usual suffix elsewhere
+    code         VARCHAR(16) NOT NULL,
+    -- suffix the form adds to names, e.g. s.r.o.
+    abbreviation VARCHAR(32) NOT NULL,
+    PRIMARY KEY (country, code)
+);
+
+INSERT INTO subject.legal_form (country, code, abbreviation) VALUES
+    ('CZ', '101', 'OSVČ'),
+    ('CZ', '111', 'v.o.s.'),
+    ('CZ', '112', 's.r.o.'),
+    ('CZ', '113', 'k.s.'),
+    ('CZ', '117', 'nadace'),
+    ('CZ', '118', 'nadační fond'),
+    ('CZ', '121', 'a.s.'),
+    ('CZ', '141', 'o.p.s.'),
+    ('CZ', '161', 'z.ú.'),
+    ('CZ', '205', 'družstvo'),
+    ('CZ', '301', 's.p.'),
+    ('CZ', '706', 'z.s.'),
+    ('SK', '101', 'živnostník'),
+    ('SK', '111', 'v.o.s.'),
+    ('SK', '112', 's.r.o.'),
+    ('SK', '113', 'k.s.'),
+    ('SK', '121', 'a.s.'),
+    ('SK', '205', 'družstvo'),
+    ('SK', '701', 'o.z.'),
+    ('AT', 'GMBH', 'GmbH'),
+    ('DE', 'AG', 'AG'),
+    ('DE', 'GMBH', 'GmbH'),
+    ('GB', 'LTD', 'Ltd'),
+    ('GB', 'PLC', 'plc'),
+    ('US', 'INC', 'Inc.'),
+    ('US', 'LLC', 'LLC');
+
+ALTER TABLE subject.organization
+    ADD COLUMN form_country VARCHAR(2),
+    ADD COLUMN form_code    VARCHAR(16);
+
+UPDATE subject.organization
+SET form_country = 'CZ',
+    form_code    = CASE form
+        WHEN 'Sro' THEN '112'
+        WHEN 'As' THEN '121'
+        WHEN 'Vos' THEN '111'
+        WHEN 'Spolek' THEN '706'
+        WHEN 'Nadace' THEN '117'
+        WHEN 'Druzstvo' THEN '205'
+    END;
+
+ALTER TABLE subject.organization
+    ALTER COLUMN form_country SET NOT NULL,
+    ALTER COLUMN form_code SET NOT NULL,
+    ADD FOREIGN KEY (form_country, form_code) REFERENCES subject.legal_form (country, code),
+    DROP COLUMN form;
+
+DROP TYPE subject.legal_form_enum;
@@ -0,0 +1,58 @@
+-- Reviewable kinds besides websites. Each has the columns shared by all subjects (see
+-- `common::database::reviewable::Reviewable`) followed by its own, and its comment and review tables.
+
+    ------------------------- MOBILE APP -------------------------
+CREATE TYPE subject.app_platform AS ENUM ('Android', 'Ios');
+
+CREATE TABLE subject.app
+(
+    id              ulid PRIMARY KEY,
+    organization_id ulid REFERENCES subject.organization (id) ON DELETE SET NULL,
+    karma           karma DEFAULT ROW(0, 0, 0, 0)::karma,
+    visits          BIGINT NOT NULL DEFAULT 0,
+    name            VARCHAR(255) NOT NULL,
+    description     VARCHAR(512),
+    platform        subject.app_platform NOT NULL,
+    -- listing in the platform's store
+    store_url       VARCHAR(512),
+    created_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+SELECT create_comment_table('app');
+SELECT create_review_table('app');
+
+    ------------------------- E-SHOP -------------------------
+CREATE TABLE subject.eshop
+(
+    id              ulid PRIMARY KEY,
+    organization_id ulid REFERENCES subject.organization (id) ON DELETE SET NULL,
+    karma           karma DEFAULT ROW(0, 0, 0, 0)::karma,
+    visits          BIGINT NOT NULL DEFAULT 0,
+    name            VARCHAR(255) NOT NULL,
+    description     VARCHAR(512),
+    domain_name     VARCHAR(255) UNIQUE NOT NULL,
+    created_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+SELECT create_comment_table('eshop');
+SELECT create_review_table('eshop');
+
+    ------------------------- PHYSICAL SERVICE -------------------------
+-- A shop, restaurant, workshop or any other place customers visit.
+CREATE TABLE subject.service
+(
+    id              ulid PRIMARY KEY,
+    organization_id ulid REFERENCES subject.organization (id) ON DELETE SET NULL,
+    karma           karma DEFAULT ROW(0, 0, 0, 0)::karma,
+    visits          BIGINT NOT NULL DEFAULT 0,
+    name            VARCHAR(255) NOT NULL,
+    description     VARCHAR(512),
+    address         TEXT NOT NULL,
+    created_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at      TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+SELECT create_comment_table('service');
+SELECT create_review_table('service');
@@ -0,0 +1,50 @@
+-- Deleting a comment used to cascade to the replies of other users. Comments are soft-deleted now,
+-- and should a row ever be removed, its replies are kept as top-level comments instead.
+DO $$
+DECLARE
+    fk RECORD;
+BEGIN
+    FOR fk IN
+        SELECT c.conrelid::regclass AS table_name, c.conname
+        FROM pg_constraint c
+        JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = ANY (c.conkey)
+        WHERE c.contype = 'f'
+          AND c.connamespace = 'comment'::regnamespace
+          AND a.attname = 'reply_to'
+    LOOP
+        EXECUTE format('ALTER TABLE %s DROP CONSTRAINT %I', fk.table_name, fk.conname);
+        EXECUTE format(
+            'ALTER TABLE %s ADD CONSTRAINT %I FOREIGN KEY (reply_to) REFERENCES %s (id) ON DELETE SET NULL',
+            fk.table_name,
+            fk.conname,
+            fk.table_name
+        );
+    END LOOP;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION create_comment_table(
+    table_name TEXT
+)
+RETURNS void AS $$
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE comment.%I (
+            id                   ulid PRIMARY KEY,
+            parent_id            ulid REFERENCES subject.%I(id),
+            reply_to             ulid REFERENCES comment.%I(id) ON DELETE SET NULL,
+            user_id              ulid REFERENCES auth.user(id) NOT NULL,
+            content              TEXT NOT NULL,
+            is_official          BOOLEAN NOT NULL DEFAULT false,
+            removed_at           TIMESTAMP WITH TIME ZONE,
+            removed_by_moderator BOOLEAN NOT NULL DEFAULT false,
+            created_at           TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            updated_at           TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+        )',
+        table_name,
+        table_name,
+        table_name
+    );
+    EXECUTE format('CREATE INDEX %I ON comment.%I (reply_to)', table_name || '_comment_reply_to_idx', table_name);
+END;
+$$ LANGUAGE plpgsql;
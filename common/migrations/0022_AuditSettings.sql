@@ -0,0 +1,2 @@
+-- Settings are keyed by name, so audit events can no longer assume every target has a ULID.
+ALTER TABLE audit.event ALTER COLUMN target_id TYPE VARCHAR(64);
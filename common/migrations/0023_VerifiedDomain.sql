@@ -0,0 +1,4 @@
+-- A verification only proves control of the domain it was checked on. Rows verified before the domain
+-- was recorded can't be tied to one, so their owners have to verify again.
+ALTER TABLE subject.website_verification ADD COLUMN domain_name VARCHAR(255);
+UPDATE subject.website_verification SET method = NULL, verified_at = NULL WHERE verified_at IS NOT NULL;
@@ -1,8 +1,30 @@
+use crate::database::reviewable::karma::KarmaWeights;
 use crate::database::DbCredentials;
+use crate::mail::MailArgs;
+use crate::rate_limit::RateLimitArgs;
+use crate::registry::RegistryArgs;
 use clap::Parser;
+use std::net::IpAddr;
 
 #[derive(Parser)]
 pub struct CliArgs {
     #[clap(flatten)]
     pub db: DbCredentials,
+    #[clap(flatten)]
+    pub karma: KarmaWeights,
+    /// Reverse proxies whose `Forwarded`/`X-Forwarded-For` headers are trusted, comma separated.
+    #[clap(long, env = "TRUSTED_PROXIES", value_delimiter = ',')]
+    pub trusted_proxies: Vec<IpAddr>,
+    #[clap(flatten)]
+    pub mail: MailArgs,
+    #[clap(flatten)]
+    pub rate_limit: RateLimitArgs,
+    #[clap(flatten)]
+    pub registry: RegistryArgs,
+    /// Key signing e-mail verification and similar links. Random on every start if unset.
+    #[clap(long, env = "SECRET_KEY")]
+    pub secret_key: Option<String>,
+    /// Address the site is reachable at, used in links sent by e-mail.
+    #[clap(long, env = "PUBLIC_URL", default_value = "http://127.0.0.1:3000")]
+    pub public_url: String,
 }
@@ -0,0 +1,199 @@
+use std::cmp::min;
+use std::net::IpAddr;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{Pool, Postgres, Row};
+use crate::database::Ulid;
+use crate::database::user::User;
+use crate::PodelError;
+
+/// User performing an audited action and the address the request came from.
+#[derive(Clone, Copy, Debug)]
+pub struct Actor<'a> {
+    pub user: &'a User,
+    pub ip: Option<IpAddr>,
+}
+
+impl<'a> Actor<'a> {
+    pub const fn new(user: &'a User, ip: Option<IpAddr>) -> Self {
+        Self { user, ip }
+    }
+}
+
+/// Narrows down the audit log. Empty fields match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditFilter {
+    /// Id or name of the acting user.
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+}
+
+/// Entry of the append-only audit log.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub id: Ulid,
+    pub actor_id: Option<Ulid>,
+    /// `None` for actions without a logged-in user or once the user is deleted.
+    pub actor: Option<User>,
+    /// What happened, e.g. `update`.
+    pub action: String,
+    /// Table of the changed row, e.g. `subject.website`.
+    pub target_type: String,
+    pub target_id: Ulid,
+    /// Row before the change, `None` if it was created.
+    pub before: Option<serde_json::Value>,
+    /// Row after the change, `None` if it was deleted.
+    pub after: Option<serde_json::Value>,
+    pub ip: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl sqlx::FromRow<'_, PgRow> for AuditEvent {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(AuditEvent {
+            id: row.try_get("id")?,
+            actor_id: row.try_get("actor_id")?,
+            actor: User::from_row(row).ok(),
+            action: row.try_get("action")?,
+            target_type: row.try_get("target_type")?,
+            target_id: row.try_get("target_id")?,
+            before: row.try_get("before")?,
+            after: row.try_get("after")?,
+            ip: row.try_get("ip")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl AuditEvent {
+    pub fn new(
+        actor: Option<&Actor<'_>>,
+        action: impl Into<String>,
+        target_type: impl Into<String>,
+        target_id: impl Into<Ulid>,
+    ) -> Self {
+        Self {
+            id: ulid::Ulid::new().into(),
+            actor_id: actor.map(|actor| actor.user.id.clone()),
+            actor: actor.map(|actor| actor.user.clone()),
+            action: action.into(),
+            target_type: target_type.into(),
+            target_id: target_id.into(),
+            before: None,
+            after: None,
+            ip: actor.and_then(|actor| actor.ip).map(|ip| ip.to_string()),
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn with_snapshots(self, before: Option<serde_json::Value>, after: Option<serde_json::Value>) -> Self {
+        Self { before, after, ..self }
+    }
+
+    /// Current state of a row as JSON, `None` if it does not exist. Password hashes are left out.
+    /// `table` must be a trusted name such as `subject.website`, it is not escaped.
+    pub async fn snapshot<'e, E>(executor: E, table: &str, id: &str) -> Result<Option<serde_json::Value>, PodelError>
+    where
+        E: sqlx::Executor<'e, Database = Postgres>,
+    {
+        Ok(
+            sqlx::query_scalar::<Postgres, serde_json::Value>(&format!(
+                "SELECT to_jsonb(t) - 'password_hash' FROM {table} t WHERE t.id = $1"
+            ))
+            .bind(id)
+            .fetch_optional(executor)
+            .await?
+        )
+    }
+
+    /// Appends the event. Run it in the transaction of the change so neither happens without the other.
+    pub async fn record<'e, E>(&self, executor: E) -> Result<(), PodelError>
+    where
+        E: sqlx::Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(r#"
+                INSERT INTO audit.event (
+                    id,
+                    actor_id,
+                    action,
+                    target_type,
+                    target_id,
+                    before,
+                    after,
+                    ip,
+                    created_at
+                )
+                VALUES (
+                    $1, $2, $3, $4, $5, $6, $7, $8, $9
+                )
+            "#)
+            .bind(&self.id)
+            .bind(&self.actor_id)
+            .bind(&self.action)
+            .bind(&self.target_type)
+            .bind(&self.target_id)
+            .bind(&self.before)
+            .bind(&self.after)
+            .bind(&self.ip)
+            .bind(self.created_at)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Newest events matching the filter.
+    pub async fn find_multiple(
+        pool: &Pool<Postgres>,
+        filter: &AuditFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Self>, PodelError> {
+        let non_empty = |value: &Option<String>| value.as_deref().map(str::trim).filter(|value| !value.is_empty()).map(str::to_string);
+
+        Ok(
+            sqlx::query_as::<Postgres, AuditEvent>(r#"
+                SELECT
+                    e.id,
+                    e.actor_id,
+                    e.action,
+                    e.target_type,
+                    e.target_id,
+                    e.before,
+                    e.after,
+                    e.ip,
+                    e.created_at,
+                    u.id as user_id,
+                    u.email as user_email,
+                    u.password_hash as user_password_hash,
+                    u.language as user_language,
+                    u.name as user_name,
+                    u.is_admin as user_is_admin,
+                    u.is_active as user_is_active,
+                    u.is_verified as user_is_verified,
+                    u.last_login as user_last_login,
+                    u.created_at as user_created_at,
+                    u.updated_at as user_updated_at
+                FROM audit.event e
+                LEFT JOIN auth.user u ON e.actor_id = u.id
+                WHERE ($1::text IS NULL OR e.actor_id = $1 OR u.name = $1)
+                  AND ($2::text IS NULL OR e.action = $2)
+                  AND ($3::text IS NULL OR e.target_type = $3)
+                  AND ($4::text IS NULL OR e.target_id = $4)
+                ORDER BY e.created_at DESC
+                LIMIT $5
+                OFFSET $6
+            "#)
+            .bind(non_empty(&filter.actor))
+            .bind(non_empty(&filter.action))
+            .bind(non_empty(&filter.target_type))
+            .bind(non_empty(&filter.target_id))
+            .bind(min(limit, 50))
+            .bind(offset)
+            .fetch_all(pool)
+            .await?
+        )
+    }
+}
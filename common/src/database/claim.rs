@@ -0,0 +1,259 @@
+use std::cmp::min;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{Pool, Postgres, Row};
+use crate::database::Ulid;
+use crate::database::audit::{Actor, AuditEvent};
+use crate::database::user::User;
+use crate::PodelError;
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "subject.claim_status")]
+pub enum ClaimStatus {
+    /// Waiting for an admin
+    Pending,
+    /// The claimant became the owner of the organization
+    Approved,
+    Rejected,
+}
+
+impl ClaimStatus {
+    /// Key of the localized name in the Fluent bundles.
+    pub fn fluent_key(&self) -> &'static str {
+        match self {
+            ClaimStatus::Pending => "claim-status-pending",
+            ClaimStatus::Approved => "claim-status-approved",
+            ClaimStatus::Rejected => "claim-status-rejected",
+        }
+    }
+}
+
+/// Request of a user to become the owner of an organization.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrganizationClaim {
+    pub id: Ulid,
+    pub organization_id: Ulid,
+    pub organization_name: String,
+    pub user: User,
+    /// Why the claimant should own the organization.
+    pub evidence: String,
+    pub status: ClaimStatus,
+    pub reviewed_by: Option<Ulid>,
+    /// Admin's explanation, shown to the claimant.
+    pub review_note: Option<String>,
+    pub reviewed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl sqlx::FromRow<'_, PgRow> for OrganizationClaim {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let user = User::from_row(row).map_err(|_| sqlx::Error::RowNotFound)?;
+
+        Ok(OrganizationClaim {
+            id: row.try_get("id")?,
+            organization_id: row.try_get("organization_id")?,
+            organization_name: row.try_get("organization_name")?,
+            user,
+            evidence: row.try_get("evidence")?,
+            status: row.try_get("status")?,
+            reviewed_by: row.try_get("reviewed_by")?,
+            review_note: row.try_get("review_note")?,
+            reviewed_at: row.try_get("reviewed_at")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl OrganizationClaim {
+    fn get_query() -> &'static str {
+        r#"
+            SELECT
+                c.id,
+                c.organization_id,
+                o.name as organization_name,
+                c.evidence,
+                c.status,
+                c.reviewed_by,
+                c.review_note,
+                c.reviewed_at,
+                c.created_at,
+                u.id as user_id,
+                u.email as user_email,
+                u.password_hash as user_password_hash,
+                u.language as user_language,
+                u.name as user_name,
+                u.is_admin as user_is_admin,
+                u.is_active as user_is_active,
+                u.is_verified as user_is_verified,
+                u.last_login as user_last_login,
+                u.created_at as user_created_at,
+                u.updated_at as user_updated_at
+            FROM subject.organization_claim c
+            JOIN subject.organization o ON c.organization_id = o.id
+            JOIN auth.user u ON c.user_id = u.id
+        "#
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.status == ClaimStatus::Pending
+    }
+
+    /// Files a claim. Fails if the organization already has an owner or the user's earlier claim is still pending.
+    pub async fn create(
+        pool: &Pool<Postgres>,
+        organization_id: impl Into<String>,
+        user: &User,
+        evidence: impl Into<String>,
+    ) -> Result<(), PodelError> {
+        let result = sqlx::query(r#"
+                INSERT INTO subject.organization_claim (id, organization_id, user_id, evidence)
+                SELECT $1, o.id, $3, $4
+                FROM subject.organization o
+                WHERE o.id = $2 AND o.user_id IS NULL
+                ON CONFLICT (organization_id, user_id) WHERE status = 'Pending' DO NOTHING
+            "#)
+            .bind(ulid::Ulid::new().to_string())
+            .bind(organization_id.into())
+            .bind(&user.id)
+            .bind(evidence.into())
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            Err(PodelError::UserError("The organization can't be claimed.".into()))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub async fn find(pool: &Pool<Postgres>, id: impl Into<String>) -> Result<Self, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, OrganizationClaim>(&format!("{} WHERE c.id = $1", Self::get_query()))
+                .bind(id.into())
+                .fetch_one(pool)
+                .await?
+        )
+    }
+
+    /// Newest claim of the user for the organization, if there is one.
+    pub async fn find_latest(
+        pool: &Pool<Postgres>,
+        organization_id: impl Into<String>,
+        user_id: impl Into<String>,
+    ) -> Result<Option<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, OrganizationClaim>(&format!(r#"
+                    {}
+                    WHERE c.organization_id = $1 AND c.user_id = $2
+                    ORDER BY c.created_at DESC
+                    LIMIT 1
+                "#, Self::get_query()))
+                .bind(organization_id.into())
+                .bind(user_id.into())
+                .fetch_optional(pool)
+                .await?
+        )
+    }
+
+    /// Claims waiting for an admin, oldest first.
+    pub async fn find_pending(pool: &Pool<Postgres>, limit: i64, offset: i64) -> Result<Vec<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, OrganizationClaim>(&format!(r#"
+                    {}
+                    WHERE c.status = 'Pending'
+                    ORDER BY c.created_at ASC
+                    LIMIT $1
+                    OFFSET $2
+                "#, Self::get_query()))
+                .bind(min(limit, 50))
+                .bind(offset)
+                .fetch_all(pool)
+                .await?
+        )
+    }
+
+    /// Makes the claimant the owner of the organization and rejects other pending claims of it.
+    /// Fails with `UserError` if the organization got an owner in the meantime.
+    pub async fn approve(&self, pool: &Pool<Postgres>, actor: &Actor<'_>, note: Option<String>) -> Result<(), PodelError> {
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, "subject.organization", &self.organization_id).await?;
+
+        let result = sqlx::query(r#"
+                UPDATE subject.organization
+                SET user_id = $2, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND user_id IS NULL
+            "#)
+            .bind(&self.organization_id)
+            .bind(&self.user.id)
+            .execute(&mut *transaction)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(PodelError::UserError("The organization already has an owner.".into()));
+        }
+
+        Self::decide(&mut transaction, &self.id, actor, ClaimStatus::Approved, note.as_deref()).await?;
+
+        sqlx::query(r#"
+                UPDATE subject.organization_claim
+                SET status = 'Rejected', reviewed_by = $2, reviewed_at = CURRENT_TIMESTAMP
+                WHERE organization_id = $1 AND status = 'Pending'
+            "#)
+            .bind(&self.organization_id)
+            .bind(&actor.user.id)
+            .execute(&mut *transaction)
+            .await?;
+
+        let after = AuditEvent::snapshot(&mut *transaction, "subject.organization", &self.organization_id).await?;
+        AuditEvent::new(Some(actor), "claim", "subject.organization", &self.organization_id)
+            .with_snapshots(before, after)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub async fn reject(&self, pool: &Pool<Postgres>, actor: &Actor<'_>, note: Option<String>) -> Result<(), PodelError> {
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, "subject.organization_claim", &self.id).await?;
+
+        Self::decide(&mut transaction, &self.id, actor, ClaimStatus::Rejected, note.as_deref()).await?;
+
+        let after = AuditEvent::snapshot(&mut *transaction, "subject.organization_claim", &self.id).await?;
+        AuditEvent::new(Some(actor), "reject", "subject.organization_claim", &self.id)
+            .with_snapshots(before, after)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Closes a pending claim. Returns `Empty` if it was decided already.
+    async fn decide(
+        transaction: &mut sqlx::Transaction<'_, Postgres>,
+        id: &str,
+        actor: &Actor<'_>,
+        status: ClaimStatus,
+        note: Option<&str>,
+    ) -> Result<(), PodelError> {
+        let result = sqlx::query(r#"
+                UPDATE subject.organization_claim
+                SET status = $2, reviewed_by = $3, review_note = $4, reviewed_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND status = 'Pending'
+            "#)
+            .bind(id)
+            .bind(status)
+            .bind(&actor.user.id)
+            .bind(note)
+            .execute(&mut **transaction)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            Err(PodelError::Empty())
+        } else {
+            Ok(())
+        }
+    }
+}
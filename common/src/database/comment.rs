@@ -2,56 +2,119 @@ use std::cmp::min;
 use std::ops::Deref;
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
-use sqlx::{Pool, Postgres};
+use sqlx::postgres::PgRow;
+use sqlx::{Pool, Postgres, Row};
 use crate::database::Ulid;
+use crate::database::audit::{Actor, AuditEvent};
 use crate::database::user::User;
 use crate::PodelError;
 
 pub trait CommentParent: Commentable + Send + Sync + Unpin + sqlx::Type<Postgres> + for<'r> sqlx::Decode<'r, Postgres> + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> {}
 impl<T> CommentParent for T where T: Commentable + Send + Sync + Unpin + sqlx::Type<Postgres> + for<'r> sqlx::Decode<'r, Postgres> + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> {}
 
-#[derive(sqlx::FromRow, Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Comment {
     pub id: Ulid,
     pub parent_type: String,
     pub parent_id: Ulid,
+    /// Comment this one replies to, `None` for top-level comments.
+    pub reply_to: Option<Ulid>,
+    /// Number of direct replies.
+    pub reply_count: i64,
+    pub upvotes: i64,
+    pub downvotes: i64,
     pub user: User,
     pub content: String,
+    /// Posted by the verified owner in the name of the subject's organization.
+    pub is_official: bool,
+    /// Set once the comment is deleted or hidden. It then shows as a placeholder.
+    pub removed_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// removed by a moderator rather than by its author
+    pub removed_by_moderator: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+impl sqlx::FromRow<'_, PgRow> for Comment {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let user = User::from_row(row).map_err(|_| sqlx::Error::RowNotFound)?;
+
+        Ok(Comment {
+            id: row.try_get("id")?,
+            parent_type: row.try_get("parent_type")?,
+            parent_id: row.try_get("parent_id")?,
+            reply_to: row.try_get("reply_to")?,
+            reply_count: row.try_get("reply_count")?,
+            upvotes: row.try_get("upvotes")?,
+            downvotes: row.try_get("downvotes")?,
+            user,
+            content: row.try_get("content")?,
+            is_official: row.try_get("is_official")?,
+            removed_at: row.try_get("removed_at")?,
+            removed_by_moderator: row.try_get("removed_by_moderator")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
 impl Comment {
     pub fn new(parent_type: impl Into<String>, parent_id: impl Into<Ulid>, content: impl Into<String>, user: Arc<User>) -> Self {
         Self {
             id: ulid::Ulid::new().into(),
             parent_type: parent_type.into(),
             parent_id: parent_id.into(),
+            reply_to: None,
+            reply_count: 0,
+            upvotes: 0,
+            downvotes: 0,
             user: user.deref().clone(),
             content: content.into(),
+            is_official: false,
+            removed_at: None,
+            removed_by_moderator: false,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         }
     }
 
+    pub fn with_reply_to(self, comment_id: impl Into<Ulid>) -> Self {
+        Self {
+            reply_to: Some(comment_id.into()),
+            ..self
+        }
+    }
+
+    /// Marks the comment as an official response. Callers check that the author owns the subject.
+    pub fn as_official(self) -> Self {
+        Self {
+            is_official: true,
+            ..self
+        }
+    }
+
     pub async fn save(&self, pool: &Pool<Postgres>) -> Result<(), PodelError> {
         sqlx::query(&format!(r#"
                 INSERT INTO comment.{} (
                     id,
                     parent_id,
+                    reply_to,
                     user_id,
                     content,
+                    is_official,
                     created_at,
                     updated_at
                 )
                 VALUES (
-                    $1, $2, $3, $4, $5, $6
+                    $1, $2, $3, $4, $5, $6, $7, $8
                 )
             "#, self.parent_type))
             .bind(&self.id)
             .bind(&self.parent_id)
+            .bind(&self.reply_to)
             .bind(&self.user.id)
             .bind(&self.content)
+            .bind(self.is_official)
             .bind(&self.created_at)
             .bind(&self.updated_at)
             .execute(pool)
@@ -60,25 +123,420 @@ impl Comment {
         Ok(())
     }
 
+    fn table(&self) -> String {
+        format!("comment.{}", self.parent_type)
+    }
+
+    /// Records a change of the comment made by `actor` within the same transaction.
+    async fn audit(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, Postgres>,
+        actor: &Actor<'_>,
+        action: &str,
+        before: Option<serde_json::Value>,
+    ) -> Result<(), PodelError> {
+        let after = AuditEvent::snapshot(&mut **transaction, &self.table(), &self.id).await?;
+        AuditEvent::new(Some(actor), action, self.table(), &self.id)
+            .with_snapshots(before, after)
+            .record(&mut **transaction)
+            .await
+    }
+
+    /// Replaces the content, keeping the previous version in the edit history.
+    pub async fn update(&mut self, pool: &Pool<Postgres>, actor: &Actor<'_>, content: impl Into<String>) -> Result<(), PodelError> {
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, &self.table(), &self.id).await?;
+
+        CommentEdit::new(self, actor.user, false).save(&mut *transaction).await?;
+
+        self.content = content.into();
+        self.updated_at = chrono::Utc::now();
+
+        let result = sqlx::query(&format!(r#"
+                UPDATE comment.{}
+                SET content = $2, updated_at = $3
+                WHERE id = $1
+            "#, self.parent_type))
+            .bind(&self.id)
+            .bind(&self.content)
+            .bind(self.updated_at)
+            .execute(&mut *transaction)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(PodelError::DatabaseError("Failed to update comment".to_string()));
+        }
+
+        self.audit(&mut transaction, actor, "update", before).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Replaces the comment with a placeholder, keeping its replies. The last content is kept in the edit history.
+    pub async fn delete(&self, pool: &Pool<Postgres>, actor: &Actor<'_>) -> Result<(), PodelError> {
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, &self.table(), &self.id).await?;
+
+        CommentEdit::new(self, actor.user, true).save(&mut *transaction).await?;
+
+        sqlx::query(&format!(r#"
+                UPDATE comment.{}
+                SET content = '', removed_at = COALESCE(removed_at, CURRENT_TIMESTAMP), removed_by_moderator = $2
+                WHERE id = $1
+            "#, self.parent_type))
+            .bind(&self.id)
+            .bind(actor.user.id != self.user.id)
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query("DELETE FROM comment.vote WHERE comment_type = $1 AND comment_id = $2")
+            .bind(&self.parent_type)
+            .bind(&self.id)
+            .execute(&mut *transaction)
+            .await?;
+
+        self.audit(&mut transaction, actor, "delete", before).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Hides the comment behind a moderator placeholder. Unlike [`Comment::delete`], the content
+    /// stays in place and [`Comment::restore`] brings it back.
+    pub async fn hide(&self, pool: &Pool<Postgres>, actor: &Actor<'_>) -> Result<(), PodelError> {
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, &self.table(), &self.id).await?;
+
+        let result = sqlx::query(&format!(r#"
+                UPDATE comment.{}
+                SET removed_at = CURRENT_TIMESTAMP, removed_by_moderator = true
+                WHERE id = $1 AND removed_at IS NULL
+            "#, self.parent_type))
+            .bind(&self.id)
+            .execute(&mut *transaction)
+            .await?;
+
+        if result.rows_affected() > 0 {
+            self.audit(&mut transaction, actor, "hide", before).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Shows a hidden comment again. Deleted comments have no content left to show and stay removed.
+    pub async fn restore(&self, pool: &Pool<Postgres>, actor: &Actor<'_>) -> Result<(), PodelError> {
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, &self.table(), &self.id).await?;
+
+        let result = sqlx::query(&format!(r#"
+                UPDATE comment.{}
+                SET removed_at = NULL, removed_by_moderator = false
+                WHERE id = $1 AND removed_at IS NOT NULL AND content <> ''
+            "#, self.parent_type))
+            .bind(&self.id)
+            .execute(&mut *transaction)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(PodelError::Empty());
+        }
+
+        self.audit(&mut transaction, actor, "restore", before).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub fn is_removed(&self) -> bool {
+        self.removed_at.is_some()
+    }
+
+    /// Casts or changes the user's vote. `value` is `1` for an upvote and `-1` for a downvote.
+    pub async fn vote(&self, pool: &Pool<Postgres>, user: &User, value: i16) -> Result<(), PodelError> {
+        if value != 1 && value != -1 {
+            return Err(PodelError::UserError("Vote must be either 1 or -1.".into()));
+        }
+
+        sqlx::query(r#"
+                INSERT INTO comment.vote (comment_type, comment_id, user_id, value)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (comment_type, comment_id, user_id) DO UPDATE
+                SET value = EXCLUDED.value, created_at = CURRENT_TIMESTAMP
+            "#)
+            .bind(&self.parent_type)
+            .bind(&self.id)
+            .bind(&user.id)
+            .bind(value)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Withdraws the user's vote.
+    pub async fn unvote(&self, pool: &Pool<Postgres>, user: &User) -> Result<(), PodelError> {
+        sqlx::query("DELETE FROM comment.vote WHERE comment_type = $1 AND comment_id = $2 AND user_id = $3")
+            .bind(&self.parent_type)
+            .bind(&self.id)
+            .bind(&user.id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub fn score(&self) -> i64 {
+        self.upvotes - self.downvotes
+    }
+
+    /// Authors can edit their own comments, admins can edit all of them. Removed comments can't be edited.
+    pub fn can_edit(&self, user: Option<&Arc<User>>) -> bool {
+        !self.is_removed() && user.is_some_and(|user| user.is_admin || user.id == self.user.id)
+    }
+
+    fn get_query(parent_type: &str) -> String {
+        format!(r#"
+            SELECT
+                c.id,
+                c.parent_id as parent_id,
+                c.reply_to,
+                $1::text as parent_type,
+                c.content,
+                c.is_official,
+                c.removed_at,
+                c.removed_by_moderator,
+                c.created_at,
+                c.updated_at,
+                (SELECT COUNT(*) FROM comment.{parent_type} r WHERE r.reply_to = c.id) as reply_count,
+                (SELECT COUNT(*) FROM comment.vote v WHERE v.comment_type = $1 AND v.comment_id = c.id AND v.value > 0) as upvotes,
+                (SELECT COUNT(*) FROM comment.vote v WHERE v.comment_type = $1 AND v.comment_id = c.id AND v.value < 0) as downvotes,
+                u.id as user_id,
+                u.email as user_email,
+                u.password_hash as user_password_hash,
+                u.language as user_language,
+                u.name as user_name,
+                u.is_admin as user_is_admin,
+                u.is_active as user_is_active,
+                u.is_verified as user_is_verified,
+                u.last_login as user_last_login,
+                u.created_at as user_created_at,
+                u.updated_at as user_updated_at
+            FROM comment.{parent_type} c
+            LEFT JOIN auth.user u ON c.user_id = u.id
+        "#)
+    }
+
+    pub async fn find(
+        pool: &Pool<Postgres>,
+        parent_type: impl Into<String>,
+        id: impl Into<String>,
+    ) -> Result<Self, PodelError> {
+        let parent_type = parent_type.into();
+        Ok(
+            sqlx::query_as::<Postgres, Comment>(
+                &format!("{} WHERE c.id = $2", Self::get_query(&parent_type))
+            )
+            .bind(&parent_type)
+            .bind(id.into())
+            .fetch_one(pool)
+            .await?
+        )
+    }
+
+    /// Newest comments and replies of all subjects of a kind, for moderation.
+    pub async fn find_recent(
+        pool: &Pool<Postgres>,
+        parent_type: impl Into<String>,
+        limit: i64,
+        offset: i64
+    ) -> Result<Vec<Self>, PodelError> {
+        let parent_type = parent_type.into();
+        Ok(
+            sqlx::query_as::<Postgres, Comment>(
+                &format!(r#"
+                    {}
+                    ORDER BY c.created_at DESC
+                    LIMIT $2
+                    OFFSET $3
+                "#, Self::get_query(&parent_type))
+            )
+            .bind(&parent_type)
+            .bind(min(limit, 50))
+            .bind(offset)
+            .fetch_all(pool)
+            .await?
+        )
+    }
+
+    /// Top-level comments of a subject. Replies are loaded with [`Comment::find_replies`].
     pub async fn find_multiple(
         pool: &Pool<Postgres>,
         parent_type: impl Into<String>,
         parent_id: impl Into<String> + sqlx::Type<sqlx::Postgres>,
+        sort: CommentSort,
         limit: i64,
         offset: i64
     ) -> Result<Vec<Self>, PodelError> {
         let parent_type = parent_type.into();
-        println!("Fetching for {parent_type}");
         Ok(
             sqlx::query_as::<Postgres, Comment>(
             &format!(r#"
+                SELECT * FROM (
+                    {}
+                    WHERE c.parent_id = $2 AND c.reply_to IS NULL
+                ) c
+                ORDER BY {}
+                LIMIT $3
+                OFFSET $4
+            "#, Self::get_query(&parent_type), sort.order_by())
+        )
+        .bind(&parent_type)
+        .bind(parent_id.into())
+        .bind(min(limit, 20))
+        .bind(offset)
+        .fetch_all(pool)
+        .await?
+        )
+    }
+
+    /// Direct replies to a comment, oldest first so the conversation reads top to bottom.
+    pub async fn find_replies(
+        pool: &Pool<Postgres>,
+        parent_type: impl Into<String>,
+        comment_id: impl Into<String>,
+        limit: i64,
+        offset: i64
+    ) -> Result<Vec<Self>, PodelError> {
+        let parent_type = parent_type.into();
+        Ok(
+            sqlx::query_as::<Postgres, Comment>(
+            &format!(r#"
+                {}
+                WHERE c.reply_to = $2
+                ORDER BY c.created_at ASC
+                LIMIT $3
+                OFFSET $4
+            "#, Self::get_query(&parent_type))
+        )
+        .bind(&parent_type)
+        .bind(comment_id.into())
+        .bind(min(limit, 20))
+        .bind(offset)
+        .fetch_all(pool)
+        .await?
+        )
+    }
+}
+
+/// Order of top-level comments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentSort {
+    #[default]
+    Newest,
+    /// Highest score first.
+    Top,
+    /// Many votes split evenly between up and down first.
+    Controversial,
+}
+
+impl CommentSort {
+    fn order_by(self) -> &'static str {
+        match self {
+            CommentSort::Newest => "c.created_at DESC",
+            CommentSort::Top => "c.upvotes - c.downvotes DESC, c.created_at DESC",
+            CommentSort::Controversial => r#"
+                CASE WHEN c.upvotes = 0 OR c.downvotes = 0 THEN 0
+                ELSE (c.upvotes + c.downvotes)::float8 * LEAST(c.upvotes, c.downvotes) / GREATEST(c.upvotes, c.downvotes)
+                END DESC,
+                c.created_at DESC"#,
+        }
+    }
+}
+
+/// Previous version of an edited or deleted comment.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommentEdit {
+    pub id: Ulid,
+    pub comment_type: String,
+    pub comment_id: Ulid,
+    pub editor: Option<User>,
+    pub content: String,
+    pub is_deletion: bool,
+    pub edited_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl sqlx::FromRow<'_, PgRow> for CommentEdit {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(CommentEdit {
+            id: row.try_get("id")?,
+            comment_type: row.try_get("comment_type")?,
+            comment_id: row.try_get("comment_id")?,
+            editor: User::from_row(row).ok(),
+            content: row.try_get("content")?,
+            is_deletion: row.try_get("is_deletion")?,
+            edited_at: row.try_get("edited_at")?,
+        })
+    }
+}
+
+impl CommentEdit {
+    fn new(comment: &Comment, editor: &User, is_deletion: bool) -> Self {
+        Self {
+            id: ulid::Ulid::new().into(),
+            comment_type: comment.parent_type.clone(),
+            comment_id: comment.id.clone(),
+            editor: Some(editor.clone()),
+            content: comment.content.clone(),
+            is_deletion,
+            edited_at: chrono::Utc::now(),
+        }
+    }
+
+    async fn save<'e, E>(&self, executor: E) -> Result<(), PodelError>
+    where
+        E: sqlx::Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(r#"
+                INSERT INTO comment.edit_history (
+                    id,
+                    comment_type,
+                    comment_id,
+                    editor_id,
+                    content,
+                    is_deletion,
+                    edited_at
+                )
+                VALUES (
+                    $1, $2, $3, $4, $5, $6, $7
+                )
+            "#)
+            .bind(&self.id)
+            .bind(&self.comment_type)
+            .bind(&self.comment_id)
+            .bind(self.editor.as_ref().map(|user| &user.id))
+            .bind(&self.content)
+            .bind(self.is_deletion)
+            .bind(self.edited_at)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+
+    /// All previous versions of a comment, newest first.
+    pub async fn find_multiple(
+        pool: &Pool<Postgres>,
+        comment_type: impl Into<String>,
+        comment_id: impl Into<String>,
+    ) -> Result<Vec<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, CommentEdit>(r#"
                 SELECT
-                    c.id,
-                    c.parent_id as parent_id,
-                    $1::text as parent_type,
-                    c.content,
-                    c.created_at,
-                    c.updated_at,
+                    h.id,
+                    h.comment_type,
+                    h.comment_id,
+                    h.content,
+                    h.is_deletion,
+                    h.edited_at,
                     u.id as user_id,
                     u.email as user_email,
                     u.password_hash as user_password_hash,
@@ -90,20 +548,15 @@ impl Comment {
                     u.last_login as user_last_login,
                     u.created_at as user_created_at,
                     u.updated_at as user_updated_at
-                FROM comment.{parent_type} c
-                LEFT JOIN auth.user u ON c.user_id = u.id
-                WHERE parent_id = $2
-                ORDER BY c.created_at DESC
-                LIMIT $3
-                OFFSET $4
+                FROM comment.edit_history h
+                LEFT JOIN auth.user u ON h.editor_id = u.id
+                WHERE h.comment_type = $1 AND h.comment_id = $2
+                ORDER BY h.edited_at DESC
             "#)
-        )
-        .bind(parent_type)
-        .bind(parent_id.into())
-        .bind(min(limit, 20))
-        .bind(offset)
-        .fetch_all(pool)
-        .await?
+            .bind(comment_type.into())
+            .bind(comment_id.into())
+            .fetch_all(pool)
+            .await?
         )
     }
 }
@@ -0,0 +1,110 @@
+use sqlx::{Pool, Postgres};
+use crate::PodelError;
+
+/// Failed logins allowed before the account gets locked.
+const FREE_ATTEMPTS: i32 = 5;
+/// Length of the first lockout, doubled with every further failure.
+const BASE_LOCKOUT_SECONDS: i64 = 30;
+/// Upper bound of a single lockout.
+const MAX_LOCKOUT_SECONDS: i64 = 24 * 60 * 60;
+
+/// Exponentially growing lockout of an account after repeated failed logins.
+pub struct Lockout;
+
+impl Lockout {
+    /// End of the lockout if the account is locked right now.
+    pub async fn locked_until(
+        pool: &Pool<Postgres>,
+        user_id: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>, PodelError> {
+        Ok(
+            sqlx::query_scalar::<Postgres, chrono::DateTime<chrono::Utc>>(r#"
+                SELECT locked_until
+                FROM auth.lockout
+                WHERE user_id = $1 AND locked_until > CURRENT_TIMESTAMP
+            "#)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+        )
+    }
+
+    /// Counts a wrong password or code and locks the account once the free attempts run out.
+    /// Failures older than a day are forgotten. Returns the end of the new lockout, if any.
+    pub async fn record_failure(
+        pool: &Pool<Postgres>,
+        user_id: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>, PodelError> {
+        let failures = sqlx::query_scalar::<Postgres, i32>(r#"
+                INSERT INTO auth.lockout (user_id, failures, last_failure_at)
+                VALUES ($1, 1, CURRENT_TIMESTAMP)
+                ON CONFLICT (user_id) DO UPDATE
+                SET failures = CASE
+                        WHEN auth.lockout.last_failure_at < CURRENT_TIMESTAMP - INTERVAL '1 day' THEN 1
+                        ELSE auth.lockout.failures + 1
+                    END,
+                    last_failure_at = CURRENT_TIMESTAMP
+                RETURNING failures
+            "#)
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+
+        if failures < FREE_ATTEMPTS {
+            return Ok(None);
+        }
+
+        let locked_until = chrono::Utc::now() + chrono::Duration::seconds(lockout_seconds(failures));
+        sqlx::query("UPDATE auth.lockout SET locked_until = $2 WHERE user_id = $1")
+            .bind(user_id)
+            .bind(locked_until)
+            .execute(pool)
+            .await?;
+
+        Ok(Some(locked_until))
+    }
+
+    /// Forgets failed attempts after a successful login.
+    pub async fn clear(pool: &Pool<Postgres>, user_id: &str) -> Result<(), PodelError> {
+        sqlx::query("DELETE FROM auth.lockout WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn lockout_seconds(failures: i32) -> i64 {
+    let doublings = u32::try_from(failures - FREE_ATTEMPTS).unwrap_or_default().min(32);
+    BASE_LOCKOUT_SECONDS.saturating_mul(1_i64 << doublings).min(MAX_LOCKOUT_SECONDS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_lockout_is_the_base() {
+        assert_eq!(lockout_seconds(FREE_ATTEMPTS), BASE_LOCKOUT_SECONDS);
+    }
+
+    #[test]
+    fn lockout_doubles_with_every_failure() {
+        assert_eq!(lockout_seconds(FREE_ATTEMPTS + 1), 2 * BASE_LOCKOUT_SECONDS);
+        assert_eq!(lockout_seconds(FREE_ATTEMPTS + 2), 4 * BASE_LOCKOUT_SECONDS);
+        assert_eq!(lockout_seconds(FREE_ATTEMPTS + 5), 32 * BASE_LOCKOUT_SECONDS);
+    }
+
+    #[test]
+    fn lockout_is_capped() {
+        assert_eq!(lockout_seconds(FREE_ATTEMPTS + 12), MAX_LOCKOUT_SECONDS);
+        assert_eq!(lockout_seconds(FREE_ATTEMPTS + 40), MAX_LOCKOUT_SECONDS);
+        assert_eq!(lockout_seconds(i32::MAX), MAX_LOCKOUT_SECONDS);
+    }
+
+    #[test]
+    fn fewer_failures_than_free_attempts_never_underflow() {
+        assert_eq!(lockout_seconds(0), BASE_LOCKOUT_SECONDS);
+    }
+}
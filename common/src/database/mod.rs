@@ -8,8 +8,19 @@ use std::time::Duration;
 use crate::database::user::User;
 
 pub mod user;
+pub mod audit;
+pub mod claim;
 pub mod reviewable;
 pub mod comment;
+pub mod lockout;
+pub mod report;
+pub mod review;
+pub mod search;
+pub mod setting;
+pub mod suspension;
+pub mod token;
+pub mod totp;
+pub mod verification;
 
 pub type Ulid = String;
 pub type UserId = String;
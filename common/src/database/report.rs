@@ -0,0 +1,316 @@
+use std::cmp::min;
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{Pool, Postgres, Row};
+use crate::database::audit::{Actor, AuditEvent};
+use crate::database::Ulid;
+use crate::database::user::User;
+use crate::PodelError;
+
+/// Why a user thinks content breaks the rules.
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "moderation.report_reason")]
+pub enum ReportReason {
+    Spam,
+    /// False claims hurting someone's reputation
+    Defamation,
+    Harassment,
+    /// Content breaking the law, e.g. leaked personal data
+    Illegal,
+    Other,
+}
+
+impl ReportReason {
+    pub const ALL: [ReportReason; 5] = [
+        ReportReason::Spam,
+        ReportReason::Defamation,
+        ReportReason::Harassment,
+        ReportReason::Illegal,
+        ReportReason::Other,
+    ];
+
+    /// Key of the localized name in the Fluent bundles.
+    pub fn fluent_key(&self) -> String {
+        format!("report-reason-{}", self.code().to_lowercase())
+    }
+
+    /// Variant name as stored in the database and sent by forms.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ReportReason::Spam => "Spam",
+            ReportReason::Defamation => "Defamation",
+            ReportReason::Harassment => "Harassment",
+            ReportReason::Illegal => "Illegal",
+            ReportReason::Other => "Other",
+        }
+    }
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "moderation.report_status")]
+pub enum ReportStatus {
+    /// Waiting in the moderation queue
+    Open,
+    /// A moderator acted on the reported content
+    Actioned,
+    /// A moderator found nothing wrong
+    Dismissed,
+}
+
+/// What kind of content a report is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportTarget {
+    Comment,
+    Review,
+    /// The reviewed subject itself, e.g. a website
+    Subject,
+}
+
+impl ReportTarget {
+    pub const ALL: [ReportTarget; 3] = [ReportTarget::Comment, ReportTarget::Review, ReportTarget::Subject];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportTarget::Comment => "comment",
+            ReportTarget::Review => "review",
+            ReportTarget::Subject => "subject",
+        }
+    }
+}
+
+impl fmt::Display for ReportTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ReportTarget {
+    type Err = PodelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ReportTarget::ALL
+            .into_iter()
+            .find(|target| target.as_str() == s)
+            .ok_or_else(|| PodelError::UserError(format!("Unknown report target: {s}.")))
+    }
+}
+
+/// Reported comment, review or subject.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Report {
+    pub id: Ulid,
+    pub target: ReportTarget,
+    /// Subject kind the target belongs to, e.g. `website`.
+    pub subject_type: String,
+    pub subject_id: Ulid,
+    /// Same as `subject_id` for reports of the subject itself.
+    pub target_id: Ulid,
+    /// `None` once the reporter's account is deleted.
+    pub reporter: Option<User>,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+    pub status: ReportStatus,
+    pub resolved_by: Option<Ulid>,
+    /// Action the moderator took, e.g. `hide`.
+    pub resolution: Option<String>,
+    pub resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl sqlx::FromRow<'_, PgRow> for Report {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let target = row
+            .try_get::<String, _>("target_kind")?
+            .parse()
+            .map_err(|err: PodelError| sqlx::Error::Decode(err.to_string().into()))?;
+
+        Ok(Report {
+            id: row.try_get("id")?,
+            target,
+            subject_type: row.try_get("subject_type")?,
+            subject_id: row.try_get("subject_id")?,
+            target_id: row.try_get("target_id")?,
+            reporter: User::from_row(row).ok(),
+            reason: row.try_get("reason")?,
+            details: row.try_get("details")?,
+            status: row.try_get("status")?,
+            resolved_by: row.try_get("resolved_by")?,
+            resolution: row.try_get("resolution")?,
+            resolved_at: row.try_get("resolved_at")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl Report {
+    pub fn new(
+        target: ReportTarget,
+        subject_type: impl Into<String>,
+        subject_id: impl Into<Ulid>,
+        target_id: impl Into<Ulid>,
+        reporter: User,
+        reason: ReportReason,
+        details: Option<impl Into<String>>,
+    ) -> Self {
+        Self {
+            id: ulid::Ulid::new().into(),
+            target,
+            subject_type: subject_type.into(),
+            subject_id: subject_id.into(),
+            target_id: target_id.into(),
+            reporter: Some(reporter),
+            reason,
+            details: details.map(Into::into),
+            status: ReportStatus::Open,
+            resolved_by: None,
+            resolution: None,
+            resolved_at: None,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    fn get_query() -> &'static str {
+        r#"
+            SELECT
+                r.id,
+                r.target_kind,
+                r.subject_type,
+                r.subject_id,
+                r.target_id,
+                r.reason,
+                r.details,
+                r.status,
+                r.resolved_by,
+                r.resolution,
+                r.resolved_at,
+                r.created_at,
+                u.id as user_id,
+                u.email as user_email,
+                u.password_hash as user_password_hash,
+                u.language as user_language,
+                u.name as user_name,
+                u.is_admin as user_is_admin,
+                u.is_active as user_is_active,
+                u.is_verified as user_is_verified,
+                u.last_login as user_last_login,
+                u.created_at as user_created_at,
+                u.updated_at as user_updated_at
+            FROM moderation.report r
+            LEFT JOIN auth.user u ON r.reporter_id = u.id
+        "#
+    }
+
+    /// Files the report. Reporting the same target again while the first report is open does nothing.
+    pub async fn save(&self, pool: &Pool<Postgres>) -> Result<(), PodelError> {
+        sqlx::query(r#"
+                INSERT INTO moderation.report (
+                    id,
+                    target_kind,
+                    subject_type,
+                    subject_id,
+                    target_id,
+                    reporter_id,
+                    reason,
+                    details,
+                    status,
+                    created_at
+                )
+                VALUES (
+                    $1, $2, $3, $4, $5, $6, $7, $8, $9, $10
+                )
+                ON CONFLICT (target_kind, subject_type, target_id, reporter_id) WHERE status = 'Open' DO NOTHING
+            "#)
+            .bind(&self.id)
+            .bind(self.target.as_str())
+            .bind(&self.subject_type)
+            .bind(&self.subject_id)
+            .bind(&self.target_id)
+            .bind(self.reporter.as_ref().map(|user| &user.id))
+            .bind(self.reason)
+            .bind(&self.details)
+            .bind(self.status)
+            .bind(self.created_at)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn find(pool: &Pool<Postgres>, id: impl Into<String>) -> Result<Self, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, Report>(&format!("{} WHERE r.id = $1", Self::get_query()))
+                .bind(id.into())
+                .fetch_one(pool)
+                .await?
+        )
+    }
+
+    /// Moderation queue, oldest reports first.
+    pub async fn find_open(pool: &Pool<Postgres>, limit: i64, offset: i64) -> Result<Vec<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, Report>(&format!(r#"
+                    {}
+                    WHERE r.status = 'Open'
+                    ORDER BY r.created_at ASC
+                    LIMIT $1
+                    OFFSET $2
+                "#, Self::get_query()))
+                .bind(min(limit, 50))
+                .bind(offset)
+                .fetch_all(pool)
+                .await?
+        )
+    }
+
+    /// Closes this report and every other open report of the same target.
+    pub async fn resolve(
+        &self,
+        pool: &Pool<Postgres>,
+        actor: &Actor<'_>,
+        status: ReportStatus,
+        resolution: impl Into<String>,
+    ) -> Result<u64, PodelError> {
+        let resolution = resolution.into();
+        let mut transaction = pool.begin().await?;
+
+        let ids = sqlx::query_scalar::<Postgres, Ulid>(r#"
+                SELECT id
+                FROM moderation.report
+                WHERE target_kind = $1 AND subject_type = $2 AND target_id = $3 AND status = 'Open'
+                FOR UPDATE
+            "#)
+            .bind(self.target.as_str())
+            .bind(&self.subject_type)
+            .bind(&self.target_id)
+            .fetch_all(&mut *transaction)
+            .await?;
+
+        for id in &ids {
+            let before = AuditEvent::snapshot(&mut *transaction, "moderation.report", id).await?;
+
+            sqlx::query(r#"
+                    UPDATE moderation.report
+                    SET status = $2, resolved_by = $3, resolution = $4, resolved_at = CURRENT_TIMESTAMP
+                    WHERE id = $1
+                "#)
+                .bind(id)
+                .bind(status)
+                .bind(&actor.user.id)
+                .bind(&resolution)
+                .execute(&mut *transaction)
+                .await?;
+
+            let after = AuditEvent::snapshot(&mut *transaction, "moderation.report", id).await?;
+            AuditEvent::new(Some(actor), "resolve", "moderation.report", id)
+                .with_snapshots(before, after)
+                .record(&mut *transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+        Ok(ids.len() as u64)
+    }
+}
@@ -0,0 +1,345 @@
+use std::cmp::min;
+use std::ops::Deref;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{Pool, Postgres, Row};
+use crate::database::Ulid;
+use crate::database::audit::{Actor, AuditEvent};
+use crate::database::user::User;
+use crate::PodelError;
+
+/// Lowest score a single rating criterion can have.
+pub const RATING_MIN: i16 = 1;
+/// Highest score a single rating criterion can have.
+pub const RATING_MAX: i16 = 5;
+
+/// Per-criterion star rating of a reviewed subject.
+#[derive(sqlx::Type, sqlx::FromRow, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "rating")]
+pub struct Rating {
+    pub overall: i16,
+    pub reliability: i16,
+    pub quality: i16,
+    pub support: i16,
+}
+
+impl Rating {
+    pub fn new(overall: i16, reliability: i16, quality: i16, support: i16) -> Result<Self, PodelError> {
+        let rating = Self { overall, reliability, quality, support };
+        if rating.is_valid() {
+            Ok(rating)
+        } else {
+            Err(PodelError::UserError(format!("Ratings must be between {RATING_MIN} and {RATING_MAX} stars.")))
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        [self.overall, self.reliability, self.quality, self.support]
+            .iter()
+            .all(|stars| (RATING_MIN..=RATING_MAX).contains(stars))
+    }
+
+    /// Mean of all criteria, in stars.
+    pub fn average(&self) -> f32 {
+        f32::from(self.overall + self.reliability + self.quality + self.support) / 4.0
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Review {
+    pub id: Ulid,
+    pub parent_type: String,
+    pub parent_id: Ulid,
+    pub user: User,
+    pub title: String,
+    pub content: String,
+    pub rating: Rating,
+    /// Set while a moderator keeps the review out of listings and karma.
+    pub hidden_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Official answer of the subject's verified owner.
+    pub response: Option<String>,
+    pub responded_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl sqlx::FromRow<'_, PgRow> for Review {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let user = User::from_row(row).map_err(|_| sqlx::Error::RowNotFound)?;
+
+        Ok(Review {
+            id: row.try_get("id")?,
+            parent_type: row.try_get("parent_type")?,
+            parent_id: row.try_get("parent_id")?,
+            user,
+            title: row.try_get("title")?,
+            content: row.try_get("content")?,
+            rating: row.try_get("rating")?,
+            hidden_at: row.try_get("hidden_at")?,
+            response: row.try_get("response")?,
+            responded_at: row.try_get("responded_at")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+impl Review {
+    pub fn new(
+        parent_type: impl Into<String>,
+        parent_id: impl Into<Ulid>,
+        title: impl Into<String>,
+        content: impl Into<String>,
+        rating: Rating,
+        user: Arc<User>
+    ) -> Self {
+        Self {
+            id: ulid::Ulid::new().into(),
+            parent_type: parent_type.into(),
+            parent_id: parent_id.into(),
+            user: user.deref().clone(),
+            title: title.into(),
+            content: content.into(),
+            rating,
+            hidden_at: None,
+            response: None,
+            responded_at: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn get_query(parent_type: &str) -> String {
+        format!(r#"
+            SELECT
+                r.id,
+                r.parent_id,
+                $1::text as parent_type,
+                r.title,
+                r.content,
+                r.rating,
+                r.hidden_at,
+                r.response,
+                r.responded_at,
+                r.created_at,
+                r.updated_at,
+                u.id as user_id,
+                u.email as user_email,
+                u.password_hash as user_password_hash,
+                u.language as user_language,
+                u.name as user_name,
+                u.is_admin as user_is_admin,
+                u.is_active as user_is_active,
+                u.is_verified as user_is_verified,
+                u.last_login as user_last_login,
+                u.created_at as user_created_at,
+                u.updated_at as user_updated_at
+            FROM review.{parent_type} r
+            LEFT JOIN auth.user u ON r.user_id = u.id
+        "#)
+    }
+
+    /// Inserts a new review. Every user can review a subject only once.
+    pub async fn save(&self, pool: &Pool<Postgres>) -> Result<(), PodelError> {
+        if !self.rating.is_valid() {
+            return Err(PodelError::UserError("Invalid rating.".into()));
+        }
+
+        sqlx::query(&format!(r#"
+                INSERT INTO review.{} (
+                    id,
+                    parent_id,
+                    user_id,
+                    title,
+                    content,
+                    rating,
+                    created_at,
+                    updated_at
+                )
+                VALUES (
+                    $1, $2, $3, $4, $5, $6, $7, $8
+                )
+            "#, self.parent_type))
+            .bind(&self.id)
+            .bind(&self.parent_id)
+            .bind(&self.user.id)
+            .bind(&self.title)
+            .bind(&self.content)
+            .bind(self.rating)
+            .bind(self.created_at)
+            .bind(self.updated_at)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Overwrites title, content and rating of an existing review written by the same author.
+    pub async fn update(&mut self, pool: &Pool<Postgres>) -> Result<(), PodelError> {
+        if !self.rating.is_valid() {
+            return Err(PodelError::UserError("Invalid rating.".into()));
+        }
+        self.updated_at = chrono::Utc::now();
+
+        let result = sqlx::query(&format!(r#"
+                UPDATE review.{}
+                SET title = $3, content = $4, rating = $5, updated_at = $6
+                WHERE id = $1 AND user_id = $2
+            "#, self.parent_type))
+            .bind(&self.id)
+            .bind(&self.user.id)
+            .bind(&self.title)
+            .bind(&self.content)
+            .bind(self.rating)
+            .bind(self.updated_at)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            Err(PodelError::DatabaseError("Failed to update review".to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets or, with `None`, withdraws the official response. Callers check that the acting user owns the subject.
+    pub async fn respond(&mut self, pool: &Pool<Postgres>, actor: &Actor<'_>, response: Option<String>) -> Result<(), PodelError> {
+        let table = format!("review.{}", self.parent_type);
+        let responded_at = response.as_ref().map(|_| chrono::Utc::now());
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, &table, &self.id).await?;
+
+        sqlx::query(&format!(r#"
+                UPDATE {table}
+                SET response = $2, responded_by = $3, responded_at = $4
+                WHERE id = $1
+            "#))
+            .bind(&self.id)
+            .bind(&response)
+            .bind(response.as_ref().map(|_| &actor.user.id))
+            .bind(responded_at)
+            .execute(&mut *transaction)
+            .await?;
+
+        let after = AuditEvent::snapshot(&mut *transaction, &table, &self.id).await?;
+        let action = if response.is_some() { "respond" } else { "withdraw-response" };
+        AuditEvent::new(Some(actor), action, &table, &self.id)
+            .with_snapshots(before, after)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        self.response = response;
+        self.responded_at = responded_at;
+        Ok(())
+    }
+
+    /// Hides or shows the review again. Karma of the subject has to be recomputed afterwards.
+    pub async fn set_hidden(&mut self, pool: &Pool<Postgres>, actor: &Actor<'_>, hidden: bool) -> Result<(), PodelError> {
+        let table = format!("review.{}", self.parent_type);
+        let hidden_at = hidden.then(chrono::Utc::now);
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, &table, &self.id).await?;
+
+        sqlx::query(&format!("UPDATE {table} SET hidden_at = $2 WHERE id = $1"))
+            .bind(&self.id)
+            .bind(hidden_at)
+            .execute(&mut *transaction)
+            .await?;
+
+        let after = AuditEvent::snapshot(&mut *transaction, &table, &self.id).await?;
+        AuditEvent::new(Some(actor), if hidden { "hide" } else { "restore" }, &table, &self.id)
+            .with_snapshots(before, after)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        self.hidden_at = hidden_at;
+        Ok(())
+    }
+
+    /// Deletes the review. Karma of the subject has to be recomputed afterwards.
+    pub async fn delete(&self, pool: &Pool<Postgres>, actor: &Actor<'_>) -> Result<(), PodelError> {
+        let table = format!("review.{}", self.parent_type);
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, &table, &self.id).await?;
+
+        sqlx::query(&format!("DELETE FROM {table} WHERE id = $1"))
+            .bind(&self.id)
+            .execute(&mut *transaction)
+            .await?;
+
+        AuditEvent::new(Some(actor), "delete", &table, &self.id)
+            .with_snapshots(before, None)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub async fn find(
+        pool: &Pool<Postgres>,
+        parent_type: impl Into<String>,
+        id: impl Into<String>,
+    ) -> Result<Self, PodelError> {
+        let parent_type = parent_type.into();
+        Ok(
+            sqlx::query_as::<Postgres, Review>(
+                &format!("{} WHERE r.id = $2", Self::get_query(&parent_type))
+            )
+            .bind(&parent_type)
+            .bind(id.into())
+            .fetch_one(pool)
+            .await?
+        )
+    }
+
+    /// Review of a subject written by the given user, if there is one.
+    pub async fn find_by_user(
+        pool: &Pool<Postgres>,
+        parent_type: impl Into<String>,
+        parent_id: impl Into<String>,
+        user_id: impl Into<String>,
+    ) -> Result<Option<Self>, PodelError> {
+        let parent_type = parent_type.into();
+        Ok(
+            sqlx::query_as::<Postgres, Review>(
+                &format!("{} WHERE r.parent_id = $2 AND r.user_id = $3", Self::get_query(&parent_type))
+            )
+            .bind(&parent_type)
+            .bind(parent_id.into())
+            .bind(user_id.into())
+            .fetch_optional(pool)
+            .await?
+        )
+    }
+
+    pub async fn find_multiple(
+        pool: &Pool<Postgres>,
+        parent_type: impl Into<String>,
+        parent_id: impl Into<String>,
+        limit: i64,
+        offset: i64
+    ) -> Result<Vec<Self>, PodelError> {
+        let parent_type = parent_type.into();
+        Ok(
+            sqlx::query_as::<Postgres, Review>(
+                &format!(r#"
+                    {}
+                    WHERE r.parent_id = $2 AND r.hidden_at IS NULL
+                    ORDER BY r.created_at DESC
+                    LIMIT $3
+                    OFFSET $4
+                "#, Self::get_query(&parent_type))
+            )
+            .bind(&parent_type)
+            .bind(parent_id.into())
+            .bind(min(limit, 20))
+            .bind(offset)
+            .fetch_all(pool)
+            .await?
+        )
+    }
+}
@@ -0,0 +1,188 @@
+use crate::database::reviewable::{FormField, Karma, Organization, Reviewable, SubjectForm};
+use crate::database::Ulid;
+use crate::PodelError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{Postgres, Row};
+use crate::database::comment::Commentable;
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "subject.app_platform")]
+pub enum AppPlatform {
+    Android,
+    Ios,
+}
+
+impl AppPlatform {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppPlatform::Android => "Android",
+            AppPlatform::Ios => "iOS",
+        }
+    }
+
+    /// Value of the platform in forms, the same as in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AppPlatform::Android => "Android",
+            AppPlatform::Ios => "Ios",
+        }
+    }
+}
+
+impl std::str::FromStr for AppPlatform {
+    type Err = PodelError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "Android" => Ok(AppPlatform::Android),
+            "Ios" => Ok(AppPlatform::Ios),
+            _ => Err(PodelError::UserError(format!("Unknown platform {value}."))),
+        }
+    }
+}
+
+/// Mobile app, reviewed separately for each platform.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct App {
+    pub id: Ulid,
+    pub karma: Karma,
+    pub organization: Option<Organization>,
+    pub name: String,
+    pub description: Option<String>,
+    pub platform: AppPlatform,
+    /// listing in the platform's store
+    pub store_url: Option<String>,
+}
+
+impl sqlx::FromRow<'_, PgRow> for App {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(App {
+            id: row.try_get("id")?,
+            karma: row.try_get("karma")?,
+            organization: Organization::from_joined_row(row)?,
+            name: row.try_get("name")?,
+            description: row.try_get("description")?,
+            platform: row.try_get("platform")?,
+            store_url: row.try_get("store_url")?,
+        })
+    }
+}
+
+impl App {
+    pub fn new(
+        name: impl Into<String>,
+        platform: AppPlatform,
+        description: Option<impl Into<String>>,
+        organization: Option<Organization>
+    ) -> Self {
+        Self {
+            id: ulid::Ulid::new().to_string(),
+            karma: Karma::new(),
+            organization,
+            name: name.into(),
+            description: description.map(|o| o.into()),
+            platform,
+            store_url: None,
+        }
+    }
+
+    pub fn with_store_url(mut self, store_url: impl Into<String>) -> Self {
+        self.store_url = Some(store_url.into());
+        self
+    }
+}
+
+impl Commentable for App {
+    fn id(&self) -> &Ulid {
+        &self.id
+    }
+}
+
+#[async_trait]
+impl Reviewable for App {
+    const KIND: &'static str = "app";
+    const COLUMNS: &'static str = "s.platform, s.store_url";
+    const FORM_FIELDS: &'static [FormField] = &[
+        FormField { name: "platform", label: "platform", required: true, options: &["Android", "Ios"] },
+        FormField { name: "store_url", label: "store-url", required: false, options: &[] },
+    ];
+
+    fn display_name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    fn karma(&self) -> &Karma {
+        &self.karma
+    }
+
+    fn organization(&self) -> Option<&Organization> {
+        self.organization.as_ref()
+    }
+
+    fn details(&self) -> Vec<(&'static str, String)> {
+        let mut details = vec![("platform", self.platform.name().to_string())];
+        if let Some(store_url) = &self.store_url {
+            details.push(("store-url", store_url.clone()));
+        }
+
+        details
+    }
+
+    fn form_value(&self, name: &str) -> String {
+        match name {
+            "platform" => self.platform.as_str().to_string(),
+            "store_url" => self.store_url.clone().unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+
+    fn from_form(existing: Option<Self>, form: SubjectForm) -> Result<Self, PodelError> {
+        let platform = form.required("platform")?.parse()?;
+        let store_url = form.optional("store_url");
+        if store_url.as_deref().is_some_and(|url| !url.starts_with("https://")) {
+            return Err(PodelError::UserError("The store listing has to be an https:// link.".into()));
+        }
+
+        Ok(match existing {
+            Some(subject) => Self {
+                name: form.name,
+                description: form.description,
+                organization: form.organization,
+                platform,
+                store_url,
+                ..subject
+            },
+            None => Self { store_url, ..Self::new(form.name, platform, form.description, form.organization) },
+        })
+    }
+
+    async fn upsert(&self, transaction: &mut sqlx::Transaction<'_, Postgres>) -> Result<(), sqlx::Error> {
+        sqlx::query(r#"
+                INSERT INTO subject.app (id, organization_id, name, description, platform, store_url)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (id) DO UPDATE
+                SET organization_id = EXCLUDED.organization_id,
+                    name = EXCLUDED.name,
+                    description = EXCLUDED.description,
+                    platform = EXCLUDED.platform,
+                    store_url = EXCLUDED.store_url,
+                    updated_at = CURRENT_TIMESTAMP
+            "#)
+            .bind(&self.id)
+            .bind(self.organization.as_ref().map(|organization| &organization.id))
+            .bind(&self.name)
+            .bind(&self.description)
+            .bind(self.platform)
+            .bind(&self.store_url)
+            .execute(&mut **transaction)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use crate::database::reviewable::{FormField, Karma, Organization, Reviewable, SubjectForm};
+use crate::database::Ulid;
+use crate::PodelError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{Postgres, Row};
+use crate::database::comment::Commentable;
+
+/// Online shop, reviewed for its orders and deliveries rather than as a website.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Eshop {
+    pub id: Ulid,
+    pub karma: Karma,
+    pub organization: Option<Organization>,
+    pub name: String,
+    pub description: Option<String>,
+    pub domain_name: String,
+}
+
+impl sqlx::FromRow<'_, PgRow> for Eshop {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Eshop {
+            id: row.try_get("id")?,
+            karma: row.try_get("karma")?,
+            organization: Organization::from_joined_row(row)?,
+            name: row.try_get("name")?,
+            description: row.try_get("description")?,
+            domain_name: row.try_get("domain_name")?,
+        })
+    }
+}
+
+impl Eshop {
+    pub fn new(
+        name: impl Into<String>,
+        domain_name: impl Into<String>,
+        description: Option<impl Into<String>>,
+        organization: Option<Organization>
+    ) -> Self {
+        Self {
+            id: ulid::Ulid::new().to_string(),
+            karma: Karma::new(),
+            organization,
+            name: name.into(),
+            description: description.map(|o| o.into()),
+            domain_name: domain_name.into(),
+        }
+    }
+}
+
+impl Commentable for Eshop {
+    fn id(&self) -> &Ulid {
+        &self.id
+    }
+}
+
+#[async_trait]
+impl Reviewable for Eshop {
+    const KIND: &'static str = "eshop";
+    const COLUMNS: &'static str = "s.domain_name";
+    const FORM_FIELDS: &'static [FormField] = &[
+        FormField { name: "domain_name", label: "domain", required: true, options: &[] },
+    ];
+
+    fn display_name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    fn karma(&self) -> &Karma {
+        &self.karma
+    }
+
+    fn organization(&self) -> Option<&Organization> {
+        self.organization.as_ref()
+    }
+
+    fn details(&self) -> Vec<(&'static str, String)> {
+        vec![("domain", self.domain_name.clone())]
+    }
+
+    fn form_value(&self, name: &str) -> String {
+        match name {
+            "domain_name" => self.domain_name.clone(),
+            _ => String::new(),
+        }
+    }
+
+    fn from_form(existing: Option<Self>, form: SubjectForm) -> Result<Self, PodelError> {
+        let domain_name = form.domain("domain_name")?;
+        Ok(match existing {
+            Some(subject) => Self {
+                name: form.name,
+                description: form.description,
+                organization: form.organization,
+                domain_name,
+                ..subject
+            },
+            None => Self::new(form.name, domain_name, form.description, form.organization),
+        })
+    }
+
+    async fn upsert(&self, transaction: &mut sqlx::Transaction<'_, Postgres>) -> Result<(), sqlx::Error> {
+        sqlx::query(r#"
+                INSERT INTO subject.eshop (id, organization_id, name, description, domain_name)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (id) DO UPDATE
+                SET organization_id = EXCLUDED.organization_id,
+                    name = EXCLUDED.name,
+                    description = EXCLUDED.description,
+                    domain_name = EXCLUDED.domain_name,
+                    updated_at = CURRENT_TIMESTAMP
+            "#)
+            .bind(&self.id)
+            .bind(self.organization.as_ref().map(|organization| &organization.id))
+            .bind(&self.name)
+            .bind(&self.description)
+            .bind(&self.domain_name)
+            .execute(&mut **transaction)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use crate::PodelError;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Identifikační číslo osoby, the 8-digit Czech business identifier ending with a mod-11 check digit.
+#[derive(sqlx::Type, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[sqlx(transparent)]
+#[serde(try_from = "String", into = "String")]
+pub struct Ico(String);
+
+impl Ico {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Check digit of the first seven digits.
+    fn check_digit(digits: &[u32]) -> u32 {
+        let sum: u32 = digits.iter().zip((2..=8).rev()).map(|(digit, weight)| digit * weight).sum();
+        (11 - sum % 11) % 10
+    }
+}
+
+impl FromStr for Ico {
+    type Err = PodelError;
+
+    /// Accepts spaces and missing leading zeros, e.g. `270 82 440` or `6947`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+        if trimmed.is_empty() || trimmed.len() > 8 || !trimmed.chars().all(|c| c.is_ascii_digit()) {
+            return Err(PodelError::UserError(format!("IČO must have up to 8 digits: {s}.")));
+        }
+
+        let ico = format!("{trimmed:0>8}");
+        let digits = ico.chars().filter_map(|c| c.to_digit(10)).collect::<Vec<_>>();
+        if Self::check_digit(&digits[..7]) != digits[7] {
+            return Err(PodelError::UserError(format!("IČO has an invalid check digit: {s}.")));
+        }
+
+        Ok(Self(ico))
+    }
+}
+
+impl TryFrom<String> for Ico {
+    type Error = PodelError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Ico> for String {
+    fn from(ico: Ico) -> Self {
+        ico.0
+    }
+}
+
+impl fmt::Display for Ico {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Daňové identifikační číslo, the Czech VAT number. `CZ` followed by the IČO of a legal person,
+/// the birth number of a natural person or a 9-digit number starting with 6 assigned by the tax office.
+#[derive(sqlx::Type, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[sqlx(transparent)]
+#[serde(try_from = "String", into = "String")]
+pub struct Dic(String);
+
+impl Dic {
+    const PREFIX: &'static str = "CZ";
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Digits after the country prefix.
+    pub fn number(&self) -> &str {
+        &self.0[Self::PREFIX.len()..]
+    }
+
+    /// Birth numbers issued since 1954 have 10 digits and are divisible by 11.
+    /// Numbers whose first nine digits leave a remainder of 10 end with 0 instead.
+    fn is_valid_birth_number(number: &str) -> bool {
+        let Ok(value) = number.parse::<u64>() else {
+            return false;
+        };
+
+        value % 11 == 0 || (value / 10 % 11 == 10 && value % 10 == 0)
+    }
+}
+
+impl FromStr for Dic {
+    type Err = PodelError;
+
+    /// Accepts spaces, lowercase and a missing `CZ` prefix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase();
+        let number = normalized.strip_prefix(Self::PREFIX).unwrap_or(&normalized);
+        if !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(PodelError::UserError(format!("DIČ must be CZ followed by digits: {s}.")));
+        }
+
+        let is_valid = match number.len() {
+            8 => number.parse::<Ico>().is_ok(),
+            // Tax office numbers, and birth numbers issued before 1954 which have no check digit.
+            9 => true,
+            10 => Self::is_valid_birth_number(number),
+            _ => false,
+        };
+
+        if is_valid {
+            Ok(Self(format!("{}{number}", Self::PREFIX)))
+        } else {
+            Err(PodelError::UserError(format!("DIČ is not valid: {s}.")))
+        }
+    }
+}
+
+impl TryFrom<String> for Dic {
+    type Error = PodelError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Dic> for String {
+    fn from(dic: Dic) -> Self {
+        dic.0
+    }
+}
+
+impl fmt::Display for Dic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_ico() {
+        // The weighted sum leaves remainder 1 and 0, which map to check digits 0 and 1.
+        for ico in ["27082440", "25596641", "12345679"] {
+            assert_eq!(ico.parse::<Ico>().unwrap().as_str(), ico);
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_check_digit() {
+        for ico in ["27082441", "25596640", "12345678"] {
+            assert!(ico.parse::<Ico>().is_err(), "{ico} should be invalid");
+        }
+    }
+
+    #[test]
+    fn pads_short_ico_and_strips_spaces() {
+        assert_eq!("6947".parse::<Ico>().unwrap().as_str(), "00006947");
+        assert_eq!("270 82 440".parse::<Ico>().unwrap().as_str(), "27082440");
+    }
+
+    #[test]
+    fn rejects_malformed_ico() {
+        for ico in ["", "123456789", "2708244a", "-2708244"] {
+            assert!(ico.parse::<Ico>().is_err(), "{ico:?} should be invalid");
+        }
+    }
+
+    #[test]
+    fn dic_of_legal_person_is_checked_as_ico() {
+        let dic = "CZ27082440".parse::<Dic>().unwrap();
+        assert_eq!(dic.as_str(), "CZ27082440");
+        assert_eq!(dic.number(), "27082440");
+        assert!("CZ27082441".parse::<Dic>().is_err());
+    }
+
+    #[test]
+    fn dic_is_normalized() {
+        assert_eq!("cz 270 82 440".parse::<Dic>().unwrap().as_str(), "CZ27082440");
+        assert_eq!("27082440".parse::<Dic>().unwrap().as_str(), "CZ27082440");
+    }
+
+    #[test]
+    fn dic_of_nine_digits_is_accepted() {
+        assert_eq!("CZ699001234".parse::<Dic>().unwrap().as_str(), "CZ699001234");
+    }
+
+    #[test]
+    fn dic_of_birth_number() {
+        // divisible by 11
+        assert!("CZ8001010006".parse::<Dic>().is_ok());
+        // first nine digits leave remainder 10, so the check digit is 0
+        assert!("CZ7552300020".parse::<Dic>().is_ok());
+        assert!("CZ8001010007".parse::<Dic>().is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_dic() {
+        for dic in ["", "CZ", "CZ1234567", "CZ12345678901", "SK27082440", "CZ2708244O"] {
+            assert!(dic.parse::<Dic>().is_err(), "{dic:?} should be invalid");
+        }
+    }
+}
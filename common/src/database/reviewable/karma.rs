@@ -0,0 +1,290 @@
+use crate::database::review::{RATING_MAX, RATING_MIN};
+use crate::database::reviewable::Karma;
+use crate::database::Ulid;
+use crate::PodelError;
+use clap::Parser;
+use sqlx::{Pool, Postgres};
+
+// Defaults shared by the command line arguments and `KarmaWeights::default`.
+const DEFAULT_REVIEWS: f64 = 100.0;
+const DEFAULT_REVIEW_CONFIDENCE: f64 = 5.0;
+const DEFAULT_COMMENTS: f64 = 10.0;
+const DEFAULT_VOTES: f64 = 8.0;
+const DEFAULT_VISITS: f64 = 5.0;
+const DEFAULT_AGE: f64 = 2.0;
+const DEFAULT_AGE_CAP: f64 = 20.0;
+
+/// Weights of the individual karma components.
+#[derive(Parser, Debug, Clone, Copy, PartialEq)]
+pub struct KarmaWeights {
+    /// Karma of a subject with a perfect rating and enough reviews to be trusted.
+    #[clap(long = "karma-reviews", env = "KARMA_REVIEWS", default_value_t = DEFAULT_REVIEWS)]
+    pub reviews: f64,
+    /// Number of reviews at which the review score is trusted at half weight.
+    #[clap(long = "karma-review-confidence", env = "KARMA_REVIEW_CONFIDENCE", default_value_t = DEFAULT_REVIEW_CONFIDENCE)]
+    pub review_confidence: f64,
+    /// Karma per natural log of the comment count.
+    #[clap(long = "karma-comments", env = "KARMA_COMMENTS", default_value_t = DEFAULT_COMMENTS)]
+    pub comments: f64,
+    /// Karma per natural log of the summed comment votes, negative for downvoted discussions.
+    #[clap(long = "karma-votes", env = "KARMA_VOTES", default_value_t = DEFAULT_VOTES)]
+    pub votes: f64,
+    /// Karma per natural log of the visit count.
+    #[clap(long = "karma-visits", env = "KARMA_VISITS", default_value_t = DEFAULT_VISITS)]
+    pub visits: f64,
+    /// Karma per year since the subject was added.
+    #[clap(long = "karma-age", env = "KARMA_AGE", default_value_t = DEFAULT_AGE)]
+    pub age: f64,
+    /// Upper bound of the age component.
+    #[clap(long = "karma-age-cap", env = "KARMA_AGE_CAP", default_value_t = DEFAULT_AGE_CAP)]
+    pub age_cap: f64,
+}
+
+impl Default for KarmaWeights {
+    fn default() -> Self {
+        Self {
+            reviews: DEFAULT_REVIEWS,
+            review_confidence: DEFAULT_REVIEW_CONFIDENCE,
+            comments: DEFAULT_COMMENTS,
+            votes: DEFAULT_VOTES,
+            visits: DEFAULT_VISITS,
+            age: DEFAULT_AGE,
+            age_cap: DEFAULT_AGE_CAP,
+        }
+    }
+}
+
+/// Everything the karma of a single subject is derived from.
+#[derive(sqlx::FromRow, Clone, Debug, Default, PartialEq)]
+pub struct KarmaInputs {
+    pub id: Ulid,
+    pub review_count: i64,
+    /// Mean overall rating, `None` if the subject has no reviews.
+    pub review_average: Option<f64>,
+    pub comment_count: i64,
+    /// Upvotes minus downvotes of all comments.
+    pub vote_score: i64,
+    pub visits: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl KarmaInputs {
+    fn get_query(kind: &str) -> String {
+        format!(r#"
+            SELECT
+                s.id,
+                s.created_at,
+                s.visits,
+                (SELECT COUNT(*) FROM review.{kind} r WHERE r.parent_id = s.id AND r.hidden_at IS NULL) as review_count,
+                (SELECT AVG((r.rating).overall)::float8 FROM review.{kind} r WHERE r.parent_id = s.id AND r.hidden_at IS NULL) as review_average,
+                (SELECT COUNT(*) FROM comment.{kind} c WHERE c.parent_id = s.id AND c.removed_at IS NULL) as comment_count,
+                (
+                    SELECT COALESCE(SUM(v.value), 0)::bigint
+                    FROM comment.vote v
+                    JOIN comment.{kind} c ON v.comment_type = '{kind}' AND v.comment_id = c.id
+                    WHERE c.parent_id = s.id AND c.removed_at IS NULL
+                ) as vote_score
+            FROM subject.{kind} s
+        "#)
+    }
+
+    pub async fn find(pool: &Pool<Postgres>, kind: &str, id: impl Into<String>) -> Result<Self, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, KarmaInputs>(&format!("{} WHERE s.id = $1", Self::get_query(kind)))
+                .bind(id.into())
+                .fetch_one(pool)
+                .await?
+        )
+    }
+
+    pub async fn find_all(pool: &Pool<Postgres>, kind: &str) -> Result<Vec<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, KarmaInputs>(&Self::get_query(kind))
+                .fetch_all(pool)
+                .await?
+        )
+    }
+}
+
+impl Karma {
+    /// Derives all karma components from the given inputs, as of `now`.
+    pub fn compute(inputs: &KarmaInputs, weights: &KarmaWeights, now: chrono::DateTime<chrono::Utc>) -> Self {
+        let reviews = inputs.review_average.map_or(0.0, |average| {
+            // Map the rating onto <-1, 1> around the middle of the scale.
+            let midpoint = f64::from(RATING_MIN + RATING_MAX) / 2.0;
+            let normalized = (average - midpoint) / (f64::from(RATING_MAX) - midpoint);
+            #[allow(clippy::cast_precision_loss)]
+            let count = inputs.review_count.max(0) as f64;
+            let confidence = count / (count + weights.review_confidence.max(f64::EPSILON));
+
+            normalized.clamp(-1.0, 1.0) * confidence * weights.reviews
+        });
+
+        #[allow(clippy::cast_precision_loss)]
+        let popularity = weights.comments * (inputs.comment_count.max(0) as f64).ln_1p()
+            + weights.votes * (inputs.vote_score.unsigned_abs() as f64).ln_1p().copysign(inputs.vote_score as f64)
+            + weights.visits * (inputs.visits.max(0) as f64).ln_1p();
+
+        #[allow(clippy::cast_precision_loss)]
+        let years = (now - inputs.created_at).num_days().max(0) as f64 / 365.25;
+        let age = (years * weights.age).min(weights.age_cap);
+
+        Self {
+            amount: to_score(reviews + popularity + age),
+            reviews: to_score(reviews),
+            age: to_score(age),
+            popularity: to_score(popularity),
+        }
+    }
+
+    /// Recomputes and stores karma of a single subject.
+    pub async fn recompute(
+        pool: &Pool<Postgres>,
+        kind: &str,
+        id: impl Into<String>,
+        weights: &KarmaWeights,
+    ) -> Result<Self, PodelError> {
+        let inputs = KarmaInputs::find(pool, kind, id).await?;
+        let karma = Self::compute(&inputs, weights, chrono::Utc::now());
+        karma.store(pool, kind, &inputs.id).await?;
+
+        Ok(karma)
+    }
+
+    /// Recomputes and stores karma of every subject of the given kind in one transaction.
+    /// Returns the number of updated subjects.
+    pub async fn recompute_all(pool: &Pool<Postgres>, kind: &str, weights: &KarmaWeights) -> Result<usize, PodelError> {
+        let now = chrono::Utc::now();
+        let inputs = KarmaInputs::find_all(pool, kind).await?;
+        let mut transaction = pool.begin().await?;
+
+        for subject in &inputs {
+            Self::compute(subject, weights, now).store(&mut *transaction, kind, &subject.id).await?;
+        }
+
+        transaction.commit().await?;
+        Ok(inputs.len())
+    }
+
+    async fn store<'e, E>(&self, executor: E, kind: &str, id: &Ulid) -> Result<(), PodelError>
+    where
+        E: sqlx::Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(&format!(r#"
+                UPDATE subject.{kind}
+                SET karma = ROW($2, $3, $4, $5)::karma
+                WHERE id = $1
+            "#))
+            .bind(id)
+            .bind(self.amount)
+            .bind(self.reviews)
+            .bind(self.age)
+            .bind(self.popularity)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn to_score(value: f64) -> i16 {
+    value.round().clamp(f64::from(i16::MIN), f64::from(i16::MAX)) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn now() -> chrono::DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    fn inputs() -> KarmaInputs {
+        KarmaInputs { created_at: now(), ..KarmaInputs::default() }
+    }
+
+    #[test]
+    fn new_subject_has_no_karma() {
+        let karma = Karma::compute(&inputs(), &KarmaWeights::default(), now());
+
+        assert_eq!((karma.amount, karma.reviews, karma.age, karma.popularity), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn review_score_grows_with_confidence() {
+        let weights = KarmaWeights::default();
+        let few = KarmaInputs { review_count: 5, review_average: Some(f64::from(RATING_MAX)), ..inputs() };
+        let many = KarmaInputs { review_count: 995, ..few.clone() };
+
+        // 5 reviews against a confidence of 5 count at half weight.
+        assert_eq!(Karma::compute(&few, &weights, now()).reviews, 50);
+        assert_eq!(Karma::compute(&many, &weights, now()).reviews, 100);
+    }
+
+    #[test]
+    fn bad_reviews_are_negative_and_middle_ones_neutral() {
+        let weights = KarmaWeights::default();
+        let worst = KarmaInputs { review_count: 5, review_average: Some(f64::from(RATING_MIN)), ..inputs() };
+        let middle = KarmaInputs { review_average: Some(f64::from(RATING_MIN + RATING_MAX) / 2.0), ..worst.clone() };
+
+        assert_eq!(Karma::compute(&worst, &weights, now()).reviews, -50);
+        assert_eq!(Karma::compute(&middle, &weights, now()).reviews, 0);
+    }
+
+    #[test]
+    fn out_of_scale_average_is_clamped() {
+        let inputs = KarmaInputs { review_count: 5, review_average: Some(100.0), ..inputs() };
+
+        assert_eq!(Karma::compute(&inputs, &KarmaWeights::default(), now()).reviews, 50);
+    }
+
+    #[test]
+    fn age_is_capped() {
+        let weights = KarmaWeights::default();
+        let year_old = KarmaInputs { created_at: now() - Duration::days(366), ..inputs() };
+        let ancient = KarmaInputs { created_at: now() - Duration::days(365 * 50), ..inputs() };
+        let future = KarmaInputs { created_at: now() + Duration::days(365), ..inputs() };
+
+        assert_eq!(Karma::compute(&year_old, &weights, now()).age, 2);
+        assert_eq!(Karma::compute(&ancient, &weights, now()).age, 20);
+        assert_eq!(Karma::compute(&future, &weights, now()).age, 0);
+    }
+
+    #[test]
+    fn downvoted_discussions_lower_popularity() {
+        let weights = KarmaWeights { comments: 0.0, visits: 0.0, ..KarmaWeights::default() };
+        let upvoted = KarmaInputs { vote_score: 20, ..inputs() };
+        let downvoted = KarmaInputs { vote_score: -20, ..inputs() };
+
+        let up = Karma::compute(&upvoted, &weights, now()).popularity;
+        assert!(up > 0);
+        assert_eq!(Karma::compute(&downvoted, &weights, now()).popularity, -up);
+    }
+
+    #[test]
+    fn amount_is_the_sum_of_components() {
+        let inputs = KarmaInputs {
+            review_count: 10,
+            review_average: Some(4.0),
+            comment_count: 12,
+            vote_score: 7,
+            visits: 1000,
+            created_at: now() - Duration::days(800),
+            ..inputs()
+        };
+        let karma = Karma::compute(&inputs, &KarmaWeights::default(), now());
+
+        assert!((karma.amount - (karma.reviews + karma.age + karma.popularity)).abs() <= 1);
+    }
+
+    #[test]
+    fn scores_saturate_instead_of_overflowing() {
+        let weights = KarmaWeights { visits: 1e9, ..KarmaWeights::default() };
+        let inputs = KarmaInputs { visits: 10, ..inputs() };
+
+        assert_eq!(Karma::compute(&inputs, &weights, now()).popularity, i16::MAX);
+        assert_eq!(to_score(-1e12), i16::MIN);
+    }
+}
@@ -1,17 +1,316 @@
+use crate::database::audit::{Actor, AuditEvent};
+use crate::database::comment::Commentable;
+use crate::database::reviewable::app::App;
+use crate::database::reviewable::eshop::Eshop;
+use crate::database::reviewable::identifier::{Dic, Ico};
+use crate::database::reviewable::service::Service;
+use crate::database::reviewable::website::Website;
 use crate::database::user::User;
 use crate::database::Ulid;
+use crate::ownership::is_public_hostname;
+use crate::PodelError;
+use async_trait::async_trait;
 use core::fmt;
 use serde::{Deserialize, Serialize};
-use sqlx::{Database, Row};
+use sqlx::postgres::PgRow;
+use sqlx::{Database, Pool, Postgres, Row};
+use std::cmp::min;
+use std::collections::HashMap;
 
+pub mod app;
+pub mod eshop;
+pub mod identifier;
+pub mod karma;
+pub mod service;
 pub mod website;
 
-#[derive(sqlx::FromRow, sqlx::Type, Clone, Debug, Serialize, Deserialize)]
-#[sqlx(type_name = "RECORD")]
+/// Every reviewable kind, each with its `subject`, `comment` and `review` table.
+pub const KINDS: [&str; 4] = [Website::KIND, App::KIND, Eshop::KIND, Service::KIND];
+
+pub fn is_kind(kind: &str) -> bool {
+    KINDS.contains(&kind)
+}
+
+/// Whether a subject of the kind has the id. Unknown kinds have no subjects.
+pub async fn exists(pool: &Pool<Postgres>, kind: &str, id: &str) -> Result<bool, PodelError> {
+    if !is_kind(kind) {
+        return Ok(false);
+    }
+
+    Ok(
+        sqlx::query_scalar::<Postgres, bool>(&format!("SELECT EXISTS (SELECT 1 FROM subject.{kind} WHERE id = $1)"))
+            .bind(id)
+            .fetch_one(pool)
+            .await?
+    )
+}
+
+/// Whether the user owns the organization the subject belongs to, which takes an approved claim.
+/// Unknown kinds have no subjects.
+pub async fn is_organization_owner(pool: &Pool<Postgres>, kind: &str, id: &str, user_id: &str) -> Result<bool, PodelError> {
+    if !is_kind(kind) {
+        return Ok(false);
+    }
+
+    Ok(
+        sqlx::query_scalar::<Postgres, bool>(&format!(r#"
+                SELECT EXISTS (
+                    SELECT 1
+                    FROM subject.{kind} s
+                    JOIN subject.organization o ON s.organization_id = o.id
+                    WHERE s.id = $1 AND o.user_id = $2
+                )
+            "#))
+            .bind(id)
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?
+    )
+}
+
+/// Deletes the subject with all its comments, votes and reviews. Unknown kinds have no subjects.
+pub async fn delete(pool: &Pool<Postgres>, actor: &Actor<'_>, kind: &str, id: impl Into<String>) -> Result<(), PodelError> {
+    if !is_kind(kind) {
+        return Err(PodelError::Empty());
+    }
+
+    let id = id.into();
+    let table = format!("subject.{kind}");
+    let mut transaction = pool.begin().await?;
+    let before = AuditEvent::snapshot(&mut *transaction, &table, &id).await?;
+
+    sqlx::query(&format!(r#"
+            DELETE FROM comment.vote v
+            USING comment.{kind} c
+            WHERE v.comment_type = $2 AND v.comment_id = c.id AND c.parent_id = $1
+        "#))
+        .bind(&id)
+        .bind(kind)
+        .execute(&mut *transaction)
+        .await?;
+
+    sqlx::query(&format!("DELETE FROM comment.{kind} WHERE parent_id = $1"))
+        .bind(&id)
+        .execute(&mut *transaction)
+        .await?;
+
+    // Reviews are removed by the cascade.
+    let result = sqlx::query(&format!("DELETE FROM {table} WHERE id = $1"))
+        .bind(&id)
+        .execute(&mut *transaction)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(PodelError::Empty());
+    }
+
+    AuditEvent::new(Some(actor), "delete", table.as_str(), &id)
+        .with_snapshots(before, None)
+        .record(&mut *transaction)
+        .await?;
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Input of the admin forms specific to a kind, e.g. the domain of a website.
+#[derive(Clone, Copy, Debug)]
+pub struct FormField {
+    /// name of the form field
+    pub name: &'static str,
+    /// key of the label in the Fluent bundles
+    pub label: &'static str,
+    pub required: bool,
+    /// values to pick from, free text if empty
+    pub options: &'static [&'static str],
+}
+
+/// Submitted admin form of a subject, with the fields shared by all kinds already checked.
+#[derive(Clone, Debug)]
+pub struct SubjectForm {
+    pub name: String,
+    pub description: Option<String>,
+    pub organization: Option<Organization>,
+    /// fields of [`Reviewable::FORM_FIELDS`] by name, trimmed and without empty ones
+    pub fields: HashMap<String, String>,
+}
+
+impl SubjectForm {
+    /// Value of a kind-specific field the form has to fill in.
+    pub fn required(&self, name: &str) -> Result<String, PodelError> {
+        self.optional(name).ok_or_else(|| PodelError::UserError(format!("The {name} can't be empty.")))
+    }
+
+    pub fn optional(&self, name: &str) -> Option<String> {
+        self.fields.get(name).cloned()
+    }
+
+    /// Required field holding a domain, lowercased and without a trailing dot. Only public hostnames are
+    /// accepted, as the domain is later fetched from when the owner verifies it.
+    pub fn domain(&self, name: &str) -> Result<String, PodelError> {
+        let domain = self.required(name)?.trim_end_matches('.').to_lowercase();
+        if is_public_hostname(&domain) {
+            Ok(domain)
+        } else {
+            Err(PodelError::UserError("The domain has to be a public hostname.".into()))
+        }
+    }
+}
+
+/// Something users review, rate and discuss, e.g. a website or a mobile app.
+///
+/// Each kind has a `subject.<kind>` table with `id`, `organization_id`, `karma`, `visits`, `name`,
+/// `description` and timestamps followed by its own columns, and comment and review tables created
+/// by `create_comment_table` and `create_review_table`. The rows are read by the `FromRow` impl from
+/// [`Reviewable::get_query`], whose organization comes from [`Organization::from_joined_row`].
+#[async_trait]
+pub trait Reviewable: Commentable + Sized + Send + Sync + Unpin + for<'r> sqlx::FromRow<'r, PgRow> {
+    /// Name of the kind in table names, URLs and the `parent_type` of comments and reviews.
+    const KIND: &'static str;
+    /// Columns of the kind besides the shared ones, prefixed with the `s` alias.
+    const COLUMNS: &'static str;
+    /// Inputs of the admin forms besides name, description and organization.
+    const FORM_FIELDS: &'static [FormField];
+
+    fn display_name(&self) -> &str;
+
+    fn description(&self) -> Option<&str>;
+
+    fn karma(&self) -> &Karma;
+
+    fn organization(&self) -> Option<&Organization>;
+
+    /// Facts about the subject shown on its page, as the key of the label in the Fluent bundles and the value.
+    fn details(&self) -> Vec<(&'static str, String)>;
+
+    /// Current value of one of the [`Reviewable::FORM_FIELDS`], empty if it is not set.
+    fn form_value(&self, name: &str) -> String;
+
+    /// New subject from a submitted admin form, or `existing` with the form written to it.
+    /// Fails with a [`PodelError::UserError`] if a kind-specific field is missing or invalid.
+    fn from_form(existing: Option<Self>, form: SubjectForm) -> Result<Self, PodelError>;
+
+    /// Inserts the row or updates all of its columns. Called by [`Reviewable::save`] after the organization is saved.
+    async fn upsert(&self, transaction: &mut sqlx::Transaction<'_, Postgres>) -> Result<(), sqlx::Error>;
+
+    fn get_query() -> String {
+        format!(r#"SELECT
+            s.id,
+            s.karma,
+            s.name,
+            s.description,
+            {},
+            u.id as user_id,
+            u.email as user_email,
+            u.password_hash as user_password_hash,
+            u.language as user_language,
+            u.name as user_name,
+            u.is_admin as user_is_admin,
+            u.is_active as user_is_active,
+            u.is_verified as user_is_verified,
+            u.last_login as user_last_login,
+            u.created_at as user_created_at,
+            u.updated_at as user_updated_at,
+            o.id as org_id,
+            o.name as org_name,
+            f.country as org_form_country,
+            f.code as org_form_code,
+            f.abbreviation as org_form_abbreviation,
+            o.ico as org_ico,
+            o.dic as org_dic,
+            o.address as org_address,
+            o.created_at as org_created_at,
+            o.updated_at as org_updated_at
+        FROM subject.{} s
+        LEFT JOIN subject.organization o ON s.organization_id = o.id
+        LEFT JOIN subject.legal_form f ON o.form_country = f.country AND o.form_code = f.code
+        LEFT JOIN auth.user u ON o.user_id = u.id
+        "#, Self::COLUMNS, Self::KIND)
+    }
+
+    async fn find<I: Into<String> + Send>(pool: &Pool<Postgres>, id: I) -> Result<Self, PodelError> {
+        let subject = sqlx::query_as::<Postgres, Self>(&format!("{} WHERE s.id = $1", Self::get_query()))
+            .bind(id.into())
+            .fetch_one(pool)
+            .await?;
+
+        Ok(subject)
+    }
+
+    async fn find_by_organization<I: Into<String> + Send>(
+        pool: &Pool<Postgres>,
+        organization_id: I,
+    ) -> Result<Vec<Self>, PodelError> {
+        let subjects = sqlx::query_as::<Postgres, Self>(
+            &format!("{} WHERE s.organization_id = $1 ORDER BY s.name", Self::get_query()),
+        )
+        .bind(organization_id.into())
+        .fetch_all(pool)
+        .await?;
+
+        Ok(subjects)
+    }
+
+    async fn find_multiple(pool: &Pool<Postgres>, limit: i64, offset: i64) -> Result<Vec<Self>, PodelError> {
+        let subjects = sqlx::query_as::<Postgres, Self>(
+            &format!(r#"
+                {}
+                ORDER BY s.created_at DESC
+                LIMIT $1
+                OFFSET $2"#,
+                Self::get_query()
+            ),
+        )
+        .bind(min(limit, 20))
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(subjects)
+    }
+
+    /// Bumps the visit counter used by the popularity part of karma.
+    async fn record_visit<I: Into<String> + Send>(pool: &Pool<Postgres>, id: I) -> Result<(), PodelError> {
+        sqlx::query(&format!("UPDATE subject.{} SET visits = visits + 1 WHERE id = $1", Self::KIND))
+            .bind(id.into())
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Inserts or updates the subject and its organization, recording the change in the audit log.
+    async fn save(&self, pool: &Pool<Postgres>, actor: Option<&Actor<'_>>) -> Result<(), PodelError> {
+        let table = format!("subject.{}", Self::KIND);
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, &table, self.id()).await?;
+
+        if let Some(organization) = self.organization() {
+            organization.upsert(&mut *transaction).await?;
+        }
+        self.upsert(&mut transaction).await?;
+
+        let after = AuditEvent::snapshot(&mut *transaction, &table, self.id()).await?;
+        let action = if before.is_some() { "update" } else { "create" };
+        AuditEvent::new(actor, action, table.as_str(), self.id())
+            .with_snapshots(before, after)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Organization {
     pub id: Ulid,
     pub name: String,
     pub form: LegalForm,
+    pub ico: Option<Ico>,
+    pub dic: Option<Dic>,
+    /// registered office
+    pub address: Option<String>,
     pub user: Option<User>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
@@ -23,41 +322,369 @@ impl Organization {
             id: ulid::Ulid::new().to_string(),
             name: name.into(),
             form,
+            ico: None,
+            dic: None,
+            address: None,
             user,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         }
     }
+
+    pub fn with_identifiers(mut self, ico: Option<Ico>, dic: Option<Dic>) -> Self {
+        self.ico = ico;
+        self.dic = dic;
+        self
+    }
+
+    pub fn with_address(mut self, address: Option<String>) -> Self {
+        self.address = address;
+        self
+    }
+
+    fn get_query() -> &'static str {
+        r#"SELECT
+            o.id,
+            o.name,
+            f.country as form_country,
+            f.code as form_code,
+            f.abbreviation as form_abbreviation,
+            o.ico,
+            o.dic,
+            o.address,
+            o.created_at,
+            o.updated_at,
+            u.id as user_id,
+            u.email as user_email,
+            u.password_hash as user_password_hash,
+            u.language as user_language,
+            u.name as user_name,
+            u.is_admin as user_is_admin,
+            u.is_active as user_is_active,
+            u.is_verified as user_is_verified,
+            u.last_login as user_last_login,
+            u.created_at as user_created_at,
+            u.updated_at as user_updated_at
+        FROM subject.organization o
+        JOIN subject.legal_form f ON o.form_country = f.country AND o.form_code = f.code
+        LEFT JOIN auth.user u ON o.user_id = u.id
+        "#
+    }
+
+    /// Organization of the id only. Everything that changes an organization looks it up this way.
+    pub async fn find_by_id(pool: &Pool<Postgres>, id: impl Into<String>) -> Result<Organization, PodelError> {
+        let organization = sqlx::query_as::<Postgres, Organization>(&format!("{} WHERE o.id = $1", Self::get_query()))
+            .bind(id.into())
+            .fetch_one(pool)
+            .await?;
+
+        Ok(organization)
+    }
+
+    pub async fn find_by_ico(pool: &Pool<Postgres>, ico: &Ico) -> Result<Organization, PodelError> {
+        let organization = sqlx::query_as::<Postgres, Organization>(&format!("{} WHERE o.ico = $1", Self::get_query()))
+            .bind(ico.as_str())
+            .fetch_one(pool)
+            .await?;
+
+        Ok(organization)
+    }
+
+    /// Oldest organization of the name, names don't have to be unique.
+    pub async fn find_by_name(pool: &Pool<Postgres>, name: impl Into<String>) -> Result<Organization, PodelError> {
+        let organization = sqlx::query_as::<Postgres, Organization>(&format!("{} WHERE o.name = $1 ORDER BY o.created_at LIMIT 1", Self::get_query()))
+            .bind(name.into())
+            .fetch_one(pool)
+            .await?;
+
+        Ok(organization)
+    }
+
+    /// Organization for a public link, which may use its id, IČO or name, tried in that order.
+    pub async fn find_public(pool: &Pool<Postgres>, key: &str) -> Result<Organization, PodelError> {
+        match Self::find_by_id(pool, key).await {
+            Err(PodelError::SqlxError(sqlx::Error::RowNotFound)) => {}
+            result => return result,
+        }
+        if let Ok(ico) = key.parse::<Ico>() {
+            match Self::find_by_ico(pool, &ico).await {
+                Err(PodelError::SqlxError(sqlx::Error::RowNotFound)) => {}
+                result => return result,
+            }
+        }
+        Self::find_by_name(pool, key).await
+    }
+
+    pub async fn find_multiple(
+        pool: &Pool<Postgres>,
+        limit: i64,
+        offset: i64
+    ) -> Result<Vec<Organization>, PodelError> {
+        let organizations = sqlx::query_as::<Postgres, Organization>(
+            &format!(r#"
+                {}
+                ORDER BY o.created_at DESC
+                LIMIT $1
+                OFFSET $2"#,
+                Self::get_query()
+            ),
+        )
+        .bind(min(limit, 20))
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(organizations)
+    }
+
+    /// Every organization, by name.
+    pub async fn find_all(pool: &Pool<Postgres>) -> Result<Vec<Organization>, PodelError> {
+        let organizations = sqlx::query_as::<Postgres, Organization>(&format!("{} ORDER BY o.name", Self::get_query()))
+            .fetch_all(pool)
+            .await?;
+
+        Ok(organizations)
+    }
+
+    /// Inserts the organization or updates its name, form, identifiers, address and owner, and records it
+    /// in the audit log. Fails with a unique violation if another organization has the same IČO or DIČ.
+    pub async fn save(&self, pool: &Pool<Postgres>, actor: Option<&Actor<'_>>) -> Result<(), PodelError> {
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, "subject.organization", &self.id).await?;
+
+        self.upsert(&mut *transaction).await?;
+
+        let after = AuditEvent::snapshot(&mut *transaction, "subject.organization", &self.id).await?;
+        let action = if before.is_some() { "update" } else { "create" };
+        AuditEvent::new(actor, action, "subject.organization", &self.id)
+            .with_snapshots(before, after)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Writes the organization without an audit record, for saves that are part of a larger audited change.
+    async fn upsert<'e, E>(&self, executor: E) -> Result<(), sqlx::Error>
+    where
+        E: sqlx::Executor<'e, Database = Postgres>,
+    {
+        executor.execute(sqlx::query(r#"
+                INSERT INTO subject.organization (id, name, form_country, form_code, ico, dic, address, user_id)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (id) DO UPDATE
+                SET name = EXCLUDED.name,
+                    form_country = EXCLUDED.form_country,
+                    form_code = EXCLUDED.form_code,
+                    ico = EXCLUDED.ico,
+                    dic = EXCLUDED.dic,
+                    address = EXCLUDED.address,
+                    user_id = EXCLUDED.user_id,
+                    updated_at = CURRENT_TIMESTAMP
+            "#)
+            .bind(&self.id)
+            .bind(&self.name)
+            .bind(&self.form.country)
+            .bind(&self.form.code)
+            .bind(&self.ico)
+            .bind(&self.dic)
+            .bind(&self.address)
+            .bind(self.user.as_ref().map(|user| &user.id))
+        ).await?;
+
+        Ok(())
+    }
+
+    /// Deletes the organization. Its subjects are kept without an organization.
+    pub async fn delete(pool: &Pool<Postgres>, actor: &Actor<'_>, id: impl Into<String>) -> Result<(), PodelError> {
+        let id = id.into();
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, "subject.organization", &id).await?;
+
+        let result = sqlx::query("DELETE FROM subject.organization WHERE id = $1")
+            .bind(&id)
+            .execute(&mut *transaction)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(PodelError::Empty());
+        }
+
+        AuditEvent::new(Some(actor), "delete", "subject.organization", &id)
+            .with_snapshots(before, None)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Moves the subjects of `source_id` to `target_id` and deletes the source.
+    /// The target keeps its owner, or takes over the one of the source if it has none.
+    pub async fn merge_into(
+        pool: &Pool<Postgres>,
+        actor: &Actor<'_>,
+        source_id: impl Into<String>,
+        target_id: impl Into<String>,
+    ) -> Result<(), PodelError> {
+        let (source_id, target_id) = (source_id.into(), target_id.into());
+        if source_id == target_id {
+            return Err(PodelError::UserError("An organization can't be merged into itself.".into()));
+        }
+
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, "subject.organization", &source_id).await?;
+
+        let result = sqlx::query(r#"
+                UPDATE subject.organization
+                SET user_id = COALESCE(user_id, (SELECT user_id FROM subject.organization WHERE id = $1)),
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = $2 AND EXISTS (SELECT 1 FROM subject.organization WHERE id = $1)
+            "#)
+            .bind(&source_id)
+            .bind(&target_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(PodelError::Empty());
+        }
+
+        for kind in KINDS {
+            sqlx::query(&format!("UPDATE subject.{kind} SET organization_id = $2 WHERE organization_id = $1"))
+                .bind(&source_id)
+                .bind(&target_id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        sqlx::query("DELETE FROM subject.organization WHERE id = $1")
+            .bind(&source_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        AuditEvent::new(Some(actor), "merge", "subject.organization", &source_id)
+            .with_snapshots(before, Some(serde_json::json!({ "merged_into": target_id })))
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub fn is_owned_by(&self, user: &User) -> bool {
+        self.user.as_ref().is_some_and(|owner| owner.id == user.id)
+    }
+
+    /// Organization of a subject read by [`Reviewable::get_query`], `None` if it has none.
+    pub(crate) fn from_joined_row(row: &PgRow) -> Result<Option<Organization>, sqlx::Error> {
+        let Some(id) = row.try_get::<String, _>("org_id").ok() else {
+            return Ok(None);
+        };
+
+        Ok(Some(Organization {
+            id,
+            name: row.try_get("org_name")?,
+            form: LegalForm {
+                country: row.try_get("org_form_country")?,
+                code: row.try_get("org_form_code")?,
+                abbreviation: row.try_get("org_form_abbreviation")?,
+            },
+            ico: row.try_get("org_ico")?,
+            dic: row.try_get("org_dic")?,
+            address: row.try_get("org_address")?,
+            user: User::from_row(row).ok(),
+            created_at: row.try_get("org_created_at")?,
+            updated_at: row.try_get("org_updated_at")?,
+        }))
+    }
 }
 
-/// Represents the type of legal entity.
-#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[sqlx(type_name = "subject.legal_form")]
-pub enum LegalForm {
-    /// Společnost s ručením omezením (Limited Liability Company)
-    Sro,
-    /// Akciová společnost (Joint Stock Company)
-    As,
-    /// Veřejná obchodní společnost (General Partnership)
-    Vos,
-    /// Zapsaný spolek (Registered Association)
-    Spolek,
-    /// Nadace (Foundation)
-    Nadace,
-    /// Družstvo (Cooperative)
-    Druzstvo,
+impl sqlx::FromRow<'_, PgRow> for Organization {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Organization {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            form: LegalForm {
+                country: row.try_get("form_country")?,
+                code: row.try_get("form_code")?,
+                abbreviation: row.try_get("form_abbreviation")?,
+            },
+            ico: row.try_get("ico")?,
+            dic: row.try_get("dic")?,
+            address: row.try_get("address")?,
+            user: User::from_row(row).ok(),
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+/// Type of legal entity as defined by the law of a country, e.g. a Czech s.r.o. or a German GmbH.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegalForm {
+    /// ISO 3166-1 alpha-2 code, e.g. `CZ`
+    pub country: String,
+    /// Code in the country's code list, e.g. `112` for a Czech s.r.o.
+    pub code: String,
+    /// Suffix the form adds to names, e.g. `s.r.o.`
+    pub abbreviation: String,
+}
+
+impl LegalForm {
+    fn get_query() -> &'static str {
+        "SELECT f.country, f.code, f.abbreviation FROM subject.legal_form f"
+    }
+
+    /// Every known form, by country and code.
+    pub async fn find_all(pool: &Pool<Postgres>) -> Result<Vec<LegalForm>, PodelError> {
+        let forms = sqlx::query_as::<Postgres, LegalForm>(&format!("{} ORDER BY f.country, f.code", Self::get_query()))
+            .fetch_all(pool)
+            .await?;
+
+        Ok(forms)
+    }
+
+    pub async fn find(
+        pool: &Pool<Postgres>,
+        country: impl Into<String>,
+        code: impl Into<String>,
+    ) -> Result<LegalForm, PodelError> {
+        let form = sqlx::query_as::<Postgres, LegalForm>(&format!("{} WHERE f.country = $1 AND f.code = $2", Self::get_query()))
+            .bind(country.into())
+            .bind(code.into())
+            .fetch_one(pool)
+            .await?;
+
+        Ok(form)
+    }
+
+    /// Form by the value sent by forms, e.g. `CZ-112`.
+    pub async fn find_by_id(pool: &Pool<Postgres>, id: &str) -> Result<LegalForm, PodelError> {
+        let (country, code) = id.split_once('-').ok_or(PodelError::Empty())?;
+        Self::find(pool, country, code).await
+    }
+
+    /// Country and code, unique among the forms.
+    pub fn id(&self) -> String {
+        format!("{}-{}", self.country, self.code)
+    }
+
+    /// Key of the localized name in the Fluent bundles.
+    pub fn fluent_key(&self) -> String {
+        format!("legal-form-{}-{}", self.country, self.code).to_lowercase()
+    }
+
+    /// Key of the localized name of the country in the Fluent bundles.
+    pub fn country_fluent_key(&self) -> String {
+        format!("country-{}", self.country.to_lowercase())
+    }
 }
 
 impl fmt::Display for LegalForm {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            LegalForm::Sro => write!(f, "Společnost s ručením omezením"),
-            LegalForm::As => write!(f, "Akciová společnost"),
-            LegalForm::Vos => write!(f, "Veřejná obchodní společnost"),
-            LegalForm::Spolek => write!(f, "Zapsaný spolek"),
-            LegalForm::Nadace => write!(f, "Nadace"),
-            LegalForm::Druzstvo => write!(f, "Družstvo"),
-        }
+        write!(f, "{}", self.id())
     }
 }
 
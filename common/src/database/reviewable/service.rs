@@ -0,0 +1,128 @@
+use crate::database::reviewable::{FormField, Karma, Organization, Reviewable, SubjectForm};
+use crate::database::Ulid;
+use crate::PodelError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{Postgres, Row};
+use crate::database::comment::Commentable;
+
+/// Place customers visit in person, e.g. a shop, restaurant or workshop.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Service {
+    pub id: Ulid,
+    pub karma: Karma,
+    pub organization: Option<Organization>,
+    pub name: String,
+    pub description: Option<String>,
+    pub address: String,
+}
+
+impl sqlx::FromRow<'_, PgRow> for Service {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Service {
+            id: row.try_get("id")?,
+            karma: row.try_get("karma")?,
+            organization: Organization::from_joined_row(row)?,
+            name: row.try_get("name")?,
+            description: row.try_get("description")?,
+            address: row.try_get("address")?,
+        })
+    }
+}
+
+impl Service {
+    pub fn new(
+        name: impl Into<String>,
+        address: impl Into<String>,
+        description: Option<impl Into<String>>,
+        organization: Option<Organization>
+    ) -> Self {
+        Self {
+            id: ulid::Ulid::new().to_string(),
+            karma: Karma::new(),
+            organization,
+            name: name.into(),
+            description: description.map(|o| o.into()),
+            address: address.into(),
+        }
+    }
+}
+
+impl Commentable for Service {
+    fn id(&self) -> &Ulid {
+        &self.id
+    }
+}
+
+#[async_trait]
+impl Reviewable for Service {
+    const KIND: &'static str = "service";
+    const COLUMNS: &'static str = "s.address";
+    const FORM_FIELDS: &'static [FormField] = &[
+        FormField { name: "address", label: "service-address", required: true, options: &[] },
+    ];
+
+    fn display_name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    fn karma(&self) -> &Karma {
+        &self.karma
+    }
+
+    fn organization(&self) -> Option<&Organization> {
+        self.organization.as_ref()
+    }
+
+    fn details(&self) -> Vec<(&'static str, String)> {
+        vec![("service-address", self.address.clone())]
+    }
+
+    fn form_value(&self, name: &str) -> String {
+        match name {
+            "address" => self.address.clone(),
+            _ => String::new(),
+        }
+    }
+
+    fn from_form(existing: Option<Self>, form: SubjectForm) -> Result<Self, PodelError> {
+        let address = form.required("address")?;
+        Ok(match existing {
+            Some(subject) => Self {
+                name: form.name,
+                description: form.description,
+                organization: form.organization,
+                address,
+                ..subject
+            },
+            None => Self::new(form.name, address, form.description, form.organization),
+        })
+    }
+
+    async fn upsert(&self, transaction: &mut sqlx::Transaction<'_, Postgres>) -> Result<(), sqlx::Error> {
+        sqlx::query(r#"
+                INSERT INTO subject.service (id, organization_id, name, description, address)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (id) DO UPDATE
+                SET organization_id = EXCLUDED.organization_id,
+                    name = EXCLUDED.name,
+                    description = EXCLUDED.description,
+                    address = EXCLUDED.address,
+                    updated_at = CURRENT_TIMESTAMP
+            "#)
+            .bind(&self.id)
+            .bind(self.organization.as_ref().map(|organization| &organization.id))
+            .bind(&self.name)
+            .bind(&self.description)
+            .bind(&self.address)
+            .execute(&mut **transaction)
+            .await?;
+
+        Ok(())
+    }
+}
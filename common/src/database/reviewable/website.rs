@@ -1,14 +1,14 @@
-use std::cmp::min;
-use crate::database::reviewable::{Karma, Organization};
-use crate::database::user::User;
+use crate::database::audit::{Actor, AuditEvent};
+use crate::database::reviewable::{FormField, Karma, Organization, Reviewable, SubjectForm};
 use crate::database::Ulid;
 use crate::PodelError;
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgRow;
-use sqlx::{Executor, Pool, Postgres, Row};
+use sqlx::{Pool, Postgres, Row};
 use crate::database::comment::Commentable;
 
-#[derive(sqlx::Type, Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Website {
     pub id: Ulid,
     pub karma: Karma,
@@ -20,25 +20,10 @@ pub struct Website {
 
 impl sqlx::FromRow<'_, PgRow> for Website {
     fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
-        let organization = if let Some(organization_id) = row.try_get::<String, _>("org_id").ok() {
-            let user = User::from_row(row).ok();
-
-            Some(Organization {
-                id: organization_id,
-                name: row.try_get("org_name")?,
-                form: row.try_get("org_form")?,
-                user,
-                created_at: row.try_get("org_created_at")?,
-                updated_at: row.try_get("org_updated_at")?
-            })
-        } else {
-            None
-        };
-
         Ok(Website {
             id: row.try_get("id")?,
             karma: row.try_get("karma")?,
-            organization,
+            organization: Organization::from_joined_row(row)?,
             name: row.try_get("name")?,
             domain_name: row.try_get("domain_name")?,
             description: row.try_get("description").ok(),
@@ -70,107 +55,148 @@ impl Website {
         }
     }
 
-    fn get_query() -> &'static str {
-        r#"SELECT
-            w.id,
-            w.karma,
-            u.id as user_id,
-            u.email as user_email,
-            u.password_hash as user_password_hash,
-            u.language as user_language,
-            u.name as user_name,
-            u.is_admin as user_is_admin,
-            u.is_active as user_is_active,
-            u.is_verified as user_is_verified,
-            u.last_login as user_last_login,
-            u.created_at as user_created_at,
-            u.updated_at as user_updated_at,
-            o.id as org_id,
-            o.name as org_name,
-            o.form as org_form,
-            o.created_at as org_created_at,
-            o.updated_at as org_updated_at,
-            w.name,
-            w.domain_name,
-            w.description
-        FROM subject.website w
-        LEFT JOIN subject.organization o ON w.organization_id = o.id
-        LEFT JOIN auth.user u ON o.user_id = u.id
-        "#
+    pub fn organization_id(&self) -> Option<&str> {
+        self.organization.as_ref().map(|organization| organization.id.as_str())
     }
 
-    pub async fn find(pool: &Pool<Postgres>, id: impl Into<String>) -> Result<Website, PodelError> {
-        let website = sqlx::query_as::<Postgres, Website>(&format!("{} WHERE w.id = $1 OR w.name = $1", Self::get_query()))
-        .bind(id.into())
-        .fetch_one(pool)
-        .await?;
+    /// Moves comments, reviews and visits of `source_id` to `target_id` and deletes the source.
+    /// Reviews of users who already reviewed the target are dropped.
+    pub async fn merge_into(
+        pool: &Pool<Postgres>,
+        actor: &Actor<'_>,
+        source_id: impl Into<String>,
+        target_id: impl Into<String>,
+    ) -> Result<(), PodelError> {
+        let (source_id, target_id) = (source_id.into(), target_id.into());
+        if source_id == target_id {
+            return Err(PodelError::UserError("A website can't be merged into itself.".into()));
+        }
+
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, "subject.website", &source_id).await?;
+
+        sqlx::query("UPDATE comment.website SET parent_id = $2 WHERE parent_id = $1")
+            .bind(&source_id)
+            .bind(&target_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query(r#"
+                DELETE FROM review.website r
+                WHERE r.parent_id = $1
+                  AND EXISTS (SELECT 1 FROM review.website t WHERE t.parent_id = $2 AND t.user_id = r.user_id)
+            "#)
+            .bind(&source_id)
+            .bind(&target_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query("UPDATE review.website SET parent_id = $2 WHERE parent_id = $1")
+            .bind(&source_id)
+            .bind(&target_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        let result = sqlx::query(r#"
+                UPDATE subject.website
+                SET visits = visits + (SELECT visits FROM subject.website WHERE id = $1), updated_at = CURRENT_TIMESTAMP
+                WHERE id = $2 AND EXISTS (SELECT 1 FROM subject.website WHERE id = $1)
+            "#)
+            .bind(&source_id)
+            .bind(&target_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(PodelError::Empty());
+        }
+
+        sqlx::query("DELETE FROM subject.website WHERE id = $1")
+            .bind(&source_id)
+            .execute(&mut *transaction)
+            .await?;
 
-        Ok(website)
+        // The target is the only "after" a merged website has.
+        AuditEvent::new(Some(actor), "merge", "subject.website", &source_id)
+            .with_snapshots(before, Some(serde_json::json!({ "merged_into": target_id })))
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
     }
+}
 
-    pub async fn find_multiple(
-        pool: &Pool<Postgres>,
-        limit: i64,
-        offset: i64
-    ) -> Result<Vec<Website>, PodelError> {
-        let websites = sqlx::query_as::<Postgres, Website>(
-            &format!(r#"
-                {}
-                ORDER BY w.created_at DESC
-                LIMIT $1
-                OFFSET $2"#,
-                Self::get_query()
-            ),
-        )
-        .bind(min(limit, 20))
-        .bind(offset)
-        .fetch_all(pool)
-        .await?;
-
-        Ok(websites)
+impl Commentable for Website {
+    fn id(&self) -> &Ulid {
+        &self.id
     }
+}
 
-    pub async fn save(&self, pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
-        let mut transaction = pool.begin().await?;
+#[async_trait]
+impl Reviewable for Website {
+    const KIND: &'static str = "website";
+    const COLUMNS: &'static str = "s.domain_name";
+    const FORM_FIELDS: &'static [FormField] = &[
+        FormField { name: "domain_name", label: "domain", required: true, options: &[] },
+    ];
+
+    fn display_name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    fn karma(&self) -> &Karma {
+        &self.karma
+    }
+
+    fn organization(&self) -> Option<&Organization> {
+        self.organization.as_ref()
+    }
+
+    fn details(&self) -> Vec<(&'static str, String)> {
+        vec![("domain", self.domain_name.clone())]
+    }
+
+    fn form_value(&self, name: &str) -> String {
+        match name {
+            "domain_name" => self.domain_name.clone(),
+            _ => String::new(),
+        }
+    }
+
+    fn from_form(existing: Option<Self>, form: SubjectForm) -> Result<Self, PodelError> {
+        let domain_name = form.domain("domain_name")?;
+        Ok(match existing {
+            Some(subject) => Self {
+                name: form.name,
+                description: form.description,
+                organization: form.organization,
+                domain_name,
+                ..subject
+            },
+            None => Self::new(form.name, domain_name, form.description, form.organization),
+        })
+    }
 
-        let organization_id = if let Some(org) = &self.organization {
-            transaction.execute(sqlx::query(r#"
-                    INSERT INTO subject.organization (id, name, form, user_id)
-                    VALUES ($1, $2, $3, $4)
-                    ON CONFLICT (id) DO UPDATE
-                    SET form = EXCLUDED.form, user_id = EXCLUDED.user_id, updated_at = CURRENT_TIMESTAMP
-                "#)
-                .bind(&org.id)
-                .bind(&org.name)
-                .bind(&org.form)
-                .bind(&org.user.as_ref().map(|user| &user.id))
-            ).await?;
-
-            Some(&org.id)
-        } else {
-            None
-        };
-
-        transaction.execute(sqlx::query(r#"
+    async fn upsert(&self, transaction: &mut sqlx::Transaction<'_, Postgres>) -> Result<(), sqlx::Error> {
+        sqlx::query(r#"
                 INSERT INTO subject.website (id, organization_id, name, domain_name, description)
                 VALUES ($1, $2, $3, $4, $5)
                 ON CONFLICT (id) DO UPDATE
                 SET organization_id = EXCLUDED.organization_id, name = EXCLUDED.name, domain_name = EXCLUDED.domain_name, description = EXCLUDED.description, updated_at = CURRENT_TIMESTAMP
             "#)
             .bind(&self.id)
-            .bind(&organization_id)
+            .bind(self.organization_id())
             .bind(&self.name)
             .bind(&self.domain_name)
             .bind(&self.description)
-        ).await?;
+            .execute(&mut **transaction)
+            .await?;
 
-        transaction.commit().await?;
         Ok(())
     }
-}
-
-impl Commentable for Website {
-    fn id(&self) -> &Ulid {
-        &self.id
-    }
 }
\ No newline at end of file
@@ -0,0 +1,246 @@
+use std::cmp::min;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use crate::database::Ulid;
+use crate::PodelError;
+
+/// Maximum number of words taken from a search query.
+const MAX_QUERY_WORDS: usize = 8;
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Website,
+    Organization,
+    User,
+}
+
+impl SearchKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchKind::Website => "website",
+            SearchKind::Organization => "organization",
+            SearchKind::User => "user",
+        }
+    }
+
+    /// Link to the detail page of a result of this kind.
+    pub fn url(&self, id: &str) -> String {
+        match self {
+            SearchKind::Website => format!("/web/{id}"),
+            SearchKind::Organization => format!("/org/{id}"),
+            SearchKind::User => format!("/user/{id}"),
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, Clone, Debug, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub kind: SearchKind,
+    pub id: Ulid,
+    pub title: String,
+    /// Domain of a website, legal form of an organization.
+    pub subtitle: Option<String>,
+    pub rank: f32,
+}
+
+impl SearchResult {
+    /// Ranked websites, organizations and users matching all words of `query` as prefixes,
+    /// regardless of diacritics and case.
+    pub async fn find_multiple(
+        pool: &Pool<Postgres>,
+        query: &str,
+        limit: i64,
+        offset: i64
+    ) -> Result<Vec<Self>, PodelError> {
+        let Some(ts_query) = to_prefix_query(query) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(
+            sqlx::query_as::<Postgres, SearchResult>(r#"
+                WITH q AS (SELECT to_tsquery('podela', $1) AS query)
+                SELECT 'website' as kind, w.id, w.name as title, w.domain_name as subtitle, ts_rank(w.search, q.query) as rank
+                FROM subject.website w, q
+                WHERE w.search @@ q.query
+                UNION ALL
+                SELECT 'organization' as kind, o.id, o.name as title, f.abbreviation as subtitle, ts_rank(o.search, q.query) as rank
+                FROM subject.organization o
+                JOIN subject.legal_form f ON o.form_country = f.country AND o.form_code = f.code, q
+                WHERE o.search @@ q.query
+                UNION ALL
+                SELECT 'user' as kind, u.id, u.name as title, NULL as subtitle, ts_rank(u.search, q.query) as rank
+                FROM auth.user u, q
+                WHERE u.search @@ q.query AND u.is_active
+                ORDER BY rank DESC, title
+                LIMIT $2
+                OFFSET $3
+            "#)
+            .bind(ts_query)
+            .bind(min(limit, 20))
+            .bind(offset)
+            .fetch_all(pool)
+            .await?
+        )
+    }
+
+    /// Title with words matching the query wrapped in `<mark>`, HTML-escaped.
+    pub fn highlighted_title(&self, query: &str) -> String {
+        highlight(&self.title, query)
+    }
+}
+
+/// Words of a user-supplied query, lowercased and without diacritics.
+pub fn query_terms(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .take(MAX_QUERY_WORDS)
+        .map(|word| word.chars().map(fold).collect())
+        .collect()
+}
+
+/// Turns free text into a `tsquery` matching all words as prefixes, e.g. `spol s` -> `spol:* & s:*`.
+/// Only alphanumeric characters survive, so the result is always valid `tsquery` syntax.
+pub fn to_prefix_query(query: &str) -> Option<String> {
+    let terms = query_terms(query);
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.iter().map(|term| format!("{term}:*")).collect::<Vec<_>>().join(" & "))
+    }
+}
+
+/// HTML-escapes `text` and wraps the parts of words starting with any of the query terms in `<mark>`.
+/// Matching ignores case and Czech and Slovak diacritics, same as the database search.
+pub fn highlight(text: &str, query: &str) -> String {
+    let terms = query_terms(query);
+    let chars: Vec<char> = text.chars().collect();
+    let mut highlighted = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let is_word_start = chars[i].is_alphanumeric() && (i == 0 || !chars[i - 1].is_alphanumeric());
+        let matched = if is_word_start {
+            terms
+                .iter()
+                .map(|term| term.chars().count())
+                .filter(|&len| i + len <= chars.len())
+                .filter(|&len| {
+                    let folded: String = chars[i..i + len].iter().copied().map(fold).collect();
+                    terms.contains(&folded)
+                })
+                .max()
+        } else {
+            None
+        };
+
+        if let Some(len) = matched {
+            highlighted.push_str("<mark>");
+            chars[i..i + len].iter().for_each(|&c| push_escaped(&mut highlighted, c));
+            highlighted.push_str("</mark>");
+            i += len;
+        } else {
+            push_escaped(&mut highlighted, chars[i]);
+            i += 1;
+        }
+    }
+
+    highlighted
+}
+
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        '\'' => out.push_str("&#x27;"),
+        _ => out.push(c),
+    }
+}
+
+/// Lowercases a character and strips Czech and Slovak diacritics.
+fn fold(c: char) -> char {
+    match c.to_lowercase().next().unwrap_or(c) {
+        'á' | 'ä' => 'a',
+        'č' => 'c',
+        'ď' => 'd',
+        'é' | 'ě' => 'e',
+        'í' => 'i',
+        'ĺ' | 'ľ' => 'l',
+        'ň' => 'n',
+        'ó' | 'ô' | 'ö' => 'o',
+        'ŕ' | 'ř' => 'r',
+        'š' => 's',
+        'ť' => 't',
+        'ú' | 'ů' | 'ü' => 'u',
+        'ý' => 'y',
+        'ž' => 'z',
+        lower => lower,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlight_escapes_html() {
+        assert_eq!(highlight(r#"<b>&"'</b>"#, ""), "&lt;b&gt;&amp;&quot;&#x27;&lt;/b&gt;");
+    }
+
+    #[test]
+    fn highlight_escapes_inside_and_around_matches() {
+        assert_eq!(highlight("x <script>", "<script>"), "x &lt;<mark>script</mark>&gt;");
+        assert_eq!(highlight(r#""onmouseover='a'"#, "onmouseover"), "&quot;<mark>onmouseover</mark>=&#x27;a&#x27;");
+    }
+
+    #[test]
+    fn highlight_ignores_case_and_diacritics() {
+        assert_eq!(highlight("Společnost s.r.o.", "spolecnost"), "<mark>Společnost</mark> s.r.o.");
+        assert_eq!(highlight("spolecnost", "SPOLEČNOST"), "<mark>spolecnost</mark>");
+    }
+
+    #[test]
+    fn highlight_marks_word_prefixes_only() {
+        assert_eq!(highlight("Společnost", "spol"), "<mark>Spol</mark>ečnost");
+        assert_eq!(highlight("Pospolu", "spol"), "Pospolu");
+    }
+
+    #[test]
+    fn highlight_prefers_the_longest_term() {
+        assert_eq!(highlight("Spolek", "s spolek"), "<mark>Spolek</mark>");
+    }
+
+    #[test]
+    fn fold_strips_czech_and_slovak_diacritics() {
+        let folded: String = "ŽLUŤOUČKÝ kůň úpěl ďábelské ódy, ĽÔŔ".chars().map(fold).collect();
+        assert_eq!(folded, "zlutoucky kun upel dabelske ody, lor");
+    }
+
+    #[test]
+    fn prefix_query_of_words() {
+        assert_eq!(to_prefix_query("spol s").as_deref(), Some("spol:* & s:*"));
+        assert_eq!(to_prefix_query("Žluťoučký  kůň").as_deref(), Some("zlutoucky:* & kun:*"));
+    }
+
+    #[test]
+    fn prefix_query_drops_tsquery_syntax() {
+        assert_eq!(to_prefix_query("a:*|b & !c <-> (d)").as_deref(), Some("a:* & b:* & c:* & d:*"));
+    }
+
+    #[test]
+    fn prefix_query_of_empty_or_punctuation_only_input() {
+        assert_eq!(to_prefix_query(""), None);
+        assert_eq!(to_prefix_query("   "), None);
+        assert_eq!(to_prefix_query("!!! ... &|:* <->"), None);
+    }
+
+    #[test]
+    fn prefix_query_is_limited_in_words() {
+        let query = to_prefix_query("a b c d e f g h i j").unwrap();
+        assert_eq!(query.split(" & ").count(), MAX_QUERY_WORDS);
+    }
+}
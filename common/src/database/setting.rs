@@ -0,0 +1,55 @@
+use sqlx::{Pool, Postgres};
+use crate::database::audit::{Actor, AuditEvent};
+use crate::PodelError;
+
+/// Site-wide switches admins can change at runtime.
+pub struct Setting;
+
+impl Setting {
+    /// Admins without two-factor authentication lose their privileges until they enable it.
+    pub const REQUIRE_ADMIN_TWO_FACTOR: &'static str = "require_admin_2fa";
+
+    /// Value of a boolean setting, `false` if it was never set.
+    pub async fn get_bool(pool: &Pool<Postgres>, key: &str) -> Result<bool, PodelError> {
+        let value = sqlx::query_scalar::<Postgres, String>("SELECT value FROM setting WHERE key = $1")
+            .bind(key)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(value.is_some_and(|value| value == "true"))
+    }
+
+    pub async fn set_bool(pool: &Pool<Postgres>, actor: &Actor<'_>, key: &str, value: bool) -> Result<(), PodelError> {
+        let mut transaction = pool.begin().await?;
+        let before = Self::snapshot(&mut transaction, key).await?;
+
+        sqlx::query(r#"
+                INSERT INTO setting (key, value, updated_at)
+                VALUES ($1, $2, CURRENT_TIMESTAMP)
+                ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = EXCLUDED.updated_at
+            "#)
+            .bind(key)
+            .bind(value.to_string())
+            .execute(&mut *transaction)
+            .await?;
+
+        let after = Self::snapshot(&mut transaction, key).await?;
+        AuditEvent::new(Some(actor), "update", "setting", key)
+            .with_snapshots(before, after)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Settings are keyed by name rather than id, so [`AuditEvent::snapshot`] can't read them.
+    async fn snapshot(transaction: &mut sqlx::Transaction<'_, Postgres>, key: &str) -> Result<Option<serde_json::Value>, PodelError> {
+        Ok(
+            sqlx::query_scalar::<Postgres, serde_json::Value>("SELECT to_jsonb(t) FROM setting t WHERE t.key = $1")
+                .bind(key)
+                .fetch_optional(&mut **transaction)
+                .await?
+        )
+    }
+}
@@ -0,0 +1,136 @@
+use sqlx::{Pool, Postgres};
+use crate::database::audit::{Actor, AuditEvent};
+use crate::database::Ulid;
+use crate::PodelError;
+
+/// Period during which a user can't post content. Permanent suspensions are bans.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Suspension {
+    pub id: Ulid,
+    pub user_id: Ulid,
+    /// shown to the suspended user
+    pub reason: String,
+    /// admin who suspended the user
+    pub issued_by: Option<Ulid>,
+    /// `None` for a permanent ban
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub lifted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Suspension {
+    /// Suspension starting now. `duration` of `None` bans the user permanently.
+    pub fn new(
+        user_id: impl Into<Ulid>,
+        reason: impl Into<String>,
+        issued_by: Option<impl Into<Ulid>>,
+        duration: Option<chrono::Duration>,
+    ) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            id: ulid::Ulid::new().to_string(),
+            user_id: user_id.into(),
+            reason: reason.into(),
+            issued_by: issued_by.map(Into::into),
+            expires_at: duration.map(|duration| now + duration),
+            lifted_at: None,
+            created_at: now,
+        }
+    }
+
+    pub const fn is_permanent(&self) -> bool {
+        self.expires_at.is_none()
+    }
+
+    pub async fn save(&self, pool: &Pool<Postgres>, actor: &Actor<'_>) -> Result<(), PodelError> {
+        let mut transaction = pool.begin().await?;
+
+        sqlx::query(r#"
+                INSERT INTO auth.suspension (id, user_id, reason, issued_by, expires_at, lifted_at, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#)
+            .bind(&self.id)
+            .bind(&self.user_id)
+            .bind(&self.reason)
+            .bind(&self.issued_by)
+            .bind(self.expires_at)
+            .bind(self.lifted_at)
+            .bind(self.created_at)
+            .execute(&mut *transaction)
+            .await?;
+
+        let action = if self.is_permanent() { "ban" } else { "suspend" };
+        let after = AuditEvent::snapshot(&mut *transaction, "auth.suspension", &self.id).await?;
+        AuditEvent::new(Some(actor), action, "auth.suspension", &self.id)
+            .with_snapshots(None, after)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Suspension in effect for the user right now, the longest one if there are several.
+    pub async fn find_active(pool: &Pool<Postgres>, user_id: impl Into<String>) -> Result<Option<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, Suspension>(r#"
+                SELECT id, user_id, reason, issued_by, expires_at, lifted_at, created_at
+                FROM auth.suspension
+                WHERE user_id = $1 AND lifted_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())
+                ORDER BY expires_at DESC NULLS FIRST
+                LIMIT 1
+            "#)
+            .bind(user_id.into())
+            .fetch_optional(pool)
+            .await?
+        )
+    }
+
+    /// Suspensions in effect for any of the given users.
+    pub async fn find_active_multiple(pool: &Pool<Postgres>, user_ids: &[Ulid]) -> Result<Vec<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, Suspension>(r#"
+                SELECT id, user_id, reason, issued_by, expires_at, lifted_at, created_at
+                FROM auth.suspension
+                WHERE user_id = ANY($1) AND lifted_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())
+                ORDER BY expires_at DESC NULLS FIRST
+            "#)
+            .bind(user_ids)
+            .fetch_all(pool)
+            .await?
+        )
+    }
+
+    /// Ends all suspensions of the user early. Returns the number of lifted suspensions.
+    pub async fn lift(pool: &Pool<Postgres>, actor: &Actor<'_>, user_id: impl Into<String>) -> Result<u64, PodelError> {
+        let mut transaction = pool.begin().await?;
+
+        let ids = sqlx::query_scalar::<Postgres, Ulid>(r#"
+                SELECT id
+                FROM auth.suspension
+                WHERE user_id = $1 AND lifted_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())
+                FOR UPDATE
+            "#)
+            .bind(user_id.into())
+            .fetch_all(&mut *transaction)
+            .await?;
+
+        for id in &ids {
+            let before = AuditEvent::snapshot(&mut *transaction, "auth.suspension", id).await?;
+
+            sqlx::query("UPDATE auth.suspension SET lifted_at = NOW() WHERE id = $1")
+                .bind(id)
+                .execute(&mut *transaction)
+                .await?;
+
+            let after = AuditEvent::snapshot(&mut *transaction, "auth.suspension", id).await?;
+            AuditEvent::new(Some(actor), "lift", "auth.suspension", id)
+                .with_snapshots(before, after)
+                .record(&mut *transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+        Ok(ids.len() as u64)
+    }
+}
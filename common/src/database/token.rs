@@ -0,0 +1,252 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::postgres::PgRow;
+use sqlx::{Pool, Postgres, Row};
+use crate::database::Ulid;
+use crate::database::user::{User, EFFECTIVE_IS_ADMIN};
+use crate::PodelError;
+
+/// Prefix of every token, makes leaked tokens easy to recognize.
+const TOKEN_PREFIX: &str = "pdl";
+
+/// What a personal access token is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    /// Read-only access.
+    Read,
+    /// Posting, editing and voting on comments.
+    WriteComments,
+    /// Everything the owner can do outside of `/admin`, which only accepts browser sessions.
+    Admin,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 3] = [TokenScope::Read, TokenScope::WriteComments, TokenScope::Admin];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::Read => "read",
+            TokenScope::WriteComments => "write_comments",
+            TokenScope::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for TokenScope {
+    type Err = PodelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TokenScope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| PodelError::UserError(format!("Unknown token scope: {s}.")))
+    }
+}
+
+/// Personal access token. Only the hash of its secret is stored.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: Ulid,
+    pub user_id: Ulid,
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl sqlx::FromRow<'_, PgRow> for ApiToken {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let scopes = row
+            .try_get::<Vec<String>, _>("scopes")?
+            .iter()
+            .filter_map(|scope| scope.parse().ok())
+            .collect();
+
+        Ok(ApiToken {
+            id: row.try_get("id")?,
+            user_id: row.try_get("token_user_id")?,
+            name: row.try_get("name")?,
+            scopes,
+            expires_at: row.try_get("expires_at")?,
+            last_used_at: row.try_get("last_used_at")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl ApiToken {
+    /// Creates a token and returns it together with its plaintext form, which is never shown again.
+    pub async fn create(
+        pool: &Pool<Postgres>,
+        user: &User,
+        name: impl Into<String>,
+        scopes: Vec<TokenScope>,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(Self, String), PodelError> {
+        if scopes.is_empty() {
+            return Err(PodelError::UserError("Token needs at least one scope.".into()));
+        }
+
+        let mut secret = [0_u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        let secret = URL_SAFE_NO_PAD.encode(secret);
+
+        let token = Self {
+            id: ulid::Ulid::new().into(),
+            user_id: user.id.clone(),
+            name: name.into(),
+            scopes,
+            expires_at,
+            last_used_at: None,
+            created_at: chrono::Utc::now(),
+        };
+
+        sqlx::query(r#"
+                INSERT INTO auth.api_token (
+                    id,
+                    user_id,
+                    name,
+                    token_hash,
+                    scopes,
+                    expires_at,
+                    created_at
+                )
+                VALUES (
+                    $1, $2, $3, $4, $5, $6, $7
+                )
+            "#)
+            .bind(&token.id)
+            .bind(&token.user_id)
+            .bind(&token.name)
+            .bind(hash_secret(&secret))
+            .bind(token.scopes.iter().map(TokenScope::as_str).collect::<Vec<_>>())
+            .bind(token.expires_at)
+            .bind(token.created_at)
+            .execute(pool)
+            .await?;
+
+        let plaintext = format!("{TOKEN_PREFIX}_{}_{secret}", token.id);
+        Ok((token, plaintext))
+    }
+
+    pub async fn find_multiple(pool: &Pool<Postgres>, user_id: impl Into<String>) -> Result<Vec<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, ApiToken>(r#"
+                SELECT id, user_id as token_user_id, name, scopes, expires_at, last_used_at, created_at
+                FROM auth.api_token
+                WHERE user_id = $1
+                ORDER BY created_at DESC
+            "#)
+            .bind(user_id.into())
+            .fetch_all(pool)
+            .await?
+        )
+    }
+
+    /// Deletes a token of the given user.
+    pub async fn revoke(pool: &Pool<Postgres>, user_id: impl Into<String>, id: impl Into<String>) -> Result<(), PodelError> {
+        let result = sqlx::query("DELETE FROM auth.api_token WHERE id = $1 AND user_id = $2")
+            .bind(id.into())
+            .bind(user_id.into())
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            Err(PodelError::Empty())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Deletes every token of the user, e.g. once their password changed. Returns the number of deleted tokens.
+    pub async fn revoke_all(pool: &Pool<Postgres>, user_id: &str) -> Result<u64, PodelError> {
+        let result = sqlx::query("DELETE FROM auth.api_token WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Resolves a plaintext token into its owner and records its use.
+    pub async fn authenticate(pool: &Pool<Postgres>, plaintext: &str) -> Result<(Arc<User>, Self), PodelError> {
+        let invalid = || PodelError::UserError("Invalid API token.".into());
+
+        let (id, secret) = plaintext
+            .strip_prefix(TOKEN_PREFIX)
+            .and_then(|rest| rest.strip_prefix('_'))
+            .and_then(|rest| rest.split_once('_'))
+            .ok_or_else(invalid)?;
+
+        let row = sqlx::query(&format!(r#"
+                SELECT
+                    t.id,
+                    t.user_id as token_user_id,
+                    t.name,
+                    t.token_hash,
+                    t.scopes,
+                    t.expires_at,
+                    t.last_used_at,
+                    t.created_at,
+                    u.id as user_id,
+                    u.email as user_email,
+                    u.password_hash as user_password_hash,
+                    u.language as user_language,
+                    u.name as user_name,
+                    {EFFECTIVE_IS_ADMIN} as user_is_admin,
+                    u.is_active as user_is_active,
+                    u.is_verified as user_is_verified,
+                    u.last_login as user_last_login,
+                    u.created_at as user_created_at,
+                    u.updated_at as user_updated_at
+                FROM auth.api_token t
+                JOIN auth.user u ON t.user_id = u.id AND u.is_active
+                WHERE t.id = $1 AND (t.expires_at IS NULL OR t.expires_at > NOW())
+            "#))
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(invalid)?;
+
+        let token_hash: String = row.try_get("token_hash")?;
+        if !constant_time_eq(token_hash.as_bytes(), hash_secret(secret).as_bytes()) {
+            return Err(invalid());
+        }
+
+        let token = <ApiToken as sqlx::FromRow<'_, PgRow>>::from_row(&row)?;
+        let user = User::from_row(&row)?;
+
+        sqlx::query("UPDATE auth.api_token SET last_used_at = NOW() WHERE id = $1")
+            .bind(&token.id)
+            .execute(pool)
+            .await?;
+
+        Ok((Arc::new(user), token))
+    }
+
+    pub fn has_scope(&self, scope: TokenScope) -> bool {
+        self.scopes.contains(&TokenScope::Admin) || self.scopes.contains(&scope)
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0_u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -0,0 +1,326 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use crate::database::Ulid;
+use crate::PodelError;
+
+/// Seconds a single code is valid for.
+const TIME_STEP: i64 = 30;
+/// Digits of a code.
+const DIGITS: u32 = 6;
+/// Number of neighbouring time steps accepted to tolerate clock drift.
+const ALLOWED_DRIFT: i64 = 1;
+/// Number of recovery codes issued at once.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// RFC 6238 time-based one-time password generator of a single user.
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct Totp {
+    pub user_id: Ulid,
+    pub secret: Vec<u8>,
+    /// `None` until the user confirms the enrolment with a valid code
+    pub enabled_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_used_step: Option<i64>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Totp {
+    pub async fn find(pool: &Pool<Postgres>, user_id: impl Into<String>) -> Result<Option<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, Totp>(r#"
+                SELECT user_id, secret, enabled_at, last_used_step, created_at
+                FROM auth.totp
+                WHERE user_id = $1
+            "#)
+            .bind(user_id.into())
+            .fetch_optional(pool)
+            .await?
+        )
+    }
+
+    pub async fn is_enabled(pool: &Pool<Postgres>, user_id: impl Into<String>) -> Result<bool, PodelError> {
+        Ok(Self::find(pool, user_id).await?.is_some_and(|totp| totp.enabled_at.is_some()))
+    }
+
+    /// Generates a new secret for a user who does not have 2FA enabled yet, replacing any unconfirmed one.
+    pub async fn begin_enrolment(pool: &Pool<Postgres>, user_id: impl Into<String>) -> Result<Self, PodelError> {
+        let mut secret = vec![0_u8; 20];
+        OsRng.fill_bytes(&mut secret);
+
+        sqlx::query_as::<Postgres, Totp>(r#"
+                INSERT INTO auth.totp (user_id, secret)
+                VALUES ($1, $2)
+                ON CONFLICT (user_id) DO UPDATE
+                SET secret = EXCLUDED.secret, last_used_step = NULL, created_at = CURRENT_TIMESTAMP
+                WHERE auth.totp.enabled_at IS NULL
+                RETURNING user_id, secret, enabled_at, last_used_step, created_at
+            "#)
+            .bind(user_id.into())
+            .bind(secret)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| PodelError::UserError("Two-factor authentication is already enabled.".into()))
+    }
+
+    /// `otpauth://` URI understood by authenticator apps, usually shown as a QR code.
+    pub fn otpauth_uri(&self, issuer: &str, account: &str) -> String {
+        let issuer = percent_encode(issuer);
+        format!(
+            "otpauth://totp/{issuer}:{}?secret={}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={TIME_STEP}",
+            percent_encode(account),
+            base32(&self.secret),
+        )
+    }
+
+    /// Base32 form of the secret, for entering it by hand.
+    pub fn secret_base32(&self) -> String {
+        base32(&self.secret)
+    }
+
+    /// Confirms the enrolment with a code from the authenticator app and returns fresh recovery codes.
+    pub async fn enable(&self, pool: &Pool<Postgres>, code: &str) -> Result<Vec<String>, PodelError> {
+        if self.enabled_at.is_some() {
+            return Err(PodelError::UserError("Two-factor authentication is already enabled.".into()));
+        }
+
+        self.verify(pool, code).await?;
+
+        sqlx::query("UPDATE auth.totp SET enabled_at = CURRENT_TIMESTAMP WHERE user_id = $1")
+            .bind(&self.user_id)
+            .execute(pool)
+            .await?;
+
+        RecoveryCode::regenerate(pool, &self.user_id).await
+    }
+
+    /// Turns 2FA off and deletes the recovery codes.
+    pub async fn disable(pool: &Pool<Postgres>, user_id: impl Into<String>) -> Result<(), PodelError> {
+        let user_id = user_id.into();
+        let mut transaction = pool.begin().await?;
+
+        sqlx::query("DELETE FROM auth.totp WHERE user_id = $1")
+            .bind(&user_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("DELETE FROM auth.recovery_code WHERE user_id = $1")
+            .bind(&user_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Accepts a current code. Every code is accepted at most once, even within its time step.
+    pub async fn verify(&self, pool: &Pool<Postgres>, code: &str) -> Result<(), PodelError> {
+        let invalid = || PodelError::UserError("Invalid code.".into());
+
+        let code = code.trim().parse::<u32>().map_err(|_| invalid())?;
+        let current = chrono::Utc::now().timestamp() / TIME_STEP;
+        let step = (current - ALLOWED_DRIFT..=current + ALLOWED_DRIFT)
+            .find(|step| self.code_at(*step) == code)
+            .ok_or_else(invalid)?;
+
+        let result = sqlx::query(r#"
+                UPDATE auth.totp
+                SET last_used_step = $2
+                WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#)
+            .bind(&self.user_id)
+            .bind(step)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            Err(invalid())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// HOTP value of the given time step, RFC 4226 section 5.3.
+    fn code_at(&self, step: i64) -> u32 {
+        let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(&self.secret) else {
+            return u32::MAX;
+        };
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+
+        let offset = usize::from(hash[hash.len() - 1] & 0x0f);
+        let binary = u32::from_be_bytes([hash[offset], hash[offset + 1], hash[offset + 2], hash[offset + 3]]) & 0x7fff_ffff;
+
+        binary % 10_u32.pow(DIGITS)
+    }
+}
+
+/// Single-use code letting a user in when they lose their authenticator. Only hashes are stored.
+pub struct RecoveryCode;
+
+impl RecoveryCode {
+    /// Replaces all recovery codes of the user and returns the new ones in plaintext.
+    pub async fn regenerate(pool: &Pool<Postgres>, user_id: &str) -> Result<Vec<String>, PodelError> {
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_code()).collect();
+        let mut transaction = pool.begin().await?;
+
+        sqlx::query("DELETE FROM auth.recovery_code WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        for code in &codes {
+            sqlx::query("INSERT INTO auth.recovery_code (id, user_id, code_hash) VALUES ($1, $2, $3)")
+                .bind(ulid::Ulid::new().to_string())
+                .bind(user_id)
+                .bind(hash_code(code))
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+        Ok(codes)
+    }
+
+    /// Uses up a recovery code.
+    pub async fn redeem(pool: &Pool<Postgres>, user_id: &str, code: &str) -> Result<(), PodelError> {
+        let result = sqlx::query(r#"
+                UPDATE auth.recovery_code
+                SET used_at = CURRENT_TIMESTAMP
+                WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#)
+            .bind(user_id)
+            .bind(hash_code(code))
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            Err(PodelError::UserError("Invalid recovery code.".into()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Number of codes the user has not used yet.
+    pub async fn remaining(pool: &Pool<Postgres>, user_id: &str) -> Result<i64, PodelError> {
+        Ok(
+            sqlx::query_scalar::<Postgres, i64>(
+                "SELECT COUNT(*) FROM auth.recovery_code WHERE user_id = $1 AND used_at IS NULL"
+            )
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?
+        )
+    }
+}
+
+/// `xxxxx-xxxxx` from an alphabet without look-alike characters.
+fn generate_code() -> String {
+    const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+    let mut bytes = [0_u8; 10];
+    OsRng.fill_bytes(&mut bytes);
+
+    let chars: String = bytes
+        .iter()
+        .map(|byte| char::from(ALPHABET[usize::from(*byte) % ALPHABET.len()]))
+        .collect();
+    format!("{}-{}", &chars[..5], &chars[5..])
+}
+
+/// Hash of the code, ignoring case, whitespace and dashes the user may or may not type.
+fn hash_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|char| char.to_ascii_lowercase())
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+/// RFC 4648 base32 without padding, as expected by authenticator apps.
+fn base32(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut output = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+
+    for byte in bytes {
+        buffer = (buffer << 8) | u16::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(char::from(ALPHABET[usize::from((buffer >> bits) & 0x1f)]));
+        }
+    }
+    if bits > 0 {
+        output.push(char::from(ALPHABET[usize::from((buffer << (5 - bits)) & 0x1f)]));
+    }
+
+    output
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => char::from(byte).to_string(),
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SHA-1 seed of the RFC 6238 appendix B test vectors.
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    fn totp(secret: &[u8]) -> Totp {
+        Totp {
+            user_id: String::new(),
+            secret: secret.to_vec(),
+            enabled_at: None,
+            last_used_step: None,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn codes_match_rfc_6238_vectors() {
+        let totp = totp(RFC_SECRET);
+        // The RFC lists eight digits, of which six-digit codes are the trailing ones.
+        for (time, code) in [
+            (59, 287_082),
+            (1_111_111_109, 81_804),
+            (1_111_111_111, 50_471),
+            (1_234_567_890, 5_924),
+            (2_000_000_000, 279_037),
+            (20_000_000_000, 353_130),
+        ] {
+            assert_eq!(totp.code_at(time / TIME_STEP), code, "time {time}");
+        }
+    }
+
+    #[test]
+    fn base32_matches_rfc_4648_vectors() {
+        for (input, encoded) in [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ] {
+            assert_eq!(base32(input.as_bytes()), encoded, "input {input:?}");
+        }
+    }
+
+    #[test]
+    fn secret_is_encoded_for_authenticator_apps() {
+        let totp = totp(RFC_SECRET);
+        assert_eq!(totp.secret_base32(), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert!(totp.otpauth_uri("Podel", "jan novák").starts_with("otpauth://totp/Podel:jan%20nov%C3%A1k?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&"));
+    }
+}
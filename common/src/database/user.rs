@@ -1,12 +1,17 @@
 use crate::database::{Ulid};
+use crate::database::audit::{Actor, AuditEvent};
 use crate::PodelError;
 use argon2::password_hash::rand_core::OsRng;
 use argon2::password_hash::SaltString;
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::{Executor, Pool, Postgres, Row};
+use std::cmp::min;
+use std::collections::HashMap;
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::net::IpAddr;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::{Duration, Instant};
 use chrono::Days;
 use poem::session::Session;
 use sqlx::postgres::PgRow;
@@ -19,6 +24,12 @@ pub struct User {
     pub email: Option<String>,
     pub language: String,
     pub is_admin: bool,
+    /// `false` once an admin deactivates the account
+    pub is_active: bool,
+    /// confirmed the e-mail address
+    pub is_verified: bool,
+    /// `None` until the first login
+    pub last_login: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     #[serde(skip_serializing, skip_deserializing)]
     pub password_hash: String,
@@ -28,7 +39,7 @@ impl User {
     pub async fn find(id: impl Into<String>, pool: &Pool<Postgres>) -> Result<User, PodelError> {
         let user = sqlx::query_as::<Postgres, User>(
             r#"
-            SELECT id, name, language, email, is_admin, created_at, password_hash
+            SELECT id, name, language, email, is_admin, is_active, is_verified, last_login, created_at, password_hash
             FROM auth.user
             WHERE id = $1 OR name = $1
         "#,
@@ -40,6 +51,68 @@ impl User {
         Ok(user)
     }
 
+    /// User of the id only. For ids from tokens and sessions, where a user named like another's id must not match.
+    pub async fn find_by_id(id: impl Into<String>, pool: &Pool<Postgres>) -> Result<User, PodelError> {
+        let user = sqlx::query_as::<Postgres, User>(
+            r#"
+            SELECT id, name, language, email, is_admin, is_active, is_verified, last_login, created_at, password_hash
+            FROM auth.user
+            WHERE id = $1
+        "#,
+        )
+        .bind(id.into())
+        .fetch_one(pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    pub async fn find_by_email(email: impl Into<String>, pool: &Pool<Postgres>) -> Result<User, PodelError> {
+        let user = sqlx::query_as::<Postgres, User>(
+            r#"
+            SELECT id, name, language, email, is_admin, is_active, is_verified, last_login, created_at, password_hash
+            FROM auth.user
+            WHERE LOWER(email) = LOWER($1)
+        "#,
+        )
+        .bind(email.into())
+        .fetch_one(pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    /// Users whose name or e-mail contains `query`, newest first.
+    pub async fn find_multiple(
+        pool: &Pool<Postgres>,
+        query: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<User>, PodelError> {
+        let pattern = query
+            .map(str::trim)
+            .filter(|query| !query.is_empty())
+            .map(|query| format!("%{}%", query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")));
+
+        let users = sqlx::query_as::<Postgres, User>(
+            r#"
+            SELECT id, name, language, email, is_admin, is_active, is_verified, last_login, created_at, password_hash
+            FROM auth.user
+            WHERE $1::text IS NULL OR name ILIKE $1 OR email ILIKE $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            OFFSET $3
+        "#,
+        )
+        .bind(pattern)
+        .bind(min(limit, 50))
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(users)
+    }
+
     pub async fn register(
         pool: &Pool<Postgres>,
         username: impl Into<String>,
@@ -55,6 +128,9 @@ impl User {
             language: "en-US".into(),
             email: email.map(|e| e.into()),
             is_admin,
+            is_active: true,
+            is_verified: false,
+            last_login: None,
             created_at: chrono::Utc::now(),
             password_hash: password_hash.clone(),
         };
@@ -88,25 +164,127 @@ impl User {
         Ok(user)
     }
 
-    pub async fn create_session(self, pool: &Pool<Postgres>, session: &Session, ip: Option<impl Into<String>>) -> Result<(), PodelError> {
+    /// Starts a new server-side session and points the cookie at it.
+    pub async fn create_session(
+        mut self,
+        pool: &Pool<Postgres>,
+        session: &Session,
+        ip: Option<impl Into<String>>,
+        user_agent: Option<impl Into<String>>,
+        enforce_ip: bool,
+    ) -> Result<(), PodelError> {
+        if !self.is_active {
+            return Err(PodelError::UserError("Account is deactivated.".into()));
+        }
+
+        self.record_login(pool).await?;
+        session.set("user_language", &self.language);
         let arc = Arc::new(self);
-        let session_data = SessionData::new(arc, ip);
+        let mut session_data = SessionData::new(arc, ip, user_agent);
+        session_data.enforce_ip = enforce_ip && session_data.ip.is_some();
         session_data.save(pool).await?;
-        match session_data.to_base64() {
-            Ok(base64) => Ok(session.set("data", base64)),
-            Err(err) => Err(PodelError::UserError(err.to_string()))
-        }
+        session.set("session_id", &session_data.id);
+        Ok(())
+    }
+
+    /// Remembers that the user just logged in.
+    pub async fn record_login(&mut self, pool: &Pool<Postgres>) -> Result<(), PodelError> {
+        let now = chrono::Utc::now();
+        sqlx::query("UPDATE auth.user SET last_login = $2 WHERE id = $1")
+            .bind(&self.id)
+            .bind(now)
+            .execute(pool)
+            .await?;
+
+        self.last_login = Some(now);
+        Ok(())
+    }
+
+    /// Persists the user's preferred language, e.g. `cs-CZ`.
+    pub async fn set_language(&mut self, pool: &Pool<Postgres>, language: impl Into<String>) -> Result<(), PodelError> {
+        let language = language.into();
+        sqlx::query("UPDATE auth.user SET language = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $1")
+            .bind(&self.id)
+            .bind(&language)
+            .execute(pool)
+            .await?;
+
+        self.language = language;
+        Ok(())
+    }
+
+    /// Replaces the password hash.
+    pub async fn set_password(&mut self, pool: &Pool<Postgres>, password: impl Into<String>) -> Result<(), PodelError> {
+        self.set_password_hash(pool, hash_password(password)?).await
+    }
+
+    /// Stores a hash made by [`hash_password`], e.g. one computed before the login was complete.
+    pub async fn set_password_hash(&mut self, pool: &Pool<Postgres>, password_hash: String) -> Result<(), PodelError> {
+        sqlx::query("UPDATE auth.user SET password_hash = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $1")
+            .bind(&self.id)
+            .bind(&password_hash)
+            .execute(pool)
+            .await?;
+
+        self.password_hash = password_hash;
+        Ok(())
+    }
+
+    /// Marks the e-mail address as confirmed.
+    pub async fn mark_verified(&mut self, pool: &Pool<Postgres>) -> Result<(), PodelError> {
+        sqlx::query("UPDATE auth.user SET is_verified = true, updated_at = CURRENT_TIMESTAMP WHERE id = $1")
+            .bind(&self.id)
+            .execute(pool)
+            .await?;
+
+        self.is_verified = true;
+        SessionData::refresh_user(&self.id);
+        Ok(())
+    }
+
+    /// Sets a boolean column of the account and records the change in the audit log.
+    async fn set_flag(&self, pool: &Pool<Postgres>, actor: &Actor<'_>, column: &str, value: bool, action: &str) -> Result<(), PodelError> {
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, "auth.user", &self.id).await?;
+
+        sqlx::query(&format!("UPDATE auth.user SET {column} = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $1"))
+            .bind(&self.id)
+            .bind(value)
+            .execute(&mut *transaction)
+            .await?;
+
+        let after = AuditEvent::snapshot(&mut *transaction, "auth.user", &self.id).await?;
+        AuditEvent::new(Some(actor), action, "auth.user", &self.id)
+            .with_snapshots(before, after)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        SessionData::refresh_user(&self.id);
+        Ok(())
     }
 
+    /// Activates or deactivates the account.
+    pub async fn set_active(&mut self, pool: &Pool<Postgres>, actor: &Actor<'_>, is_active: bool) -> Result<(), PodelError> {
+        let action = if is_active { "activate" } else { "deactivate" };
+        self.set_flag(pool, actor, "is_active", is_active, action).await?;
+        self.is_active = is_active;
+        Ok(())
+    }
+
+    /// Grants or revokes administrator privileges.
+    pub async fn set_admin(&mut self, pool: &Pool<Postgres>, actor: &Actor<'_>, is_admin: bool) -> Result<(), PodelError> {
+        let action = if is_admin { "promote" } else { "demote" };
+        self.set_flag(pool, actor, "is_admin", is_admin, action).await?;
+        self.is_admin = is_admin;
+        Ok(())
+    }
+
+    /// User of the session resolved for the current request.
     pub fn from_session(session: &Session) -> Result<Arc<Self>, PodelError> {
-        if let Some(base64) = session.get::<String>("data") {
-            match SessionData::from_base64(&base64) {
-                Ok(data) => Ok(data.user),
-                Err(err) => Err(PodelError::UserError(err.to_string()))
-            }
-        } else {
-            Err(PodelError::Empty())
-        }
+        SessionData::from_session(session)
+            .map(|data| data.user.clone())
+            .ok_or(PodelError::Empty())
     }
 
     pub fn from_row(row: &PgRow) -> Result<Self, PodelError> {
@@ -118,6 +296,9 @@ impl User {
                 language: row.try_get("user_language")?,
                 name: row.try_get("user_name")?,
                 is_admin: row.try_get("user_is_admin")?,
+                is_active: row.try_get("user_is_active")?,
+                is_verified: row.try_get("user_is_verified")?,
+                last_login: row.try_get("user_last_login")?,
                 created_at: row.try_get("user_created_at")?,
             })
         } else {
@@ -132,16 +313,41 @@ impl Commentable for User {
     }
 }
 
+/// Whether the cookie belongs to a session that is still alive on the server.
 pub fn is_valid(session: &Session) -> bool {
-    if let Some(expiration) = session.get::<chrono::DateTime<chrono::Utc>>("expiration") {
-        if expiration > chrono::Utc::now() {
-            return true
-        }
+    SessionData::from_session(session).is_some_and(|data| data.expires_at > chrono::Utc::now())
+}
+
+/// `is_admin` as it applies to authorization. While the site requires two-factor authentication
+/// for admins, those who have not enabled it act as regular users.
+pub(crate) const EFFECTIVE_IS_ADMIN: &str = r#"(
+    u.is_admin AND (
+        EXISTS (SELECT 1 FROM auth.totp tf WHERE tf.user_id = u.id AND tf.enabled_at IS NOT NULL)
+        OR NOT EXISTS (SELECT 1 FROM setting st WHERE st.key = 'require_admin_2fa' AND st.value = 'true')
+    )
+)"#;
+
+/// How long a session lives without being used. Every request pushes the expiration this far into the future.
+const SESSION_LIFETIME: Days = Days::new(4);
+/// How long a session resolved from the database is trusted before it is looked up again.
+const SESSION_CACHE_TTL: Duration = Duration::from_secs(30);
+
+struct CachedSession {
+    data: Arc<SessionData>,
+    fetched_at: Instant,
+}
+
+impl CachedSession {
+    /// Whether the entry may still be used without looking the session up again.
+    fn is_fresh(&self) -> bool {
+        self.fetched_at.elapsed() < SESSION_CACHE_TTL && self.data.expires_at > chrono::Utc::now()
     }
-    false
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+/// Sessions resolved during the last [`SESSION_CACHE_TTL`], keyed by session id.
+static SESSION_CACHE: LazyLock<RwLock<HashMap<Ulid, CachedSession>>> = LazyLock::new(Default::default);
+
+#[derive(PartialEq, Clone, Debug)]
 pub struct SessionData {
     pub id: Ulid,
     /// logged-in user
@@ -150,92 +356,263 @@ pub struct SessionData {
     pub ip: Option<String>,
     /// shall we invalidate the session on ip change?
     pub enforce_ip: bool,
+    /// `User-Agent` of the browser the session was created in
+    pub user_agent: Option<String>,
     pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub last_seen_at: chrono::DateTime<chrono::Utc>,
     pub created_at: chrono::DateTime<chrono::Utc>
 }
 
 impl SessionData {
-    pub fn new(user: Arc<User>, ip: Option<impl Into<String>>) -> Self {
+    pub fn new(user: Arc<User>, ip: Option<impl Into<String>>, user_agent: Option<impl Into<String>>) -> Self {
         let now = chrono::Utc::now();
-        let expiration = now.checked_add_days(Days::new(4));
+        let expiration = now.checked_add_days(SESSION_LIFETIME);
         Self {
             id: ulid::Ulid::new().into(),
             user,
             ip: ip.map(Into::into),
-            enforce_ip: false, //todo
+            enforce_ip: false,
+            user_agent: user_agent.map(|agent| agent.into().chars().take(512).collect()),
             expires_at: expiration.unwrap_or(now),
+            last_seen_at: now,
             created_at: now
         }
     }
 
-    pub fn from_session(session: &Session) -> Option<Self> {
-        session.get::<String>("data").map(|base64| Self::from_base64(&base64).ok()).flatten()
+    /// Session the cookie points to, as resolved by [`SessionData::resolve`] earlier in the request.
+    /// `None` once the cache entry is older than [`SESSION_CACHE_TTL`] or the session has expired.
+    pub fn from_session(session: &Session) -> Option<Arc<Self>> {
+        let id = session.get::<String>("session_id")?;
+        let cache = SESSION_CACHE.read().ok()?;
+        cache.get(&id).filter(|cached| cached.is_fresh()).map(|cached| cached.data.clone())
     }
 
-    pub fn to_base64(&self) -> Result<String, Box<dyn std::error::Error>> {
-        // Convert expiration to i64 (UTC timestamp in seconds)
-        let expiration_ts = self.expires_at.timestamp();
-        // Serialize that i64 to 8 bytes (big-endian)
-        let mut bytes = expiration_ts.to_be_bytes().to_vec();
-        // Then serialize the full SessionData (including expiration, if you like)
-        let session_bytes = bincode::serialize(self)?;
+    /// Looks up the session the cookie points to, using the in-process cache when it is fresh enough.
+    /// Every database lookup also extends the session's expiration. Clears the cookie if the session
+    /// no longer exists, has expired or its user was deleted.
+    pub async fn resolve(pool: &Pool<Postgres>, session: &Session) -> Result<Arc<Self>, PodelError> {
+        let Some(id) = session.get::<String>("session_id") else {
+            return Err(PodelError::Empty());
+        };
 
-        // Prepend those 8 bytes
-        bytes.extend_from_slice(&session_bytes);
+        if let Some(cached) = SESSION_CACHE.read().ok().and_then(|cache| {
+            cache.get(&id)
+                .filter(|cached| cached.is_fresh())
+                .map(|cached| cached.data.clone())
+        }) {
+            return Ok(cached);
+        }
 
-        // Finally, Base64-encode
-        let encoded = base64::encode(&bytes);
-        Ok(encoded)
+        match Self::touch(pool, &id).await {
+            Ok(data) => {
+                let data = Arc::new(data);
+                if let Ok(mut cache) = SESSION_CACHE.write() {
+                    cache.insert(id, CachedSession { data: data.clone(), fetched_at: Instant::now() });
+                }
+                Ok(data)
+            }
+            Err(PodelError::SqlxError(sqlx::Error::RowNotFound)) => {
+                Self::forget(|cached| cached.id == id);
+                session.remove("session_id");
+                Err(PodelError::Empty())
+            }
+            Err(err) => {
+                // The stale entry must not keep authenticating the request while the database is unreachable.
+                Self::forget(|cached| cached.id == id);
+                Err(err)
+            }
+        }
     }
 
-    pub fn from_base64(encoded: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let bytes = base64::decode(encoded)?;
-        if bytes.len() < 8 {
-            println!("Not enough bytes {:?}", bytes);
-            return Err("Not enough bytes to decode expiration".into());
+    /// Resolves the session like [`SessionData::resolve`] and additionally ends it
+    /// if it is pinned to an IP address other than the one the request came from.
+    pub async fn authenticate(
+        pool: &Pool<Postgres>,
+        session: &Session,
+        ip: Option<IpAddr>,
+    ) -> Result<Arc<Self>, PodelError> {
+        let data = Self::resolve(pool, session).await?;
+
+        if data.enforce_ip && data.ip.as_deref().and_then(|ip| ip.parse::<IpAddr>().ok()) != ip {
+            session.remove("session_id");
+            Self::revoke(pool, &data.user.id, &data.id).await?;
+            return Err(PodelError::UserError("Session is pinned to a different IP address.".into()));
         }
 
-        // First 8 bytes -> expiration
-        let (expiration_part, session_bytes) = bytes.split_at(8);
-        let expiration_ts = i64::from_be_bytes(expiration_part.try_into()?);
+        Ok(data)
+    }
 
-        if expiration_ts < chrono::Utc::now().timestamp() {
-            return Err(PodelError::UserError(format!("Session expired at: {expiration_ts}.")).into())
-        }
+    fn get_query() -> String {
+        format!(r#"
+            s.id,
+            s.ip,
+            s.enforce_ip,
+            s.user_agent,
+            s.expires_at,
+            s.last_seen_at,
+            s.created_at,
+            u.id as user_id,
+            u.email as user_email,
+            u.password_hash as user_password_hash,
+            u.language as user_language,
+            u.name as user_name,
+            {EFFECTIVE_IS_ADMIN} as user_is_admin,
+            u.is_active as user_is_active,
+            u.is_verified as user_is_verified,
+            u.last_login as user_last_login,
+            u.created_at as user_created_at
+        "#)
+    }
 
-        let session: SessionData = bincode::deserialize(session_bytes)?;
-        Ok(session)
+    /// Marks a live session as used right now and slides its expiration.
+    async fn touch(pool: &Pool<Postgres>, id: &str) -> Result<Self, PodelError> {
+        let now = chrono::Utc::now();
+        Ok(
+            sqlx::query_as::<Postgres, SessionData>(&format!(r#"
+                WITH s AS (
+                    UPDATE auth.session
+                    SET last_seen_at = $2, expires_at = GREATEST(expires_at, $3)
+                    WHERE id = $1 AND expires_at > $2
+                    RETURNING *
+                )
+                SELECT {}
+                FROM s
+                JOIN auth.user u ON s.user_id = u.id AND u.is_active
+            "#, Self::get_query()))
+            .bind(id)
+            .bind(now)
+            .bind(now.checked_add_days(SESSION_LIFETIME).unwrap_or(now))
+            .fetch_one(pool)
+            .await?
+        )
     }
 
-    pub async fn authenticate(&self, pool: &Pool<Postgres>, session: &Session) -> Result<(), PodelError> {
-        if let Err(err) = self.validate(pool).await {
-            session.clear();
-            Err(err)
-        } else {
-            Ok(())
-        }
+    /// Live sessions of a user, most recently used first.
+    pub async fn find_by_user(pool: &Pool<Postgres>, user_id: impl Into<String>) -> Result<Vec<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, SessionData>(&format!(r#"
+                SELECT {}
+                FROM auth.session s
+                JOIN auth.user u ON s.user_id = u.id
+                WHERE s.user_id = $1 AND s.expires_at > NOW()
+                ORDER BY s.last_seen_at DESC
+            "#, Self::get_query()))
+            .bind(user_id.into())
+            .fetch_all(pool)
+            .await?
+        )
     }
 
-    pub async fn validate(&self, pool: &Pool<Postgres>) -> Result<(), PodelError> {
-        let row = sqlx::query(r#"
-            SELECT id, user_id, ip, expires_at
-            FROM auth.session
-            WHERE id = $1 AND user_id = $2 AND ip = $3 AND expires_at > NOW()
-        "#)
-            .bind(&self.id)
-            .bind(&self.user.id)
-            .bind(&self.ip)
-            .bind(&self.expires_at)
-            .fetch_one(pool)
+    /// Live sessions of all users, most recently used first.
+    pub async fn find_recent(pool: &Pool<Postgres>, limit: i64, offset: i64) -> Result<Vec<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, SessionData>(&format!(r#"
+                SELECT {}
+                FROM auth.session s
+                JOIN auth.user u ON s.user_id = u.id
+                WHERE s.expires_at > NOW()
+                ORDER BY s.last_seen_at DESC
+                LIMIT $1
+                OFFSET $2
+            "#, Self::get_query()))
+            .bind(min(limit, 50))
+            .bind(offset)
+            .fetch_all(pool)
+            .await?
+        )
+    }
+
+    /// Ends a single session of the given user.
+    pub async fn revoke(pool: &Pool<Postgres>, user_id: &str, id: impl Into<String>) -> Result<(), PodelError> {
+        let id = id.into();
+        let result = sqlx::query("DELETE FROM auth.session WHERE id = $1 AND user_id = $2")
+            .bind(&id)
+            .bind(user_id)
+            .execute(pool)
             .await?;
 
-        if row.is_empty() {
+        Self::forget(|cached| cached.id == id);
+
+        if result.rows_affected() == 0 {
             Err(PodelError::Empty())
         } else {
             Ok(())
         }
     }
 
+    /// Ends a session of another user on behalf of an admin and records it in the audit log.
+    pub async fn revoke_as(pool: &Pool<Postgres>, actor: &Actor<'_>, user_id: &str, id: impl Into<String>) -> Result<(), PodelError> {
+        let id = id.into();
+        let mut transaction = pool.begin().await?;
+        let before = AuditEvent::snapshot(&mut *transaction, "auth.session", &id).await?;
+
+        let result = sqlx::query("DELETE FROM auth.session WHERE id = $1 AND user_id = $2")
+            .bind(&id)
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(PodelError::Empty());
+        }
+
+        AuditEvent::new(Some(actor), "revoke", "auth.session", &id)
+            .with_snapshots(before, None)
+            .record(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Self::forget(|cached| cached.id == id);
+        Ok(())
+    }
+
+    /// Ends every session of the given user except the given one.
+    pub async fn revoke_others(pool: &Pool<Postgres>, user_id: &str, current_id: &str) -> Result<u64, PodelError> {
+        let result = sqlx::query("DELETE FROM auth.session WHERE user_id = $1 AND id <> $2")
+            .bind(user_id)
+            .bind(current_id)
+            .execute(pool)
+            .await?;
+
+        Self::forget(|cached| cached.user.id == user_id && cached.id != current_id);
+        Ok(result.rows_affected())
+    }
+
+    /// Ends every session of the given user, on all devices.
+    pub async fn revoke_all(pool: &Pool<Postgres>, user_id: &str) -> Result<u64, PodelError> {
+        let result = sqlx::query("DELETE FROM auth.session WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+
+        Self::forget(|cached| cached.user.id == user_id);
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes expired sessions and drops stale cache entries. Returns the number of deleted sessions.
+    pub async fn delete_expired(pool: &Pool<Postgres>) -> Result<u64, PodelError> {
+        let result = sqlx::query("DELETE FROM auth.session WHERE expires_at <= NOW()")
+            .execute(pool)
+            .await?;
+
+        if let Ok(mut cache) = SESSION_CACHE.write() {
+            cache.retain(|_, cached| cached.is_fresh());
+        }
+
+        Ok(result.rows_affected())
+    }
+
+    /// Makes sessions of the user pick up changes of their account on the next request.
+    pub fn refresh_user(user_id: &str) {
+        Self::forget(|cached| cached.user.id == user_id);
+    }
+
+    fn forget(predicate: impl Fn(&SessionData) -> bool) {
+        if let Ok(mut cache) = SESSION_CACHE.write() {
+            cache.retain(|_, cached| !predicate(&cached.data));
+        }
+    }
+
     pub async fn save(&self, pool: &Pool<Postgres>) -> Result<(), PodelError> {
         let result = sqlx::query(r#"
                 INSERT INTO auth.session (
@@ -243,19 +620,23 @@ impl SessionData {
                     user_id,
                     ip,
                     enforce_ip,
+                    user_agent,
                     expires_at,
+                    last_seen_at,
                     created_at
                 )
                 VALUES (
-                    $1, $2, $3, $4, $5, $6
+                    $1, $2, $3, $4, $5, $6, $7, $8
                 )
             "#)
             .bind(&self.id)
             .bind(&self.user.id)
             .bind(&self.ip)
-            .bind(&self.enforce_ip)
-            .bind(&self.expires_at)
-            .bind(&self.created_at)
+            .bind(self.enforce_ip)
+            .bind(&self.user_agent)
+            .bind(self.expires_at)
+            .bind(self.last_seen_at)
+            .bind(self.created_at)
             .execute(pool)
             .await?;
 
@@ -277,7 +658,9 @@ impl sqlx::FromRow<'_, PgRow> for SessionData {
             user: Arc::new(user),
             ip: row.try_get::<Option<String>, _>("ip")?,
             enforce_ip: row.try_get::<bool, _>("enforce_ip")?,
+            user_agent: row.try_get::<Option<String>, _>("user_agent")?,
             expires_at: row.try_get::<chrono::DateTime<chrono::Utc>, _>("expires_at")?,
+            last_seen_at: row.try_get::<chrono::DateTime<chrono::Utc>, _>("last_seen_at")?,
             created_at: row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at")?,
         })
     }
@@ -290,6 +673,22 @@ pub fn verify_password(password: impl Into<String>, hash: &str) -> Result<(), Po
     Ok(argon2.verify_password(password.into().as_bytes(), &parsed_hash)?)
 }
 
+/// Whether the hash was created with other Argon2 parameters than the current ones and should be replaced
+/// the next time the plaintext password is known.
+pub fn needs_rehash(hash: &str) -> bool {
+    let Ok(parsed_hash) = PasswordHash::new(hash) else {
+        return true;
+    };
+
+    parsed_hash.algorithm != Algorithm::default().ident()
+        || parsed_hash.version != Some(Version::default().into())
+        || Params::try_from(&parsed_hash).map_or(true, |params| {
+            // The output length is read from the hash but unset in the defaults, so only the costs are compared.
+            let current = Params::default();
+            params.m_cost() != current.m_cost() || params.t_cost() != current.t_cost() || params.p_cost() != current.p_cost()
+        })
+}
+
 pub fn hash_password(password: impl Into<String>) -> Result<String, PodelError> {
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default();
@@ -316,14 +715,19 @@ impl AuthUser for User {
 pub struct Credentials {
     #[serde(default, deserialize_with = "deserialize_checkbox")]
     pub authentication: bool,
+    /// end the session when the client's IP address changes
+    #[serde(default, deserialize_with = "deserialize_checkbox")]
+    pub enforce_ip: bool,
     pub username: String,
+    /// required when registering
+    pub email: Option<String>,
     pub password: String,
     pub next: Option<String>,
 }
 
 /// This will accept any value (including "on", which is what HTML forms send for checked checkboxes)
 /// and return true if the field is present, false if it's absent
-fn deserialize_checkbox<'de, D>(deserializer: D) -> Result<bool, D::Error>
+pub fn deserialize_checkbox<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -332,3 +736,64 @@ where
         .flatten()
         .is_some())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresh_hash_needs_no_rehash() {
+        let hash = hash_password("correct horse battery staple").unwrap();
+        assert!(!needs_rehash(&hash));
+    }
+
+    #[test]
+    fn weaker_parameters_need_rehash() {
+        let salt = SaltString::generate(&mut OsRng);
+        let params = Params::new(Params::DEFAULT_M_COST / 2, Params::DEFAULT_T_COST, Params::DEFAULT_P_COST, None).unwrap();
+        let hash = Argon2::new(Algorithm::default(), Version::default(), params)
+            .hash_password(b"password", &salt)
+            .unwrap()
+            .to_string();
+
+        assert!(needs_rehash(&hash));
+    }
+
+    #[test]
+    fn unparsable_hash_needs_rehash() {
+        assert!(needs_rehash("not a hash"));
+    }
+
+    fn cached(age: Duration, expires_in: chrono::Duration) -> CachedSession {
+        let user = User {
+            id: ulid::Ulid::new().to_string(),
+            name: "user".into(),
+            email: None,
+            language: "en-US".into(),
+            is_admin: false,
+            is_active: true,
+            is_verified: true,
+            last_login: None,
+            created_at: chrono::Utc::now(),
+            password_hash: String::new(),
+        };
+        let mut data = SessionData::new(Arc::new(user), None::<String>, None::<String>);
+        data.expires_at = chrono::Utc::now() + expires_in;
+        CachedSession { data: Arc::new(data), fetched_at: Instant::now().checked_sub(age).unwrap() }
+    }
+
+    #[test]
+    fn recent_session_is_fresh() {
+        assert!(cached(Duration::ZERO, chrono::Duration::hours(1)).is_fresh());
+    }
+
+    #[test]
+    fn session_older_than_ttl_is_stale() {
+        assert!(!cached(SESSION_CACHE_TTL, chrono::Duration::hours(1)).is_fresh());
+    }
+
+    #[test]
+    fn expired_session_is_stale() {
+        assert!(!cached(Duration::ZERO, chrono::Duration::seconds(-1)).is_fresh());
+    }
+}
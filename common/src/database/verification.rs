@@ -0,0 +1,195 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use crate::database::Ulid;
+use crate::ownership::OwnershipProbe;
+use crate::PodelError;
+
+/// Where the verification token was found.
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "subject.verification_method")]
+pub enum VerificationMethod {
+    /// `podela-verification=<token>` TXT record of the domain
+    Dns,
+    /// `/.well-known/podela-verification.txt` file on the website
+    WellKnown,
+}
+
+impl VerificationMethod {
+    /// Key of the localized name in the Fluent bundles.
+    pub fn fluent_key(&self) -> &'static str {
+        match self {
+            VerificationMethod::Dns => "verification-method-dns",
+            VerificationMethod::WellKnown => "verification-method-well-known",
+        }
+    }
+}
+
+/// Challenge proving that the owner of a website's organization controls its domain.
+#[derive(sqlx::FromRow, Clone, Debug, Serialize, Deserialize)]
+pub struct WebsiteVerification {
+    pub id: Ulid,
+    pub website_id: Ulid,
+    pub user_id: Ulid,
+    pub token: String,
+    /// `None` until verified
+    pub method: Option<VerificationMethod>,
+    /// domain the token was found on, ownership lapses once the website moves to another one
+    pub domain_name: Option<String>,
+    pub verified_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// last time the token was looked for
+    pub checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl WebsiteVerification {
+    fn get_query() -> &'static str {
+        r#"SELECT
+            v.id,
+            v.website_id,
+            v.user_id,
+            v.token,
+            v.method,
+            v.domain_name,
+            v.verified_at,
+            v.checked_at,
+            v.created_at
+        FROM subject.website_verification v
+        "#
+    }
+
+    /// Challenge of the user for the website, created with a fresh token if there is none yet.
+    pub async fn issue(
+        pool: &Pool<Postgres>,
+        website_id: impl Into<String>,
+        user_id: impl Into<String>,
+    ) -> Result<Self, PodelError> {
+        let mut token = [0_u8; 24];
+        OsRng.fill_bytes(&mut token);
+
+        // The no-op update makes the existing row come back on conflict.
+        Ok(
+            sqlx::query_as::<Postgres, WebsiteVerification>(r#"
+                INSERT INTO subject.website_verification (id, website_id, user_id, token)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (website_id, user_id) DO UPDATE SET token = subject.website_verification.token
+                RETURNING id, website_id, user_id, token, method, domain_name, verified_at, checked_at, created_at
+            "#)
+            .bind(ulid::Ulid::new().to_string())
+            .bind(website_id.into())
+            .bind(user_id.into())
+            .bind(URL_SAFE_NO_PAD.encode(token))
+            .fetch_one(pool)
+            .await?
+        )
+    }
+
+    /// Verified ownership of the website by the current owner of its organization.
+    /// Ownership lapses when the organization changes hands, the website moves to another one
+    /// or its domain changes.
+    pub async fn find_verified(
+        pool: &Pool<Postgres>,
+        website_id: impl Into<String>,
+    ) -> Result<Option<Self>, PodelError> {
+        Ok(
+            sqlx::query_as::<Postgres, WebsiteVerification>(&format!(r#"
+                    {}
+                    JOIN subject.website w ON w.id = v.website_id AND w.domain_name = v.domain_name
+                    JOIN subject.organization o ON o.id = w.organization_id AND o.user_id = v.user_id
+                    WHERE v.website_id = $1 AND v.verified_at IS NOT NULL
+                "#, Self::get_query()))
+                .bind(website_id.into())
+                .fetch_optional(pool)
+                .await?
+        )
+    }
+
+    /// Whether the user is the verified owner of the website, allowed to respond in its name.
+    pub async fn is_verified_owner(
+        pool: &Pool<Postgres>,
+        website_id: impl Into<String>,
+        user_id: &str,
+    ) -> Result<bool, PodelError> {
+        Ok(Self::find_verified(pool, website_id)
+            .await?
+            .is_some_and(|verification| verification.user_id == user_id))
+    }
+
+    /// Whether the token was found on `domain`, the current domain of the website.
+    pub fn is_verified_for(&self, domain: &str) -> bool {
+        self.verified_at.is_some() && self.domain_name.as_deref() == Some(domain)
+    }
+
+    /// Looks for the token on `domain` and records the ownership if it is there.
+    /// Returns `Empty` if the domain doesn't publish the token.
+    pub async fn verify(
+        &mut self,
+        pool: &Pool<Postgres>,
+        probe: &OwnershipProbe,
+        domain: &str,
+    ) -> Result<VerificationMethod, PodelError> {
+        let method = probe.check(domain, &self.token).await;
+        let now = chrono::Utc::now();
+
+        sqlx::query(r#"
+                UPDATE subject.website_verification
+                SET checked_at = $2,
+                    method = COALESCE($3, method),
+                    domain_name = CASE WHEN $3 IS NULL THEN domain_name ELSE $4 END,
+                    verified_at = CASE WHEN $3 IS NULL THEN verified_at ELSE $2 END
+                WHERE id = $1
+            "#)
+            .bind(&self.id)
+            .bind(now)
+            .bind(method)
+            .bind(domain)
+            .execute(pool)
+            .await?;
+
+        self.checked_at = Some(now);
+        let method = method.ok_or(PodelError::Empty())?;
+        self.method = Some(method);
+        self.domain_name = Some(domain.to_string());
+        self.verified_at = Some(now);
+
+        Ok(method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verification(domain_name: Option<&str>, verified: bool) -> WebsiteVerification {
+        let now = chrono::Utc::now();
+        WebsiteVerification {
+            id: "verification".into(),
+            website_id: "website".into(),
+            user_id: "user".into(),
+            token: "token".into(),
+            method: verified.then_some(VerificationMethod::Dns),
+            domain_name: domain_name.map(str::to_string),
+            verified_at: verified.then_some(now),
+            checked_at: Some(now),
+            created_at: now,
+        }
+    }
+
+    #[test]
+    fn verified_for_the_checked_domain() {
+        assert!(verification(Some("example.com"), true).is_verified_for("example.com"));
+    }
+
+    #[test]
+    fn lapses_when_the_domain_changes() {
+        assert!(!verification(Some("example.com"), true).is_verified_for("example.org"));
+    }
+
+    #[test]
+    fn unverified_or_unrecorded_domain_is_not_verified() {
+        assert!(!verification(Some("example.com"), false).is_verified_for("example.com"));
+        assert!(!verification(None, true).is_verified_for("example.com"));
+    }
+}
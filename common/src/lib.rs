@@ -1,14 +1,28 @@
 extern crate core;
 
+use crate::database::reviewable::karma::KarmaWeights;
 use crate::database::user::{verify_password, Credentials, User};
-use log::info;
+use crate::mail::Mailer;
+use crate::ownership::OwnershipProbe;
+use crate::rate_limit::RateLimitStore;
+use crate::registry::BusinessRegistry;
+use clap::Parser;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use log::{info, warn};
 use sqlx::PgPool;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
 use thiserror::Error;
 
 pub mod args;
 pub mod database;
+pub mod mail;
+pub mod ownership;
+pub mod rate_limit;
+pub mod registry;
+pub mod signing;
 
 #[derive(Error, Debug)]
 pub enum PodelError {
@@ -18,6 +32,10 @@ pub enum PodelError {
     UserError(String),
     #[error("It's empty.")]
     Empty(),
+    #[error("MailError: {0}")]
+    MailError(String),
+    #[error("LookupError: {0}")]
+    LookupError(String),
 
     #[error(transparent)]
     SqlxError(#[from] sqlx::Error),
@@ -34,15 +52,49 @@ pub struct AppState {
     pub title: &'static str,
     pub visitors: u64,
     pub pool: PgPool,
+    pub karma_weights: KarmaWeights,
+    pub trusted_proxies: Vec<IpAddr>,
+    pub mailer: Arc<dyn Mailer>,
+    /// key signing stateless tokens, e.g. e-mail verification links
+    pub secret_key: Arc<[u8]>,
+    /// address the site is reachable at, used in links sent by e-mail
+    pub public_url: String,
+    /// buckets of the rate limits, shared between instances if backed by Postgres
+    pub rate_limiter: Arc<dyn RateLimitStore>,
+    /// looks for website ownership tokens in DNS and on the websites themselves
+    pub ownership_probe: OwnershipProbe,
+    /// looks up organizations by IČO, ARES unless running offline
+    pub registry: Arc<dyn BusinessRegistry>,
 }
 
 impl AppState {
-    pub fn new(pool: PgPool) -> Self {
-        Self {
+    pub fn new(pool: PgPool) -> Result<Self, PodelError> {
+        let args = args::CliArgs::parse();
+        let secret_key: Arc<[u8]> = match args.secret_key {
+            Some(key) => key.into_bytes().into(),
+            None => {
+                warn!("SECRET_KEY is not set, signed links will stop working after restart");
+                let mut key = [0_u8; 32];
+                OsRng.fill_bytes(&mut key);
+                key.into()
+            }
+        };
+
+        let rate_limiter = args.rate_limit.store(&pool);
+
+        Ok(Self {
             pool,
             title: "Podela.me",
             visitors: 0,
-        }
+            karma_weights: args.karma,
+            trusted_proxies: args.trusted_proxies,
+            mailer: args.mail.mailer()?,
+            secret_key,
+            public_url: args.public_url.trim_end_matches('/').to_string(),
+            rate_limiter,
+            ownership_probe: OwnershipProbe::network()?,
+            registry: args.registry.registry()?,
+        })
     }
 }
 
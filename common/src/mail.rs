@@ -0,0 +1,138 @@
+use crate::PodelError;
+use async_trait::async_trait;
+use clap::Parser;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use log::{debug, info, warn};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Parser, Debug, Clone)]
+pub struct MailArgs {
+    /// SMTP relay to send mail through. Without one, mail is written to `mail-dir` instead.
+    #[clap(long = "smtp-host", env = "SMTP_HOST")]
+    pub smtp_host: Option<String>,
+    #[clap(long = "smtp-port", env = "SMTP_PORT", default_value = "587")]
+    pub smtp_port: u16,
+    #[clap(long = "smtp-username", env = "SMTP_USERNAME")]
+    pub smtp_username: Option<String>,
+    #[clap(long = "smtp-password", env = "SMTP_PASSWORD")]
+    pub smtp_password: Option<String>,
+    #[clap(long = "mail-from", env = "MAIL_FROM", default_value = "Podela.me <noreply@podela.me>")]
+    pub from: String,
+    #[clap(long = "mail-dir", env = "MAIL_DIR", default_value = "mail")]
+    pub directory: PathBuf,
+}
+
+impl MailArgs {
+    /// SMTP mailer if a relay is configured, file mailer otherwise.
+    pub fn mailer(&self) -> Result<Arc<dyn Mailer>, PodelError> {
+        let from = self.from.parse::<Mailbox>().map_err(|err| PodelError::MailError(err.to_string()))?;
+
+        Ok(match &self.smtp_host {
+            Some(host) => Arc::new(SmtpMailer::new(
+                host,
+                self.smtp_port,
+                self.smtp_username.clone().zip(self.smtp_password.clone()),
+                from,
+            )?),
+            None => {
+                warn!(
+                    "SMTP_HOST is not set, mail will not be delivered and is written to {} instead",
+                    self.directory.display()
+                );
+                Arc::new(FileMailer::new(&self.directory, from))
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    /// plain text body
+    pub body: String,
+}
+
+/// Transport for outgoing e-mail.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), PodelError>;
+}
+
+fn build_message(from: &Mailbox, email: Email) -> Result<Message, PodelError> {
+    let to = email.to.parse::<Mailbox>().map_err(|err| PodelError::MailError(err.to_string()))?;
+
+    Message::builder()
+        .from(from.clone())
+        .to(to)
+        .subject(email.subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(email.body)
+        .map_err(|err| PodelError::MailError(err.to_string()))
+}
+
+/// Sends mail through an SMTP relay using STARTTLS.
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(host: &str, port: u16, credentials: Option<(String, String)>, from: Mailbox) -> Result<Self, PodelError> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|err| PodelError::MailError(err.to_string()))?
+            .port(port);
+
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self { transport: builder.build(), from })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> Result<(), PodelError> {
+        let message = build_message(&self.from, email)?;
+        self.transport
+            .send(message)
+            .await
+            .map_err(|err| PodelError::MailError(err.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Writes every mail as an `.eml` file into a directory, for development and tests.
+///
+/// Bodies carry live verification and password reset links, so they are only logged at `debug`.
+pub struct FileMailer {
+    directory: PathBuf,
+    from: Mailbox,
+}
+
+impl FileMailer {
+    pub fn new(directory: impl Into<PathBuf>, from: Mailbox) -> Self {
+        Self { directory: directory.into(), from }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: Email) -> Result<(), PodelError> {
+        let path = self.directory.join(format!("{}.eml", ulid::Ulid::new()));
+        info!("Mail to {}: {} written to {}", email.to, email.subject, path.display());
+        debug!("{}", email.body);
+
+        let message = build_message(&self.from, email)?;
+        tokio::fs::create_dir_all(&self.directory).await?;
+        tokio::fs::write(path, message.formatted()).await?;
+
+        Ok(())
+    }
+}
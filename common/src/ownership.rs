@@ -0,0 +1,361 @@
+use crate::database::verification::VerificationMethod;
+use crate::PodelError;
+use async_trait::async_trait;
+use hickory_resolver::TokioAsyncResolver;
+use log::warn;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Prefix of the TXT record value, e.g. `podela-verification=<token>`.
+pub const TXT_PREFIX: &str = "podela-verification=";
+/// Path of the file holding the token, relative to the root of the domain.
+pub const WELL_KNOWN_PATH: &str = "/.well-known/podela-verification.txt";
+
+/// Time a lookup may take before the domain counts as unreachable.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+/// The file only holds a token, anything longer is not read.
+const MAX_FILE_SIZE: usize = 1024;
+
+/// Top-level domains that never name a host on the internet.
+const RESERVED_TLDS: [&str; 7] = ["localhost", "local", "internal", "intranet", "lan", "home", "arpa"];
+
+/// Whether `domain` is a hostname on the public internet, e.g. `example.com`.
+/// IP literals, ports, paths, userinfo, single labels and reserved names such as `localhost` are not.
+pub fn is_public_hostname(domain: &str) -> bool {
+    if domain.is_empty() || domain.len() > 253 {
+        return false;
+    }
+
+    let labels = domain.split('.').collect::<Vec<_>>();
+    let is_valid_label = |label: &&str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    };
+
+    // A numeric last label would make `127.0.0.1` a hostname.
+    labels.len() >= 2
+        && labels.iter().all(is_valid_label)
+        && labels.last().is_some_and(|tld| {
+            !tld.chars().all(|c| c.is_ascii_digit()) && !RESERVED_TLDS.contains(tld)
+        })
+}
+
+/// Whether the address is reachable on the public internet, not a loopback, private, link-local
+/// or otherwise reserved one the server could reach on its own network.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // shared address space of carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (b & 0b1100_0000) == 64)
+                // "this network" and reserved for future use
+                || a == 0
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_address(IpAddr::V4(ip));
+            }
+
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // link-local, fe80::/10
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Looks up TXT records of a domain.
+#[async_trait]
+pub trait TxtResolver: Send + Sync {
+    async fn txt_records(&self, domain: &str) -> Result<Vec<String>, PodelError>;
+}
+
+/// Downloads small text files over HTTPS.
+#[async_trait]
+pub trait HttpFetcher: Send + Sync {
+    /// Body of the response, `Err` for anything but `200 OK`.
+    async fn fetch(&self, url: &str) -> Result<String, PodelError>;
+}
+
+/// Checks whether a domain publishes a verification token.
+#[derive(Clone)]
+pub struct OwnershipProbe {
+    resolver: Arc<dyn TxtResolver>,
+    fetcher: Arc<dyn HttpFetcher>,
+}
+
+impl OwnershipProbe {
+    pub fn new(resolver: Arc<dyn TxtResolver>, fetcher: Arc<dyn HttpFetcher>) -> Self {
+        Self { resolver, fetcher }
+    }
+
+    /// Probe asking the system resolver and the domain's web server.
+    pub fn network() -> Result<Self, PodelError> {
+        Ok(Self::new(Arc::new(SystemResolver::new()?), Arc::new(HttpsFetcher::new()?)))
+    }
+
+    /// URL the token file is expected at.
+    pub fn well_known_url(domain: &str) -> String {
+        format!("https://{domain}{WELL_KNOWN_PATH}")
+    }
+
+    /// Method the token was found by, `None` if the domain doesn't publish it or isn't a public hostname.
+    /// The TXT record is tried first, lookup failures are logged and count as not found.
+    pub async fn check(&self, domain: &str, token: &str) -> Option<VerificationMethod> {
+        if !is_public_hostname(domain) {
+            warn!("Refusing to verify ownership of {}, which is not a public hostname", domain);
+            return None;
+        }
+
+        match self.resolver.txt_records(domain).await {
+            Ok(records) if records.iter().any(|record| record.trim().strip_prefix(TXT_PREFIX) == Some(token)) => {
+                return Some(VerificationMethod::Dns);
+            }
+            Ok(_) => {}
+            Err(err) => warn!("Failed to look up TXT records of {}: {}", domain, err),
+        }
+
+        match self.fetcher.fetch(&Self::well_known_url(domain)).await {
+            Ok(body) if body.trim() == token => Some(VerificationMethod::WellKnown),
+            Ok(_) => None,
+            Err(err) => {
+                warn!("Failed to fetch verification file of {}: {}", domain, err);
+                None
+            }
+        }
+    }
+}
+
+/// Resolver configured by the operating system, e.g. `/etc/resolv.conf`.
+pub struct SystemResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl SystemResolver {
+    pub fn new() -> Result<Self, PodelError> {
+        let (config, mut options) = hickory_resolver::system_conf::read_system_conf()
+            .map_err(|err| PodelError::LookupError(err.to_string()))?;
+        options.timeout = LOOKUP_TIMEOUT;
+
+        Ok(Self { resolver: TokioAsyncResolver::tokio(config, options) })
+    }
+}
+
+#[async_trait]
+impl TxtResolver for SystemResolver {
+    async fn txt_records(&self, domain: &str) -> Result<Vec<String>, PodelError> {
+        let lookup = self
+            .resolver
+            .txt_lookup(domain)
+            .await
+            .map_err(|err| PodelError::LookupError(err.to_string()))?;
+
+        // Long records are split into several strings, join them back.
+        Ok(lookup
+            .iter()
+            .map(|txt| txt.iter().map(|data| String::from_utf8_lossy(data)).collect::<String>())
+            .collect())
+    }
+}
+
+/// Resolves hosts with the system resolver, leaving out addresses that aren't public so that
+/// a domain pointing at the server's own network can't be used to reach it.
+struct PublicAddressResolver;
+
+impl reqwest::dns::Resolve for PublicAddressResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addresses = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| is_public_address(address.ip()))
+                .collect::<Vec<SocketAddr>>();
+
+            if addresses.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+
+            Ok::<reqwest::dns::Addrs, Box<dyn std::error::Error + Send + Sync>>(Box::new(addresses.into_iter()))
+        })
+    }
+}
+
+/// Fetches files from public addresses with a short timeout. Redirects are not followed,
+/// the file has to be served by the domain itself.
+pub struct HttpsFetcher {
+    client: reqwest::Client,
+}
+
+impl HttpsFetcher {
+    pub fn new() -> Result<Self, PodelError> {
+        let client = reqwest::Client::builder()
+            .timeout(LOOKUP_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicAddressResolver))
+            .https_only(true)
+            .user_agent("Podela.me ownership verification")
+            .build()
+            .map_err(|err| PodelError::LookupError(err.to_string()))?;
+
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl HttpFetcher for HttpsFetcher {
+    async fn fetch(&self, url: &str) -> Result<String, PodelError> {
+        let mut response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|err| PodelError::LookupError(err.to_string()))?;
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|err| PodelError::LookupError(err.to_string()))? {
+            body.extend_from_slice(&chunk);
+            if body.len() > MAX_FILE_SIZE {
+                return Err(PodelError::LookupError(format!("{url} is larger than {MAX_FILE_SIZE} bytes")));
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+}
+
+/// Fixed TXT records and files instead of the network, for development and tests.
+#[derive(Default, Clone)]
+pub struct StaticRecords {
+    /// TXT records by domain
+    pub txt: HashMap<String, Vec<String>>,
+    /// file contents by URL
+    pub files: HashMap<String, String>,
+}
+
+#[async_trait]
+impl TxtResolver for StaticRecords {
+    async fn txt_records(&self, domain: &str) -> Result<Vec<String>, PodelError> {
+        Ok(self.txt.get(domain).cloned().unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl HttpFetcher for StaticRecords {
+    async fn fetch(&self, url: &str) -> Result<String, PodelError> {
+        self.files
+            .get(url)
+            .cloned()
+            .ok_or_else(|| PodelError::LookupError(format!("{url} not found")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "abc123";
+
+    fn probe(records: StaticRecords) -> OwnershipProbe {
+        let records = Arc::new(records);
+        OwnershipProbe::new(records.clone(), records)
+    }
+
+    #[test]
+    fn public_hostnames() {
+        assert!(is_public_hostname("example.com"));
+        assert!(is_public_hostname("www.podela-me.cz"));
+        assert!(is_public_hostname("1password.com"));
+    }
+
+    #[test]
+    fn rejects_non_public_hostnames() {
+        for domain in [
+            "",
+            "localhost",
+            "intranet",
+            "127.0.0.1",
+            "10.0.0.1",
+            "[::1]",
+            "::1",
+            "example.com:8080",
+            "example.com/admin",
+            "user@example.com",
+            "user:pass@example.com",
+            "db.internal",
+            "printer.local",
+            "foo.localhost",
+            "-bad.com",
+            "exa_mple.com",
+            "Example.com",
+            "example..com",
+        ] {
+            assert!(!is_public_hostname(domain), "{domain} should not be public");
+        }
+    }
+
+    #[test]
+    fn private_addresses_are_not_public() {
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:10.0.0.1"] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{ip} should not be public");
+        }
+        for ip in ["93.184.216.34", "2606:2800:220:1:248:1893:25c8:1946"] {
+            assert!(is_public_address(ip.parse().unwrap()), "{ip} should be public");
+        }
+    }
+
+    #[tokio::test]
+    async fn finds_token_in_txt_record() {
+        let mut records = StaticRecords::default();
+        records.txt.insert("example.com".into(), vec!["v=spf1 -all".into(), format!("{TXT_PREFIX}{TOKEN}")]);
+
+        assert_eq!(probe(records).check("example.com", TOKEN).await, Some(VerificationMethod::Dns));
+    }
+
+    #[tokio::test]
+    async fn finds_token_in_well_known_file() {
+        let mut records = StaticRecords::default();
+        records.files.insert(OwnershipProbe::well_known_url("example.com"), format!("{TOKEN}\n"));
+
+        assert_eq!(probe(records).check("example.com", TOKEN).await, Some(VerificationMethod::WellKnown));
+    }
+
+    #[tokio::test]
+    async fn ignores_other_tokens() {
+        let mut records = StaticRecords::default();
+        records.txt.insert("example.com".into(), vec![format!("{TXT_PREFIX}other")]);
+        records.files.insert(OwnershipProbe::well_known_url("example.com"), "other".into());
+
+        assert_eq!(probe(records).check("example.com", TOKEN).await, None);
+    }
+
+    #[tokio::test]
+    async fn refuses_non_public_domains() {
+        let mut records = StaticRecords::default();
+        for domain in ["localhost", "127.0.0.1", "example.com:8080", "example.com/x"] {
+            records.txt.insert(domain.into(), vec![format!("{TXT_PREFIX}{TOKEN}")]);
+            records.files.insert(OwnershipProbe::well_known_url(domain), TOKEN.into());
+        }
+
+        let probe = probe(records);
+        for domain in ["localhost", "127.0.0.1", "example.com:8080", "example.com/x"] {
+            assert_eq!(probe.check(domain, TOKEN).await, None, "{domain} should be refused");
+        }
+    }
+}
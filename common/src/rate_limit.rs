@@ -0,0 +1,252 @@
+use crate::PodelError;
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use clap::{Parser, ValueEnum};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Postgres};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Parser, Debug, Clone)]
+pub struct RateLimitArgs {
+    /// Where rate limit buckets are kept. Use `postgres` when running more than one instance.
+    #[clap(long = "rate-limit-store", env = "RATE_LIMIT_STORE", value_enum, default_value = "memory")]
+    pub store: RateLimitStoreKind,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitStoreKind {
+    Memory,
+    Postgres,
+}
+
+impl RateLimitArgs {
+    pub fn store(&self, pool: &PgPool) -> Arc<dyn RateLimitStore> {
+        match self.store {
+            RateLimitStoreKind::Memory => Arc::new(MemoryStore::default()),
+            RateLimitStoreKind::Postgres => Arc::new(PostgresStore::new(pool.clone())),
+        }
+    }
+}
+
+/// At most `burst` requests at once, refilling continuously to `burst` requests per `period`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quota {
+    pub burst: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    pub const fn new(burst: u32, period: Duration) -> Self {
+        Self { burst, period }
+    }
+
+    /// Tokens added to the bucket per second.
+    fn rate(&self) -> f64 {
+        f64::from(self.burst) / self.period.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Time until a bucket holding `tokens` has a whole token again.
+    fn wait(&self, tokens: f64) -> Duration {
+        Duration::from_secs_f64(((1.0 - tokens) / self.rate()).max(0.0))
+    }
+}
+
+/// Token buckets shared by all requests hitting the same key.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Takes a token from the bucket of `key`. Returns how long to wait if the bucket is empty.
+    async fn acquire(&self, key: &str, quota: Quota) -> Result<Option<Duration>, PodelError>;
+
+    /// Forgets buckets untouched for `max_age`, which must be longer than any quota period.
+    async fn prune(&self, max_age: Duration) -> Result<u64, PodelError>;
+}
+
+/// Length of the `rate_limit.key` column.
+const MAX_KEY_LENGTH: usize = 256;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn full(quota: Quota, now: Instant) -> Self {
+        Self { tokens: f64::from(quota.burst), updated_at: now }
+    }
+
+    /// Refills the bucket up to `now` and takes a token. Returns how long to wait if the bucket is empty.
+    fn take(&mut self, quota: Quota, now: Instant) -> Option<Duration> {
+        let refill = now.saturating_duration_since(self.updated_at).as_secs_f64() * quota.rate();
+        self.tokens = (self.tokens + refill).min(f64::from(quota.burst));
+        self.updated_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(quota.wait(self.tokens))
+        }
+    }
+}
+
+/// Buckets of a single process, lost on restart.
+#[derive(Default)]
+pub struct MemoryStore {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn acquire(&self, key: &str, quota: Quota) -> Result<Option<Duration>, PodelError> {
+        let mut buckets = self
+            .buckets
+            .lock()
+            .map_err(|_| PodelError::DatabaseError("Rate limit store is poisoned".into()))?;
+        let now = Instant::now();
+        let bucket = buckets.entry(key.to_string()).or_insert_with(|| Bucket::full(quota, now));
+
+        Ok(bucket.take(quota, now))
+    }
+
+    async fn prune(&self, max_age: Duration) -> Result<u64, PodelError> {
+        let mut buckets = self
+            .buckets
+            .lock()
+            .map_err(|_| PodelError::DatabaseError("Rate limit store is poisoned".into()))?;
+        let before = buckets.len();
+        buckets.retain(|_, bucket| bucket.updated_at.elapsed() < max_age);
+
+        Ok(u64::try_from(before - buckets.len()).unwrap_or_default())
+    }
+}
+
+/// Buckets in the `rate_limit` table, shared by every instance using the database.
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    pub const fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Key as stored in the `rate_limit` table. Keys too long for the column are replaced by their hash,
+/// as a failing insert would let every request through.
+fn stored_key(key: &str) -> Cow<'_, str> {
+    if key.len() <= MAX_KEY_LENGTH {
+        Cow::Borrowed(key)
+    } else {
+        Cow::Owned(format!("sha256:{}", URL_SAFE_NO_PAD.encode(Sha256::digest(key.as_bytes()))))
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for PostgresStore {
+    async fn acquire(&self, key: &str, quota: Quota) -> Result<Option<Duration>, PodelError> {
+        // All SET expressions see the old row, so the refill is computed from the previous state.
+        let refilled = "LEAST($2, rate_limit.tokens + EXTRACT(EPOCH FROM CURRENT_TIMESTAMP - rate_limit.updated_at) * $3)";
+        let (allowed, tokens) = sqlx::query_as::<Postgres, (bool, f64)>(&format!(r#"
+                INSERT INTO rate_limit (key, tokens, allowed, updated_at)
+                VALUES ($1, $2 - 1, TRUE, CURRENT_TIMESTAMP)
+                ON CONFLICT (key) DO UPDATE
+                SET allowed = {refilled} >= 1,
+                    tokens = CASE WHEN {refilled} >= 1 THEN {refilled} - 1 ELSE {refilled} END,
+                    updated_at = CURRENT_TIMESTAMP
+                RETURNING allowed, tokens
+            "#))
+            .bind(stored_key(key).as_ref())
+            .bind(f64::from(quota.burst))
+            .bind(quota.rate())
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((!allowed).then(|| quota.wait(tokens)))
+    }
+
+    async fn prune(&self, max_age: Duration) -> Result<u64, PodelError> {
+        let result = sqlx::query("DELETE FROM rate_limit WHERE updated_at < CURRENT_TIMESTAMP - make_interval(secs => $1)")
+            .bind(max_age.as_secs_f64())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUOTA: Quota = Quota::new(3, Duration::from_secs(30));
+
+    #[test]
+    fn full_bucket_allows_a_burst() {
+        let now = Instant::now();
+        let mut bucket = Bucket::full(QUOTA, now);
+
+        for _ in 0..QUOTA.burst {
+            assert_eq!(bucket.take(QUOTA, now), None);
+        }
+        assert_eq!(bucket.take(QUOTA, now), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn empty_bucket_refills_over_time() {
+        let now = Instant::now();
+        let mut bucket = Bucket { tokens: 0.0, updated_at: now };
+
+        assert_eq!(bucket.take(QUOTA, now + Duration::from_secs(5)), Some(Duration::from_secs(5)));
+        assert_eq!(bucket.take(QUOTA, now + Duration::from_secs(10)), None);
+        assert_eq!(bucket.take(QUOTA, now + Duration::from_secs(10)), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn refill_stops_at_the_burst() {
+        let now = Instant::now();
+        let mut bucket = Bucket { tokens: 0.0, updated_at: now };
+        let later = now + Duration::from_secs(3600);
+
+        for _ in 0..QUOTA.burst {
+            assert_eq!(bucket.take(QUOTA, later), None);
+        }
+        assert!(bucket.take(QUOTA, later).is_some());
+    }
+
+    #[tokio::test]
+    async fn memory_store_keeps_buckets_apart() {
+        let store = MemoryStore::default();
+        let quota = Quota::new(1, Duration::from_secs(60));
+
+        assert_eq!(store.acquire("a", quota).await.unwrap(), None);
+        assert!(store.acquire("a", quota).await.unwrap().is_some());
+        assert_eq!(store.acquire("b", quota).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn memory_store_prunes_old_buckets() {
+        let store = MemoryStore::default();
+        store.acquire("a", QUOTA).await.unwrap();
+
+        assert_eq!(store.prune(Duration::from_secs(60)).await.unwrap(), 0);
+        assert_eq!(store.prune(Duration::ZERO).await.unwrap(), 1);
+    }
+
+    #[test]
+    fn short_keys_are_stored_as_they_are() {
+        assert_eq!(stored_key("comment:ip:203.0.113.9"), "comment:ip:203.0.113.9");
+    }
+
+    #[test]
+    fn long_keys_are_hashed_to_fit_the_column() {
+        let key = "x".repeat(MAX_KEY_LENGTH + 1);
+        let stored = stored_key(&key);
+
+        assert!(stored.len() <= MAX_KEY_LENGTH);
+        assert_ne!(stored, stored_key(&"y".repeat(MAX_KEY_LENGTH + 1)));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::database::reviewable::identifier::{Dic, Ico};
+use crate::PodelError;
+use async_trait::async_trait;
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Records served by the `fixtures` registry unless another file is given.
+const BUNDLED_FIXTURES: &str = include_str!("../fixtures/registry.json");
+/// Time the registry may take to answer.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Args, Debug, Clone)]
+pub struct RegistryArgs {
+    /// Where organizations are looked up by IČO. Use `fixtures` to work offline.
+    #[clap(long = "business-registry", env = "BUSINESS_REGISTRY", value_enum, default_value = "ares")]
+    pub kind: RegistryKind,
+    /// Base URL of the ARES REST API.
+    #[clap(
+        long = "ares-url",
+        env = "ARES_URL",
+        default_value = "https://ares.gov.cz/ekonomicke-subjekty-v-be/rest"
+    )]
+    pub ares_url: String,
+    /// JSON file with the records of the `fixtures` registry. The bundled ones are used if unset.
+    #[clap(long = "registry-fixtures", env = "REGISTRY_FIXTURES")]
+    pub fixtures: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryKind {
+    Ares,
+    Fixtures,
+}
+
+impl RegistryArgs {
+    pub fn registry(&self) -> Result<Arc<dyn BusinessRegistry>, PodelError> {
+        Ok(match self.kind {
+            RegistryKind::Ares => Arc::new(AresRegistry::new(&self.ares_url)?),
+            RegistryKind::Fixtures => match &self.fixtures {
+                Some(path) => Arc::new(FixtureRegistry::from_file(path)?),
+                None => Arc::new(FixtureRegistry::bundled()?),
+            },
+        })
+    }
+}
+
+/// Organization as registered under its IČO.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryRecord {
+    pub ico: Ico,
+    pub dic: Option<Dic>,
+    pub name: String,
+    /// Code of the legal form in the Czech code list, e.g. `112` for an s.r.o.
+    pub form: Option<String>,
+    /// registered office
+    pub address: Option<String>,
+}
+
+/// Country whose organizations the registries know, the one of `RegistryRecord::form`.
+pub const REGISTRY_COUNTRY: &str = "CZ";
+
+/// Public register of organizations, e.g. ARES.
+#[async_trait]
+pub trait BusinessRegistry: Send + Sync {
+    /// Record of the organization, `None` if nothing is registered under the IČO.
+    async fn lookup(&self, ico: &Ico) -> Result<Option<RegistryRecord>, PodelError>;
+}
+
+/// Administrativní registr ekonomických subjektů of the Czech Ministry of Finance.
+pub struct AresRegistry {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AresSubject {
+    ico: String,
+    obchodni_jmeno: String,
+    dic: Option<String>,
+    pravni_forma: Option<String>,
+    sidlo: Option<AresAddress>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AresAddress {
+    textova_adresa: Option<String>,
+}
+
+impl AresRegistry {
+    pub fn new(base_url: &str) -> Result<Self, PodelError> {
+        let client = reqwest::Client::builder()
+            .timeout(LOOKUP_TIMEOUT)
+            .user_agent("Podela.me registry lookup")
+            .build()
+            .map_err(|err| PodelError::LookupError(err.to_string()))?;
+
+        Ok(Self { client, base_url: base_url.trim_end_matches('/').to_string() })
+    }
+}
+
+#[async_trait]
+impl BusinessRegistry for AresRegistry {
+    async fn lookup(&self, ico: &Ico) -> Result<Option<RegistryRecord>, PodelError> {
+        let response = self
+            .client
+            .get(format!("{}/ekonomicke-subjekty/{}", self.base_url, ico))
+            .header(reqwest::header::ACCEPT, "application/json")
+            .send()
+            .await
+            .map_err(|err| PodelError::LookupError(err.to_string()))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let body = response
+            .error_for_status()
+            .map_err(|err| PodelError::LookupError(err.to_string()))?
+            .text()
+            .await
+            .map_err(|err| PodelError::LookupError(err.to_string()))?;
+        let subject = serde_json::from_str::<AresSubject>(&body)
+            .map_err(|err| PodelError::LookupError(err.to_string()))?;
+
+        Ok(Some(RegistryRecord {
+            ico: subject.ico.parse()?,
+            // Natural persons have their birth number hidden, anything unparsable is left out.
+            dic: subject.dic.and_then(|dic| dic.parse().ok()),
+            name: subject.obchodni_jmeno,
+            form: subject.pravni_forma,
+            address: subject.sidlo.and_then(|sidlo| sidlo.textova_adresa),
+        }))
+    }
+}
+
+/// Fixed records instead of the network, for development and tests.
+#[derive(Default, Clone)]
+pub struct FixtureRegistry {
+    records: HashMap<Ico, RegistryRecord>,
+}
+
+impl FixtureRegistry {
+    pub fn new(records: impl IntoIterator<Item = RegistryRecord>) -> Self {
+        Self { records: records.into_iter().map(|record| (record.ico.clone(), record)).collect() }
+    }
+
+    /// Fictional organizations shipped in `common/fixtures/registry.json`.
+    pub fn bundled() -> Result<Self, PodelError> {
+        Self::from_json(BUNDLED_FIXTURES)
+    }
+
+    /// Reads a JSON array of records.
+    pub fn from_file(path: &Path) -> Result<Self, PodelError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    fn from_json(json: &str) -> Result<Self, PodelError> {
+        let records = serde_json::from_str::<Vec<RegistryRecord>>(json)
+            .map_err(|err| PodelError::LookupError(err.to_string()))?;
+
+        Ok(Self::new(records))
+    }
+}
+
+#[async_trait]
+impl BusinessRegistry for FixtureRegistry {
+    async fn lookup(&self, ico: &Ico) -> Result<Option<RegistryRecord>, PodelError> {
+        Ok(self.records.get(ico).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn bundled_fixtures_find_registered_organization() {
+        let registry = FixtureRegistry::bundled().unwrap();
+        let record = registry.lookup(&"12345679".parse().unwrap()).await.unwrap().unwrap();
+
+        assert_eq!(record.name, "Test org s.r.o.");
+        assert_eq!(record.form.as_deref(), Some("112"));
+        assert_eq!(record.dic.map(String::from).as_deref(), Some("CZ12345679"));
+    }
+
+    #[tokio::test]
+    async fn bundled_fixtures_miss_unknown_ico() {
+        let registry = FixtureRegistry::bundled().unwrap();
+
+        assert_eq!(registry.lookup(&"27082440".parse().unwrap()).await.unwrap(), None);
+    }
+
+    #[test]
+    fn fixtures_with_invalid_ico_are_rejected() {
+        let json = r#"[{"ico": "12345678", "dic": null, "name": "Broken", "form": null, "address": null}]"#;
+
+        assert!(FixtureRegistry::from_json(json).is_err());
+    }
+}
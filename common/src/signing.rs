@@ -0,0 +1,192 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use crate::PodelError;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Unguessable value for server-side state the client refers to, e.g. a login waiting for its second factor.
+pub fn random_nonce() -> String {
+    let mut nonce = [0_u8; 32];
+    OsRng.fill_bytes(&mut nonce);
+    URL_SAFE_NO_PAD.encode(nonce)
+}
+
+/// Stateless, expiring token in the form `<subject>.<expiration>.<signature>`.
+///
+/// The signature covers the purpose, so a token issued for one flow can't be replayed in another,
+/// and a caller-provided binding, e.g. the e-mail address being verified. Changing the bound value
+/// invalidates every token issued before.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedToken {
+    pub purpose: &'static str,
+    pub subject: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Token whose signature has not been checked yet.
+#[derive(Clone, Debug)]
+pub struct UnverifiedToken {
+    token: SignedToken,
+    signature: Vec<u8>,
+}
+
+impl SignedToken {
+    pub fn new(purpose: &'static str, subject: impl Into<String>, lifetime: chrono::Duration) -> Self {
+        Self {
+            purpose,
+            subject: subject.into(),
+            expires_at: chrono::Utc::now() + lifetime,
+        }
+    }
+
+    pub fn encode(&self, secret: &[u8], binding: &str) -> Result<String, PodelError> {
+        let signature = self.mac(secret, binding)?.finalize().into_bytes();
+        Ok(format!(
+            "{}.{}.{}",
+            self.subject,
+            self.expires_at.timestamp(),
+            URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+
+    /// Splits the token, so the caller can look up the binding of its subject before verifying it.
+    pub fn decode(purpose: &'static str, token: &str) -> Result<UnverifiedToken, PodelError> {
+        let invalid = || PodelError::UserError("Invalid token.".into());
+
+        let mut parts = token.splitn(3, '.');
+        let (Some(subject), Some(expiration), Some(signature)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+
+        let expires_at = expiration
+            .parse::<i64>()
+            .ok()
+            .and_then(|timestamp| chrono::DateTime::from_timestamp(timestamp, 0))
+            .ok_or_else(invalid)?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+
+        Ok(UnverifiedToken {
+            token: Self { purpose, subject: subject.to_string(), expires_at },
+            signature,
+        })
+    }
+
+    fn mac(&self, secret: &[u8], binding: &str) -> Result<HmacSha256, PodelError> {
+        let mut mac = HmacSha256::new_from_slice(secret)
+            .map_err(|err| PodelError::UserError(err.to_string()))?;
+        let expiration = self.expires_at.timestamp().to_string();
+        for part in [self.purpose, self.subject.as_str(), expiration.as_str(), binding] {
+            // Length prefixes keep `ab` + `c` and `a` + `bc` from signing the same bytes.
+            mac.update(&(part.len() as u64).to_be_bytes());
+            mac.update(part.as_bytes());
+        }
+        Ok(mac)
+    }
+}
+
+impl UnverifiedToken {
+    pub fn subject(&self) -> &str {
+        &self.token.subject
+    }
+
+    /// Checks the signature in constant time and rejects expired tokens.
+    pub fn verify(self, secret: &[u8], binding: &str) -> Result<SignedToken, PodelError> {
+        self.token
+            .mac(secret, binding)?
+            .verify_slice(&self.signature)
+            .map_err(|_| PodelError::UserError("Invalid token.".into()))?;
+
+        if self.token.expires_at <= chrono::Utc::now() {
+            return Err(PodelError::UserError("Token has expired.".into()));
+        }
+
+        Ok(self.token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"test secret";
+    const PURPOSE: &str = "verify-email";
+
+    fn issue(lifetime: chrono::Duration) -> String {
+        SignedToken::new(PURPOSE, "user-1", lifetime).encode(SECRET, "user@example.com").unwrap()
+    }
+
+    fn check(purpose: &'static str, token: &str, binding: &str) -> Result<SignedToken, PodelError> {
+        SignedToken::decode(purpose, token)?.verify(SECRET, binding)
+    }
+
+    #[test]
+    fn valid_token_round_trips() {
+        let token = issue(chrono::Duration::hours(1));
+        let unverified = SignedToken::decode(PURPOSE, &token).unwrap();
+
+        assert_eq!(unverified.subject(), "user-1");
+        assert_eq!(unverified.verify(SECRET, "user@example.com").unwrap().subject, "user-1");
+    }
+
+    #[test]
+    fn tampered_subject_is_rejected() {
+        let token = issue(chrono::Duration::hours(1)).replacen("user-1", "user-2", 1);
+        assert!(check(PURPOSE, &token, "user@example.com").is_err());
+    }
+
+    #[test]
+    fn tampered_expiration_is_rejected() {
+        let token = issue(chrono::Duration::hours(1));
+        let mut parts = token.splitn(3, '.');
+        let (subject, expiration, signature) = (parts.next().unwrap(), parts.next().unwrap(), parts.next().unwrap());
+        let extended = format!("{subject}.{}.{signature}", expiration.parse::<i64>().unwrap() + 86400);
+
+        assert!(check(PURPOSE, &extended, "user@example.com").is_err());
+    }
+
+    #[test]
+    fn tampered_signature_is_rejected() {
+        let token = issue(chrono::Duration::hours(1));
+        let (rest, signature) = token.rsplit_once('.').unwrap();
+        let forged = format!("{rest}.{}", URL_SAFE_NO_PAD.encode([0_u8; 32]));
+
+        assert_ne!(signature, URL_SAFE_NO_PAD.encode([0_u8; 32]));
+        assert!(check(PURPOSE, &forged, "user@example.com").is_err());
+    }
+
+    #[test]
+    fn wrong_secret_is_rejected() {
+        let token = issue(chrono::Duration::hours(1));
+        let unverified = SignedToken::decode(PURPOSE, &token).unwrap();
+
+        assert!(unverified.verify(b"other secret", "user@example.com").is_err());
+    }
+
+    #[test]
+    fn expired_token_is_rejected() {
+        let token = issue(chrono::Duration::seconds(-1));
+        assert!(check(PURPOSE, &token, "user@example.com").is_err());
+    }
+
+    #[test]
+    fn token_for_another_purpose_is_rejected() {
+        let token = issue(chrono::Duration::hours(1));
+        assert!(check("reset-password", &token, "user@example.com").is_err());
+    }
+
+    #[test]
+    fn changed_binding_is_rejected() {
+        let token = issue(chrono::Duration::hours(1));
+        assert!(check(PURPOSE, &token, "other@example.com").is_err());
+    }
+
+    #[test]
+    fn malformed_tokens_are_rejected() {
+        for token in ["", "user-1", "user-1.123", "user-1.soon.c2ln", "user-1.123.not base64!"] {
+            assert!(SignedToken::decode(PURPOSE, token).is_err(), "{token}");
+        }
+    }
+}
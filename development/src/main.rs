@@ -1,7 +1,12 @@
 use crate::docker::Docker;
+use clap::Parser;
+use common::database::reviewable::app::{App, AppPlatform};
+use common::database::reviewable::eshop::Eshop;
+use common::database::reviewable::service::Service;
 use common::database::reviewable::website::Website;
-use common::database::reviewable::{LegalForm, Organization};
+use common::database::reviewable::{Karma, LegalForm, Organization, Reviewable, KINDS};
 use common::database::{create_pool, migrate};
+use common::registry::{BusinessRegistry, FixtureRegistry, REGISTRY_COUNTRY};
 use common::PodelError;
 use notify::{Error, RecursiveMode, Watcher};
 use sqlx::{Pool, Postgres};
@@ -26,6 +31,7 @@ async fn main() -> Result<(), PodelError> {
             .arg("watch")
             .arg("-x")
             .arg("run --package website")
+            .env("BUSINESS_REGISTRY", "fixtures")
             .current_dir(Path::new("website"))
             .spawn()
             .unwrap();
@@ -42,20 +48,38 @@ async fn main() -> Result<(), PodelError> {
 }
 
 async fn setup_dev(pool: &Pool<Postgres>) -> Result<(), PodelError> {
-    let admin = common::database::user::User::register(
+    let mut admin = common::database::user::User::register(
         &pool,
         "admin",
         Some("test@example.com"),
         "admin",
         true,
     ).await.unwrap_or(common::database::user::User::find("admin", &pool).await?);
-    let org = Organization::new("Test org", LegalForm::Sro, Some(admin.clone()));
+    admin.mark_verified(&pool).await?;
+    let record = FixtureRegistry::bundled()?
+        .lookup(&"12345679".parse()?)
+        .await?
+        .ok_or(PodelError::Empty())?;
+    let form = LegalForm::find(&pool, REGISTRY_COUNTRY, record.form.clone().unwrap_or_default()).await?;
+    let mut org = Organization::from_record(record, form);
+    org.user = Some(admin.clone());
     let web_test1 = Website::new("test1", "example.com", None::<String>, Some(org));
-    let _ = web_test1.save(&pool).await;
-    let _ = Website::new("test2", "google.com", Some("Short description test\nyes"), None).save(&pool).await;
+    let _ = web_test1.save(&pool, None).await;
+    let _ = Website::new("test2", "google.com", Some("Short description test\nyes"), None).save(&pool, None).await;
+    let _ = App::new("test app", AppPlatform::Android, None::<String>, web_test1.organization.clone())
+        .with_store_url("https://play.google.com/store/apps/details?id=com.example.test")
+        .save(&pool, None)
+        .await;
+    let _ = Eshop::new("test shop", "shop.example.com", None::<String>, web_test1.organization.clone()).save(&pool, None).await;
+    let _ = Service::new("test branch", "Václavské náměstí 1, Praha", Some("Open on weekdays"), None).save(&pool, None).await;
 
     let _ = Comment::new("website", web_test1.id, "test text", Arc::new(admin)).save(&pool).await?;
 
+    let weights = common::args::CliArgs::parse().karma;
+    for kind in KINDS {
+        Karma::recompute_all(&pool, kind, &weights).await?;
+    }
+
     Ok(())
 }
 
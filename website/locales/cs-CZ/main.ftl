@@ -0,0 +1,318 @@
+user-id = ID uživatele
+member-since = Členem od
+login = Přihlášení
+register = Registrace
+username = Uživatelské jméno
+password = Heslo
+submit = Odeslat
+websites = Weby
+organizations = Organizace
+search = Hledat
+website = Web
+domain = Doména
+karma = Karma
+reviews = Recenze
+age = Stáří
+popularity = Popularita
+organization = Organizace
+owner = Vlastník
+review-title = Nadpis
+review-content = Podělte se o svou zkušenost
+rating-overall = Celkově
+rating-reliability = Spolehlivost
+rating-quality = Kvalita
+rating-support = Podpora
+no-reviews = Zatím žádné recenze.
+edited = upraveno
+legal-form = Právní forma
+no-owner = Bez vlastníka
+organization-subjects = Weby, aplikace a služby
+no-subjects = Tato organizace zatím nemá nic k hodnocení.
+organization-id = ID organizace
+show-replies = Zobrazit odpovědi ({ $count })
+comments = Komentáře
+write-comment = Napsat komentář
+reply = Odpovědět
+edit = Upravit
+delete = Smazat
+history = Historie
+confirm-delete = Opravdu to chcete smazat?
+comment-deleted = smazáno
+no-history = Tento komentář nebyl upraven.
+sort-newest = Nejnovější
+sort-top = Nejlepší
+sort-controversial = Kontroverzní
+search-placeholder = Hledat weby, organizace a uživatele
+no-results = Nic nenalezeno.
+api-tokens = API tokeny
+create-token = Vytvořit token
+token-name = Název
+token-scopes = Oprávnění
+token-scope-read = Čtení
+token-scope-write_comments = Psaní komentářů
+token-scope-admin = Plný přístup k účtu (bez administrace)
+token-expiration = Platnost do
+token-never-expires = Neomezeně
+token-last-used = Naposledy použit
+token-created = Váš nový token
+token-shown-once = Zkopírujte si jej hned, znovu už zobrazen nebude.
+revoke = Zneplatnit
+login-required = Nejprve se musíte přihlásit.
+home = Domů
+about = O nás
+logout = Odhlásit se
+sign-up = Registrovat se
+account-type = Typ účtu
+administrator = Administrátor
+regular-user = Běžný uživatel
+user-not-found = Uživatel nenalezen
+user-not-found-description = Hledaný uživatel neexistuje.
+organization-not-found = Organizace nenalezena
+organization-not-found-description = Hledaná organizace neexistuje.
+description = Popis
+total-karma = Celková karma
+age-score = Skóre stáří
+website-id = ID webu
+website-not-found = Web nenalezen
+website-not-found-description = Hledaný web neexistuje.
+none = Žádná
+language = Jazyk
+legal-form-cz-101 = Osoba samostatně výdělečně činná
+legal-form-cz-111 = Veřejná obchodní společnost
+legal-form-cz-112 = Společnost s ručením omezeným
+legal-form-cz-113 = Komanditní společnost
+legal-form-cz-117 = Nadace
+legal-form-cz-118 = Nadační fond
+legal-form-cz-121 = Akciová společnost
+legal-form-cz-141 = Obecně prospěšná společnost
+legal-form-cz-161 = Ústav
+legal-form-cz-205 = Družstvo
+legal-form-cz-301 = Státní podnik
+legal-form-cz-706 = Zapsaný spolek
+legal-form-sk-101 = Živnostník
+legal-form-sk-111 = Verejná obchodná spoločnosť
+legal-form-sk-112 = Spoločnosť s ručením obmedzeným
+legal-form-sk-113 = Komanditná spoločnosť
+legal-form-sk-121 = Akciová spoločnosť
+legal-form-sk-205 = Družstvo
+legal-form-sk-701 = Občianske združenie
+legal-form-at-gmbh = Společnost s ručením omezeným
+legal-form-de-ag = Akciová společnost
+legal-form-de-gmbh = Společnost s ručením omezeným
+legal-form-gb-ltd = Soukromá společnost s ručením omezeným
+legal-form-gb-plc = Veřejná akciová společnost
+legal-form-us-inc = Korporace
+legal-form-us-llc = Společnost s ručením omezeným
+country-at = Rakousko
+country-cz = Česko
+country-de = Německo
+country-gb = Spojené království
+country-sk = Slovensko
+country-us = Spojené státy
+settings = Nastavení
+sessions = Přihlášená zařízení
+session-device = Zařízení
+session-ip = IP adresa
+session-created = Přihlášeno
+session-last-seen = Naposledy aktivní
+session-unknown-device = Neznámé zařízení
+session-current = Toto zařízení
+logout-everywhere = Odhlásit se všude
+confirm-logout-everywhere = Opravdu se chcete odhlásit na všech zařízeních, včetně tohoto?
+session-pin-ip = Odhlásit mě při změně IP adresy
+session-pinned = Připnuto
+email = E-mail
+email-not-verified = Potvrďte svou e-mailovou adresu, abyste mohli psát komentáře a recenze.
+resend-verification = Znovu poslat ověřovací e-mail
+verification-sent = Ověřovací e-mail odeslán
+email-verified = E-mailová adresa potvrzena
+email-verified-description = Nyní můžete psát komentáře a recenze.
+email-verification-failed = Ověření se nezdařilo
+email-verification-failed-description = Odkaz je neplatný nebo vypršel. Po přihlášení si vyžádejte nový.
+verify-email-subject = Potvrďte svou e-mailovou adresu
+verify-email-body =
+    Dobrý den, { $name },
+
+    potvrďte prosím svou e-mailovou adresu otevřením odkazu níže. Platí 48 hodin.
+
+    { $link }
+
+    Pokud jste si účet nevytvořili, můžete tento e-mail ignorovat.
+change-password = Změnit heslo
+current-password = Současné heslo
+new-password = Nové heslo
+password-changed = Vaše heslo bylo změněno.
+password-change-failed = Heslo se nepodařilo změnit. Zkontrolujte současné heslo.
+forgot-password = Zapomněli jste heslo?
+forgot-password-description = Zadejte e-mailovou adresu svého účtu a pošleme vám odkaz pro nastavení nového hesla.
+reset-link-sent = Pokud účet s touto adresou existuje, odkaz pro obnovení je na cestě.
+reset-password = Nastavit nové heslo
+reset-link-invalid = Tento odkaz je neplatný, vypršel nebo už byl použit.
+reset-password-subject = Obnovení hesla
+reset-password-body =
+    Dobrý den, { $name },
+
+    někdo požádal o obnovení hesla k vašemu účtu. Otevřením odkazu níže si zvolíte nové. Odkaz platí jednu hodinu a lze jej použít jen jednou.
+
+    { $link }
+
+    Pokud jste to nebyli vy, můžete tento e-mail ignorovat. Vaše heslo zůstane beze změny.
+two-factor = Dvoufázové ověření
+two-factor-prompt = Zadejte kód z ověřovací aplikace nebo jeden ze záložních kódů.
+two-factor-code = Kód
+two-factor-enabled = Dvoufázové ověření je zapnuté.
+two-factor-description = Chraňte svůj účet kromě hesla i kódem z ověřovací aplikace.
+two-factor-scan = Naskenujte tento QR kód ověřovací aplikací a zadejte kód, který zobrazí.
+two-factor-manual = Nebo zadejte tento klíč ručně:
+enable-two-factor = Zapnout dvoufázové ověření
+disable-two-factor = Vypnout dvoufázové ověření
+confirm-disable-two-factor = Opravdu chcete vypnout dvoufázové ověření?
+recovery-codes = Záložní kódy
+recovery-codes-description = Uložte si tyto kódy na bezpečné místo. Každý z nich vás jednou přihlásí, pokud přijdete o ověřovací aplikaci. Znovu už zobrazeny nebudou.
+recovery-codes-remaining = Nepoužité záložní kódy: { $count }
+regenerate-recovery-codes = Vygenerovat nové záložní kódy
+require-admin-two-factor = Dvoufázové ověření pro administrátory
+require-admin-two-factor-description = Vyžadovat dvoufázové ověření u všech administrátorů
+admin-two-factor-required = Administrátorská oprávnění platí až po zapnutí dvoufázového ověření.
+done = Hotovo
+admin = Administrace
+users = Uživatelé
+admin-users-description = Deaktivace účtů, udělování administrátorských oprávnění a odesílání odkazů pro obnovu hesla.
+admin-websites-description = Přidávání, úpravy a slučování webů.
+admin-apps-description = Přidávání a úpravy mobilních aplikací.
+admin-eshops-description = Přidávání a úpravy e-shopů.
+admin-services-description = Přidávání a úpravy provozoven.
+admin-organizations-description = Přidávání, úpravy a slučování organizací.
+admin-comments-description = Kontrola a mazání nejnovějších komentářů.
+admin-sessions-description = Kdo je přihlášen a ukončení jeho relací.
+admin-user-search = Jméno nebo e-mail
+deactivated = Deaktivován
+unverified = Neověřen
+activate = Aktivovat
+deactivate = Deaktivovat
+confirm-deactivate = Opravdu chcete tento účet deaktivovat? Uživatel bude odhlášen ze všech zařízení.
+promote = Udělit administrátora
+confirm-promote = Opravdu chcete tomuto uživateli udělit administrátorská oprávnění?
+demote = Odebrat administrátora
+send-password-reset = Poslat odkaz pro obnovu hesla
+password-reset-sent = Odkaz pro obnovu hesla odeslán
+next-page = Další stránka
+author = Autor
+comment = Komentář
+created = Vytvořeno
+merge = Sloučit
+merge-into = ID nebo název cíle
+merge-website-description = Přesune komentáře, recenze a návštěvy na jiný web a tento smaže.
+merge-organization-description = Přesune weby do jiné organizace a tuto smaže.
+confirm-merge = Opravdu chcete sloučit? Tuto akci nelze vrátit.
+last-login = Poslední přihlášení
+never = Nikdy
+suspended-until = Pozastaven do { $until }
+banned = Zablokován
+suspend = Pozastavit
+unsuspend = Zrušit pozastavení
+suspension-reason = Důvod
+suspension-days = Počet dní, 0 pro trvalý zákaz
+report = Nahlásit
+report-sent = Nahlášeno, děkujeme
+report-details = Co je špatně? (nepovinné)
+report-reason-spam = Spam nebo reklama
+report-reason-defamation = Pomluva
+report-reason-harassment = Obtěžování
+report-reason-illegal = Nezákonný obsah
+report-reason-other = Něco jiného
+comment-removed-by-moderator = [odstraněno moderátorem]
+comment-deleted-placeholder = [smazáno]
+reports = Nahlášení
+admin-reports-description = Řešení obsahu nahlášeného uživateli.
+report-target = Nahlášený obsah
+report-target-comment = Komentář
+report-target-review = Recenze
+report-target-subject = Subjekt
+report-target-gone = Obsah už neexistuje
+report-reason = Důvod
+reported-by = Nahlásil
+moderation-hide = Skrýt
+moderation-delete = Smazat
+moderation-warn = Varovat autora
+moderation-ban = Zablokovat autora
+moderation-dismiss = Zamítnout
+confirm-moderation = Opravdu to chcete udělat?
+hide = Skrýt
+hidden = Skryto
+deleted = Smazáno
+restore = Obnovit
+moderation-warning-subject = Váš obsah byl nahlášen
+moderation-warning-body =
+    Dobrý den, { $name },
+
+    moderátor prověřil nahlášení vašeho příspěvku a zjistil, že porušuje pravidla webu. Pište prosím komentáře a recenze věcně a slušně, opakované porušení vede k zablokování.
+
+    Obsah najdete na této stránce: { $link }
+audit-log = Auditní záznam
+admin-audit-description = Kdo a kdy měnil subjekty, komentáře, recenze a účty.
+actor = Aktér
+action = Akce
+target-type = Typ cíle
+target-id = ID cíle
+ip-address = IP adresa
+before = Před
+after = Po
+filter = Filtrovat
+verify-ownership = Ověřit vlastnictví
+verified-owner = Ověřený vlastník
+verification-method-dns = DNS záznam TXT
+verification-method-well-known = Ověřovací soubor
+verification-dns-instructions = Přidejte k doméně { $domain } záznam TXT s následující hodnotou.
+verification-well-known-instructions = Nebo následující token zveřejněte jako textový soubor na adrese { $url }.
+verification-check = Zkontrolovat
+verification-failed = Token zatím nebyl nalezen. Změny v DNS se mohou projevit až po nějaké době.
+verification-last-checked = Naposledy zkontrolováno { $at }.
+claims = Nároky na organizace
+admin-claims-description = Žádosti uživatelů o převzetí organizace.
+claim-organization = Převzít tuto organizaci
+claim-evidence = Doklady
+claimant = Žadatel
+claim-note = Poznámka pro žadatele
+your-claim = Vaše žádost
+claim-status-pending = Čeká na vyřízení
+claim-status-approved = Schváleno
+claim-status-rejected = Zamítnuto
+approve = Schválit
+reject = Zamítnout
+official-response = Oficiální odpověď
+post-as-official = Odeslat jako oficiální odpověď
+respond-officially = Odpovědět oficiálně
+official-response-hint = Prázdným polem odpověď stáhnete.
+claim-approved-subject = Vaše žádost o převzetí organizace byla schválena
+claim-approved-body =
+    Dobrý den, { $name },
+
+    vaše žádost byla schválena a nyní jste vlastníkem organizace. Ověřte vlastnictví jejích webů, abyste mohli oficiálně odpovídat na komentáře a recenze.
+
+    Organizaci najdete na této stránce: { $link }
+claim-rejected-subject = Vaše žádost o převzetí organizace byla zamítnuta
+claim-rejected-body =
+    Dobrý den, { $name },
+
+    administrátor vaši žádost posoudil a zamítl. Případné odůvodnění najdete na stránce organizace.
+
+    Organizaci najdete na této stránce: { $link }
+ico = IČO
+dic = DIČ
+address = Sídlo
+registry-lookup = Vyhledat v registru
+registry-not-found = Pod tímto IČO není registrována žádná organizace.
+
+app = Mobilní aplikace
+apps = Mobilní aplikace
+eshop = E-shop
+eshops = E-shopy
+service = Provozovna
+services = Provozovny
+platform = Platforma
+store-url = Stránka v obchodě
+service-address = Adresa
+subject-id = ID
+subject-not-found = Nenalezeno
+subject-not-found-description = Hledaná stránka neexistuje.
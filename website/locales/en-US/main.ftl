@@ -15,4 +15,304 @@ reviews = Reviews
 age = Age
 popularity = Popularity
 organization = Organization
-owner = Owner
\ No newline at end of file
+owner = Owner
+review-title = Title
+review-content = Share your experience
+rating-overall = Overall
+rating-reliability = Reliability
+rating-quality = Quality
+rating-support = Support
+no-reviews = No reviews yet.
+edited = edited
+legal-form = Legal form
+no-owner = No owner
+organization-subjects = Websites, apps and services
+no-subjects = This organization has nothing to review yet.
+organization-id = Organization ID
+show-replies = Show replies ({ $count })
+comments = Comments
+write-comment = Write a comment
+reply = Reply
+edit = Edit
+delete = Delete
+history = History
+confirm-delete = Do you really want to delete this?
+comment-deleted = deleted
+no-history = This comment has not been edited.
+sort-newest = Newest
+sort-top = Top
+sort-controversial = Controversial
+search-placeholder = Search websites, organizations and users
+no-results = Nothing found.
+api-tokens = API tokens
+create-token = Create token
+token-name = Name
+token-scopes = Scopes
+token-scope-read = Read
+token-scope-write_comments = Write comments
+token-scope-admin = Full account access (no administration)
+token-expiration = Expires
+token-never-expires = Never
+token-last-used = Last used
+token-created = Your new token
+token-shown-once = Copy it now, it will not be shown again.
+revoke = Revoke
+login-required = You need to log in first.
+home = Home
+about = About
+logout = Logout
+sign-up = Sign Up
+account-type = Account type
+administrator = Administrator
+regular-user = Regular user
+user-not-found = User not found
+user-not-found-description = The user you are looking for does not exist.
+organization-not-found = Organization not found
+organization-not-found-description = The organization you are looking for does not exist.
+description = Description
+total-karma = Total karma
+age-score = Age score
+website-id = Website ID
+website-not-found = Website not found
+website-not-found-description = The website you are looking for does not exist.
+none = None
+language = Language
+legal-form-cz-101 = Sole trader
+legal-form-cz-111 = General partnership
+legal-form-cz-112 = Limited liability company
+legal-form-cz-113 = Limited partnership
+legal-form-cz-117 = Foundation
+legal-form-cz-118 = Endowment fund
+legal-form-cz-121 = Joint-stock company
+legal-form-cz-141 = Public benefit company
+legal-form-cz-161 = Institute
+legal-form-cz-205 = Cooperative
+legal-form-cz-301 = State enterprise
+legal-form-cz-706 = Registered association
+legal-form-sk-101 = Sole trader
+legal-form-sk-111 = General partnership
+legal-form-sk-112 = Limited liability company
+legal-form-sk-113 = Limited partnership
+legal-form-sk-121 = Joint-stock company
+legal-form-sk-205 = Cooperative
+legal-form-sk-701 = Civic association
+legal-form-at-gmbh = Limited liability company
+legal-form-de-ag = Stock corporation
+legal-form-de-gmbh = Limited liability company
+legal-form-gb-ltd = Private limited company
+legal-form-gb-plc = Public limited company
+legal-form-us-inc = Corporation
+legal-form-us-llc = Limited liability company
+country-at = Austria
+country-cz = Czechia
+country-de = Germany
+country-gb = United Kingdom
+country-sk = Slovakia
+country-us = United States
+settings = Settings
+sessions = Sessions
+session-device = Device
+session-ip = IP address
+session-created = Logged in
+session-last-seen = Last active
+session-unknown-device = Unknown device
+session-current = This device
+logout-everywhere = Log out everywhere
+confirm-logout-everywhere = Do you really want to log out on all devices, including this one?
+session-pin-ip = Log me out when my IP address changes
+session-pinned = Pinned
+email = E-mail
+email-not-verified = Confirm your e-mail address to post comments and reviews.
+resend-verification = Resend verification e-mail
+verification-sent = Verification e-mail sent
+email-verified = E-mail address confirmed
+email-verified-description = You can now post comments and reviews.
+email-verification-failed = Verification failed
+email-verification-failed-description = The link is invalid or has expired. Request a new one after logging in.
+verify-email-subject = Confirm your e-mail address
+verify-email-body =
+    Hi { $name },
+
+    please confirm your e-mail address by opening the link below. It is valid for 48 hours.
+
+    { $link }
+
+    If you did not create an account, you can ignore this e-mail.
+change-password = Change password
+current-password = Current password
+new-password = New password
+password-changed = Your password has been changed.
+password-change-failed = The password could not be changed. Check your current password.
+forgot-password = Forgot your password?
+forgot-password-description = Enter the e-mail address of your account and we will send you a link to set a new password.
+reset-link-sent = If an account with this address exists, a reset link is on its way.
+reset-password = Set a new password
+reset-link-invalid = This link is invalid, has expired or has already been used.
+reset-password-subject = Reset your password
+reset-password-body =
+    Hi { $name },
+
+    someone asked to reset the password of your account. Open the link below to choose a new one. It is valid for one hour and works only once.
+
+    { $link }
+
+    If it was not you, you can ignore this e-mail. Your password stays unchanged.
+two-factor = Two-factor authentication
+two-factor-prompt = Enter the code from your authenticator app, or one of your recovery codes.
+two-factor-code = Code
+two-factor-enabled = Two-factor authentication is enabled.
+two-factor-description = Protect your account with a code from an authenticator app in addition to your password.
+two-factor-scan = Scan this QR code with your authenticator app, then enter the code it shows.
+two-factor-manual = Or enter this key manually:
+enable-two-factor = Enable two-factor authentication
+disable-two-factor = Disable two-factor authentication
+confirm-disable-two-factor = Do you really want to disable two-factor authentication?
+recovery-codes = Recovery codes
+recovery-codes-description = Store these codes somewhere safe. Each of them lets you log in once if you lose your authenticator. They will not be shown again.
+recovery-codes-remaining = Unused recovery codes: { $count }
+regenerate-recovery-codes = Generate new recovery codes
+require-admin-two-factor = Two-factor authentication for admins
+require-admin-two-factor-description = Require two-factor authentication for all administrators
+admin-two-factor-required = Administrator privileges apply only after you enable two-factor authentication.
+done = Done
+admin = Administration
+users = Users
+admin-users-description = Deactivate accounts, grant administrator privileges and send password reset links.
+admin-websites-description = Add, edit and merge websites.
+admin-apps-description = Add and edit mobile apps.
+admin-eshops-description = Add and edit e-shops.
+admin-services-description = Add and edit services.
+admin-organizations-description = Add, edit and merge organizations.
+admin-comments-description = Review and delete the newest comments.
+admin-sessions-description = See who is logged in and end their sessions.
+admin-user-search = Name or e-mail
+deactivated = Deactivated
+unverified = Unverified
+activate = Activate
+deactivate = Deactivate
+confirm-deactivate = Do you really want to deactivate this account? The user will be logged out everywhere.
+promote = Make administrator
+confirm-promote = Do you really want to give this user administrator privileges?
+demote = Revoke administrator
+send-password-reset = Send password reset link
+password-reset-sent = Password reset link sent
+next-page = Next page
+author = Author
+comment = Comment
+created = Created
+merge = Merge
+merge-into = ID or name of the target
+merge-website-description = Moves comments, reviews and visits to another website and deletes this one.
+merge-organization-description = Moves websites to another organization and deletes this one.
+confirm-merge = Do you really want to merge? This can't be undone.
+last-login = Last login
+never = Never
+suspended-until = Suspended until { $until }
+banned = Banned
+suspend = Suspend
+unsuspend = Lift suspension
+suspension-reason = Reason
+suspension-days = Days, 0 for a permanent ban
+report = Report
+report-sent = Reported, thank you
+report-details = What is wrong? (optional)
+report-reason-spam = Spam or advertising
+report-reason-defamation = Defamation
+report-reason-harassment = Harassment
+report-reason-illegal = Illegal content
+report-reason-other = Something else
+comment-removed-by-moderator = [removed by moderator]
+comment-deleted-placeholder = [deleted]
+reports = Reports
+admin-reports-description = Act on content reported by users.
+report-target = Reported content
+report-target-comment = Comment
+report-target-review = Review
+report-target-subject = Subject
+report-target-gone = Content no longer exists
+report-reason = Reason
+reported-by = Reported by
+moderation-hide = Hide
+moderation-delete = Delete
+moderation-warn = Warn author
+moderation-ban = Ban author
+moderation-dismiss = Dismiss
+confirm-moderation = Do you really want to do this?
+hide = Hide
+hidden = Hidden
+deleted = Deleted
+restore = Restore
+moderation-warning-subject = Your content was reported
+moderation-warning-body =
+    Hi { $name },
+
+    a moderator reviewed a report of something you posted and found that it breaks the rules of the site. Please keep your comments and reviews factual and respectful, repeated violations lead to a ban.
+
+    The content is on this page: { $link }
+audit-log = Audit log
+admin-audit-description = Who changed subjects, comments, reviews and accounts, and when.
+actor = Actor
+action = Action
+target-type = Target type
+target-id = Target ID
+ip-address = IP address
+before = Before
+after = After
+filter = Filter
+verify-ownership = Verify ownership
+verified-owner = Verified owner
+verification-method-dns = DNS TXT record
+verification-method-well-known = Verification file
+verification-dns-instructions = Add a TXT record with the following value to { $domain }.
+verification-well-known-instructions = Or serve the following token as a plain text file at { $url }.
+verification-check = Check now
+verification-failed = The token was not found yet. DNS changes can take a while to propagate.
+verification-last-checked = Last checked { $at }.
+claims = Organization claims
+admin-claims-description = Requests of users to become the owner of an organization.
+claim-organization = Claim this organization
+claim-evidence = Evidence
+claimant = Claimant
+claim-note = Note for the claimant
+your-claim = Your claim
+claim-status-pending = Pending
+claim-status-approved = Approved
+claim-status-rejected = Rejected
+approve = Approve
+reject = Reject
+official-response = Official response
+post-as-official = Post as an official response
+respond-officially = Respond officially
+official-response-hint = Leave empty to withdraw the response.
+claim-approved-subject = Your organization claim was approved
+claim-approved-body =
+    Hi { $name },
+
+    your claim was approved and you are now the owner of the organization. Verify ownership of its websites to respond to comments and reviews officially.
+
+    The organization is on this page: { $link }
+claim-rejected-subject = Your organization claim was rejected
+claim-rejected-body =
+    Hi { $name },
+
+    an administrator reviewed your claim and rejected it. The reason, if given, is shown on the page of the organization.
+
+    The organization is on this page: { $link }
+ico = Company ID (IČO)
+dic = VAT number (DIČ)
+address = Registered office
+registry-lookup = Look up in registry
+registry-not-found = No organization is registered under this IČO.
+
+app = Mobile app
+apps = Mobile apps
+eshop = E-shop
+eshops = E-shops
+service = Service
+services = Services
+platform = Platform
+store-url = Store listing
+service-address = Address
+subject-id = ID
+subject-not-found = Not found
+subject-not-found-description = The page you are looking for does not exist.
@@ -0,0 +1,8 @@
+use poem::Route;
+
+pub mod v1;
+
+pub fn route() -> Route {
+    Route::new()
+        .nest("/v1", v1::route())
+}
@@ -0,0 +1,55 @@
+use common::PodelError;
+use log::error;
+use poem_openapi::payload::Json;
+use poem_openapi::{ApiResponse, Object};
+
+/// Body of every error response.
+#[derive(Object, Debug, Clone)]
+pub struct ErrorBody {
+    pub status: u16,
+    pub message: String,
+}
+
+impl ErrorBody {
+    fn new(status: u16, message: impl Into<String>) -> Json<Self> {
+        Json(Self { status, message: message.into() })
+    }
+}
+
+#[derive(ApiResponse, Debug)]
+pub enum ApiError {
+    /// The request is malformed.
+    #[oai(status = 400)]
+    BadRequest(Json<ErrorBody>),
+    /// The requested resource does not exist.
+    #[oai(status = 404)]
+    NotFound(Json<ErrorBody>),
+    /// Something went wrong on our side.
+    #[oai(status = 500)]
+    Internal(Json<ErrorBody>),
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::BadRequest(ErrorBody::new(400, message))
+    }
+
+    pub fn not_found() -> Self {
+        Self::NotFound(ErrorBody::new(404, "Not found."))
+    }
+}
+
+impl From<PodelError> for ApiError {
+    fn from(err: PodelError) -> Self {
+        match err {
+            PodelError::SqlxError(sqlx::Error::RowNotFound) | PodelError::Empty() => Self::not_found(),
+            PodelError::UserError(message) => Self::bad_request(message),
+            err => {
+                error!("API request failed: {}", err);
+                Self::Internal(ErrorBody::new(500, "Internal server error."))
+            }
+        }
+    }
+}
+
+pub type ApiResult<T> = Result<Json<T>, ApiError>;
@@ -0,0 +1,170 @@
+use crate::api::v1::error::{ApiError, ApiResult};
+use crate::api::v1::model::{ApiComment, ApiOrganization, ApiOrganizationDetail, ApiReview, ApiUser, ApiWebsite};
+use crate::page::subject::SubjectCard;
+use common::database::comment::{Comment, CommentSort};
+use common::database::review::Review;
+use common::database::reviewable::website::Website;
+use common::database::reviewable::{Organization, Reviewable};
+use common::database::user::User;
+use common::AppState;
+use poem::web::Data;
+use poem::{EndpointExt, Route};
+use poem_openapi::param::{Path, Query};
+use poem_openapi::payload::Json;
+use poem_openapi::{OpenApi, OpenApiService, Tags};
+use std::sync::Arc;
+
+pub mod error;
+pub mod model;
+
+/// Same defaults as the `DbQuery` of the HTML pages.
+const DEFAULT_LIMIT: i64 = 10;
+const DEFAULT_OFFSET: i64 = 0;
+
+#[derive(Tags)]
+enum ApiTags {
+    Websites,
+    Organizations,
+    Users,
+    Comments,
+    Reviews,
+}
+
+pub struct Api;
+
+#[OpenApi]
+impl Api {
+    /// List websites, newest first.
+    #[oai(path = "/websites", method = "get", tag = "ApiTags::Websites")]
+    async fn websites(
+        &self,
+        state: Data<&Arc<AppState>>,
+        limit: Query<Option<i64>>,
+        offset: Query<Option<i64>>,
+    ) -> ApiResult<Vec<ApiWebsite>> {
+        let websites = Website::find_multiple(&state.pool, limit.unwrap_or(DEFAULT_LIMIT), offset.unwrap_or(DEFAULT_OFFSET)).await?;
+        Ok(Json(websites.into_iter().map(Into::into).collect()))
+    }
+
+    /// Find a website by its id.
+    #[oai(path = "/websites/:id", method = "get", tag = "ApiTags::Websites")]
+    async fn website(&self, state: Data<&Arc<AppState>>, id: Path<String>) -> ApiResult<ApiWebsite> {
+        Ok(Json(Website::find(&state.pool, id.0).await?.into()))
+    }
+
+    /// Top-level comments of a website, sorted by `newest` (default), `top` or `controversial`.
+    #[oai(path = "/websites/:id/comments", method = "get", tag = "ApiTags::Comments")]
+    async fn website_comments(
+        &self,
+        state: Data<&Arc<AppState>>,
+        id: Path<String>,
+        sort: Query<Option<String>>,
+        limit: Query<Option<i64>>,
+        offset: Query<Option<i64>>,
+    ) -> ApiResult<Vec<ApiComment>> {
+        let sort = parse_sort(sort.0.as_deref())?;
+        let comments = Comment::find_multiple(
+            &state.pool,
+            "website",
+            id.0,
+            sort,
+            limit.unwrap_or(DEFAULT_LIMIT),
+            offset.unwrap_or(DEFAULT_OFFSET),
+        ).await?;
+        Ok(Json(comments.into_iter().map(Into::into).collect()))
+    }
+
+    /// Direct replies to a comment of a website.
+    #[oai(path = "/websites/:id/comments/:comment_id/replies", method = "get", tag = "ApiTags::Comments")]
+    async fn website_comment_replies(
+        &self,
+        state: Data<&Arc<AppState>>,
+        #[oai(name = "id")] _id: Path<String>,
+        comment_id: Path<String>,
+        limit: Query<Option<i64>>,
+        offset: Query<Option<i64>>,
+    ) -> ApiResult<Vec<ApiComment>> {
+        let comments = Comment::find_replies(
+            &state.pool,
+            "website",
+            comment_id.0,
+            limit.unwrap_or(DEFAULT_LIMIT),
+            offset.unwrap_or(DEFAULT_OFFSET),
+        ).await?;
+        Ok(Json(comments.into_iter().map(Into::into).collect()))
+    }
+
+    /// Reviews of a website, newest first.
+    #[oai(path = "/websites/:id/reviews", method = "get", tag = "ApiTags::Reviews")]
+    async fn website_reviews(
+        &self,
+        state: Data<&Arc<AppState>>,
+        id: Path<String>,
+        limit: Query<Option<i64>>,
+        offset: Query<Option<i64>>,
+    ) -> ApiResult<Vec<ApiReview>> {
+        let reviews = Review::find_multiple(
+            &state.pool,
+            "website",
+            id.0,
+            limit.unwrap_or(DEFAULT_LIMIT),
+            offset.unwrap_or(DEFAULT_OFFSET),
+        ).await?;
+        Ok(Json(reviews.into_iter().map(Into::into).collect()))
+    }
+
+    /// List organizations, newest first.
+    #[oai(path = "/organizations", method = "get", tag = "ApiTags::Organizations")]
+    async fn organizations(
+        &self,
+        state: Data<&Arc<AppState>>,
+        limit: Query<Option<i64>>,
+        offset: Query<Option<i64>>,
+    ) -> ApiResult<Vec<ApiOrganization>> {
+        let organizations = Organization::find_multiple(&state.pool, limit.unwrap_or(DEFAULT_LIMIT), offset.unwrap_or(DEFAULT_OFFSET)).await?;
+        Ok(Json(organizations.into_iter().map(Into::into).collect()))
+    }
+
+    /// Find an organization by its id, IČO or name, including its websites, apps, e-shops and services.
+    #[oai(path = "/organizations/:id", method = "get", tag = "ApiTags::Organizations")]
+    async fn organization(&self, state: Data<&Arc<AppState>>, id: Path<String>) -> ApiResult<ApiOrganizationDetail> {
+        let organization = Organization::find_public(&state.pool, &id.0).await?;
+        let (websites, subjects) = tokio::try_join!(
+            Website::find_by_organization(&state.pool, &organization.id),
+            SubjectCard::find_by_organization(&state, &organization.id),
+        )?;
+
+        Ok(Json(ApiOrganizationDetail {
+            organization: organization.into(),
+            websites: websites.into_iter().map(Into::into).collect(),
+            subjects: subjects.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    /// Find a user by their id or name.
+    #[oai(path = "/users/:id", method = "get", tag = "ApiTags::Users")]
+    async fn user(&self, state: Data<&Arc<AppState>>, id: Path<String>) -> ApiResult<ApiUser> {
+        Ok(Json(User::find(id.0, &state.pool).await?.into()))
+    }
+}
+
+fn parse_sort(sort: Option<&str>) -> Result<CommentSort, ApiError> {
+    match sort {
+        None | Some("newest") => Ok(CommentSort::Newest),
+        Some("top") => Ok(CommentSort::Top),
+        Some("controversial") => Ok(CommentSort::Controversial),
+        Some(other) => Err(ApiError::bad_request(format!("Unknown sort: {other}."))),
+    }
+}
+
+/// API routes together with the generated OpenAPI document at `/openapi.json` and its UI at `/docs`.
+pub fn route() -> Route {
+    let service = OpenApiService::new(Api, "Podela.me", "1.0").server("/api/v1");
+    let spec = service.spec_endpoint();
+    let docs = service.swagger_ui();
+
+    Route::new()
+        .nest("/", service)
+        .at("/openapi.json", spec)
+        .nest("/docs", docs)
+}
@@ -0,0 +1,245 @@
+use common::database::comment::Comment;
+use common::database::review::{Rating, Review};
+use common::database::reviewable::website::Website;
+use common::database::reviewable::{Karma, Organization};
+use common::database::user::User;
+use crate::filters::LOCALES;
+use crate::page::subject::SubjectCard;
+use crate::DEFAULT_LANGUAGE;
+use fluent_templates::Loader;
+use poem_openapi::Object;
+use std::collections::HashMap;
+
+/// Public part of a user profile.
+#[derive(Object, Debug, Clone)]
+pub struct ApiUser {
+    pub id: String,
+    pub name: String,
+    pub is_admin: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<User> for ApiUser {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            is_admin: user.is_admin,
+            created_at: user.created_at,
+        }
+    }
+}
+
+#[derive(Object, Debug, Clone)]
+pub struct ApiOrganization {
+    pub id: String,
+    pub name: String,
+    /// Legal form id, country and code, e.g. `CZ-112`.
+    pub form: String,
+    /// Human-readable legal form in the default language.
+    pub form_name: String,
+    /// Suffix the legal form adds to names, e.g. `s.r.o.`.
+    pub form_abbreviation: String,
+    /// ISO 3166-1 alpha-2 code of the country whose law defines the legal form.
+    pub country: String,
+    /// Czech business identifier, 8 digits.
+    pub ico: Option<String>,
+    /// VAT number, e.g. `CZ12345679`.
+    pub dic: Option<String>,
+    /// Registered office.
+    pub address: Option<String>,
+    pub owner: Option<ApiUser>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<Organization> for ApiOrganization {
+    fn from(organization: Organization) -> Self {
+        Self {
+            id: organization.id,
+            name: organization.name,
+            form: organization.form.id(),
+            form_name: LOCALES.lookup(&DEFAULT_LANGUAGE, &organization.form.fluent_key()),
+            form_abbreviation: organization.form.abbreviation,
+            country: organization.form.country,
+            ico: organization.ico.map(Into::into),
+            dic: organization.dic.map(Into::into),
+            address: organization.address,
+            owner: organization.user.map(Into::into),
+            created_at: organization.created_at,
+            updated_at: organization.updated_at,
+        }
+    }
+}
+
+#[derive(Object, Debug, Clone)]
+pub struct ApiKarma {
+    pub amount: i16,
+    pub reviews: i16,
+    pub age: i16,
+    pub popularity: i16,
+}
+
+impl From<Karma> for ApiKarma {
+    fn from(karma: Karma) -> Self {
+        Self {
+            amount: karma.amount,
+            reviews: karma.reviews,
+            age: karma.age,
+            popularity: karma.popularity,
+        }
+    }
+}
+
+#[derive(Object, Debug, Clone)]
+pub struct ApiWebsite {
+    pub id: String,
+    pub name: String,
+    pub domain_name: String,
+    pub description: Option<String>,
+    pub karma: ApiKarma,
+    pub organization: Option<ApiOrganization>,
+}
+
+impl From<Website> for ApiWebsite {
+    fn from(website: Website) -> Self {
+        Self {
+            id: website.id,
+            name: website.name,
+            domain_name: website.domain_name,
+            description: website.description,
+            karma: website.karma.into(),
+            organization: website.organization.map(Into::into),
+        }
+    }
+}
+
+/// Subject of any kind, as listed on the page of its organization.
+#[derive(Object, Debug, Clone)]
+pub struct ApiSubject {
+    /// `website`, `app`, `eshop` or `service`.
+    pub kind: String,
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub karma: ApiKarma,
+    /// Kind-specific values by name, e.g. `domain` or `platform`.
+    pub details: HashMap<String, String>,
+}
+
+impl From<SubjectCard> for ApiSubject {
+    fn from(card: SubjectCard) -> Self {
+        Self {
+            kind: card.kind.to_string(),
+            id: card.id,
+            name: card.name,
+            description: card.description,
+            karma: card.karma.into(),
+            details: card.details.into_iter().map(|(name, value)| (name.to_string(), value)).collect(),
+        }
+    }
+}
+
+/// Organization together with everything of it that can be reviewed.
+#[derive(Object, Debug, Clone)]
+pub struct ApiOrganizationDetail {
+    #[oai(flatten)]
+    pub organization: ApiOrganization,
+    pub websites: Vec<ApiWebsite>,
+    /// Websites, apps, e-shops and services, by kind and name.
+    pub subjects: Vec<ApiSubject>,
+}
+
+#[derive(Object, Debug, Clone)]
+pub struct ApiComment {
+    pub id: String,
+    pub parent_type: String,
+    pub parent_id: String,
+    pub reply_to: Option<String>,
+    pub reply_count: i64,
+    pub upvotes: i64,
+    pub downvotes: i64,
+    pub author: ApiUser,
+    /// Empty for removed comments.
+    pub content: String,
+    /// Deleted by its author or removed by a moderator.
+    pub removed: bool,
+    /// Posted by the verified owner in the name of the organization.
+    pub official: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<Comment> for ApiComment {
+    fn from(comment: Comment) -> Self {
+        let removed = comment.is_removed();
+        Self {
+            id: comment.id,
+            parent_type: comment.parent_type,
+            parent_id: comment.parent_id,
+            reply_to: comment.reply_to,
+            reply_count: comment.reply_count,
+            upvotes: comment.upvotes,
+            downvotes: comment.downvotes,
+            author: comment.user.into(),
+            content: if removed { String::new() } else { comment.content },
+            removed,
+            official: comment.is_official,
+            created_at: comment.created_at,
+            updated_at: comment.updated_at,
+        }
+    }
+}
+
+#[derive(Object, Debug, Clone)]
+pub struct ApiRating {
+    pub overall: i16,
+    pub reliability: i16,
+    pub quality: i16,
+    pub support: i16,
+}
+
+impl From<Rating> for ApiRating {
+    fn from(rating: Rating) -> Self {
+        Self {
+            overall: rating.overall,
+            reliability: rating.reliability,
+            quality: rating.quality,
+            support: rating.support,
+        }
+    }
+}
+
+#[derive(Object, Debug, Clone)]
+pub struct ApiReview {
+    pub id: String,
+    pub parent_type: String,
+    pub parent_id: String,
+    pub author: ApiUser,
+    pub title: String,
+    pub content: String,
+    pub rating: ApiRating,
+    /// Official answer of the website's verified owner.
+    pub response: Option<String>,
+    pub responded_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<Review> for ApiReview {
+    fn from(review: Review) -> Self {
+        Self {
+            id: review.id,
+            parent_type: review.parent_type,
+            parent_id: review.parent_id,
+            author: review.user.into(),
+            title: review.title,
+            content: review.content,
+            rating: review.rating.into(),
+            response: review.response,
+            responded_at: review.responded_at,
+            created_at: review.created_at,
+            updated_at: review.updated_at,
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use common::database::suspension::Suspension;
+use common::database::token::{ApiToken, TokenScope};
+use common::database::user::{SessionData, User};
+use common::AppState;
+use crate::client_ip::client_ip;
+use common::PodelError;
+use log::{debug, error};
+use poem::http::{header, Method, StatusCode};
+use poem::session::Session;
+use poem::{Endpoint, Error, FromRequest, IntoResponse, Middleware, Request, RequestBody, Response, Result};
+use std::sync::Arc;
+
+/// User and token of a request authenticated with `Authorization: Bearer`.
+#[derive(Clone, Debug)]
+pub struct TokenAuth {
+    pub user: Arc<User>,
+    pub token: ApiToken,
+}
+
+/// Logged-in user of a request, either from the session cookie or from an API token.
+pub struct AuthUser(pub Arc<User>);
+
+impl<'a> FromRequest<'a> for AuthUser {
+    async fn from_request(req: &'a Request, _body: &mut RequestBody) -> Result<Self> {
+        if let Some(auth) = req.extensions().get::<TokenAuth>() {
+            return Ok(Self(auth.user.clone()));
+        }
+
+        let user = req
+            .extensions()
+            .get::<Session>()
+            .and_then(|session| User::from_session(session).ok())
+            .ok_or_else(|| Error::from_status(StatusCode::UNAUTHORIZED))?;
+
+        // A cached session may outlive the deactivation by a few seconds.
+        if user.is_active {
+            Ok(Self(user))
+        } else {
+            Err(Error::from_string("Account is deactivated.", StatusCode::FORBIDDEN))
+        }
+    }
+}
+
+/// Logged-in user who confirmed their e-mail address and isn't suspended, required for posting content.
+pub struct VerifiedUser(pub Arc<User>);
+
+impl<'a> FromRequest<'a> for VerifiedUser {
+    async fn from_request(req: &'a Request, body: &mut RequestBody) -> Result<Self> {
+        let AuthUser(user) = AuthUser::from_request(req, body).await?;
+        if !user.is_verified {
+            return Err(Error::from_string("E-mail address is not verified.", StatusCode::FORBIDDEN));
+        }
+
+        let state = req
+            .extensions()
+            .get::<Arc<AppState>>()
+            .ok_or_else(|| Error::from_status(StatusCode::INTERNAL_SERVER_ERROR))?;
+
+        match Suspension::find_active(&state.pool, &user.id).await {
+            Ok(None) => Ok(Self(user)),
+            Ok(Some(suspension)) => Err(Error::from_string(
+                format!("Account is suspended: {}", suspension.reason),
+                StatusCode::FORBIDDEN,
+            )),
+            Err(err) => {
+                error!("Failed to check suspension: {}", err);
+                Err(Error::from_status(StatusCode::INTERNAL_SERVER_ERROR))
+            }
+        }
+    }
+}
+
+/// Logged-in administrator, required for everything under `/admin`. API tokens are never accepted here,
+/// whatever their scopes, so administration always needs a browser session.
+pub struct AdminUser(pub Arc<User>);
+
+impl<'a> FromRequest<'a> for AdminUser {
+    async fn from_request(req: &'a Request, body: &mut RequestBody) -> Result<Self> {
+        if req.extensions().get::<TokenAuth>().is_some() {
+            return Err(Error::from_string("API tokens cannot be used for administration.", StatusCode::FORBIDDEN));
+        }
+
+        let AuthUser(user) = AuthUser::from_request(req, body).await?;
+        if user.is_admin {
+            Ok(Self(user))
+        } else {
+            Err(Error::from_status(StatusCode::FORBIDDEN))
+        }
+    }
+}
+
+/// Scope a token needs for the given request.
+fn required_scope(method: &Method, path: &str) -> TokenScope {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        TokenScope::Read
+    } else if path.starts_with("/partials/comments") || path.ends_with("/reviews") {
+        TokenScope::WriteComments
+    } else {
+        TokenScope::Admin
+    }
+}
+
+/// Authenticates requests carrying a personal access token. Requests without one pass through untouched.
+pub struct BearerAuth;
+
+impl<E: Endpoint> Middleware<E> for BearerAuth {
+    type Output = BearerAuthEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        BearerAuthEndpoint { inner: ep }
+    }
+}
+
+pub struct BearerAuthEndpoint<E> {
+    inner: E,
+}
+
+impl<E: Endpoint> Endpoint for BearerAuthEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string());
+
+        if let Some(token) = token {
+            let state = req
+                .extensions()
+                .get::<Arc<AppState>>()
+                .cloned()
+                .ok_or_else(|| Error::from_status(StatusCode::INTERNAL_SERVER_ERROR))?;
+
+            let (user, token) = ApiToken::authenticate(&state.pool, &token).await.map_err(|err| match err {
+                // Anyone can send a made-up token, that is not worth more than a debug line.
+                PodelError::UserError(_) => {
+                    debug!("Bearer authentication failed: {}", err);
+                    Error::from_status(StatusCode::UNAUTHORIZED)
+                }
+                err => {
+                    error!("Failed to authenticate bearer token: {}", err);
+                    Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            })?;
+
+            if !token.has_scope(required_scope(req.method(), req.uri().path())) {
+                return Err(Error::from_status(StatusCode::FORBIDDEN));
+            }
+
+            req.extensions_mut().insert(TokenAuth { user, token });
+        }
+
+        self.inner.call(req).await.map(IntoResponse::into_response)
+    }
+}
+
+/// Resolves the session cookie against `auth.session`, so revoked, expired or IP-pinned sessions used
+/// from another address are logged out immediately.
+pub struct SessionAuth;
+
+impl<E: Endpoint> Middleware<E> for SessionAuth {
+    type Output = SessionAuthEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        SessionAuthEndpoint { inner: ep }
+    }
+}
+
+pub struct SessionAuthEndpoint<E> {
+    inner: E,
+}
+
+impl<E: Endpoint> Endpoint for SessionAuthEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        if let (Some(session), Some(state)) = (req.extensions().get::<Session>(), req.extensions().get::<Arc<AppState>>()) {
+            let ip = client_ip(&req, &state.trusted_proxies);
+            if let Err(err) = SessionData::authenticate(&state.pool, session, ip).await {
+                if !matches!(err, PodelError::Empty()) {
+                    error!("Failed to resolve session: {}", err);
+                }
+            }
+        }
+
+        self.inner.call(req).await
+    }
+}
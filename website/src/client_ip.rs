@@ -0,0 +1,188 @@
+use common::AppState;
+use poem::http::HeaderMap;
+use poem::{FromRequest, Request, RequestBody, Result};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+/// Address of the client that sent the request, seen through trusted reverse proxies.
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<'a> FromRequest<'a> for ClientIp {
+    async fn from_request(req: &'a Request, _body: &mut RequestBody) -> Result<Self> {
+        let trusted_proxies = req
+            .extensions()
+            .get::<Arc<AppState>>()
+            .map(|state| state.trusted_proxies.as_slice())
+            .unwrap_or_default();
+
+        Ok(Self(client_ip(req, trusted_proxies)))
+    }
+}
+
+/// Resolves the client address. Forwarding headers are only honored when the peer is a trusted proxy,
+/// and they are walked from the nearest hop so a client cannot spoof its address by sending them itself.
+pub fn client_ip(req: &Request, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let peer = req.remote_addr().as_socket_addr().map(SocketAddr::ip)?;
+    Some(resolve(peer, req.headers(), trusted_proxies))
+}
+
+fn resolve(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> IpAddr {
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+
+    let mut client = peer;
+    for hop in forwarded_for(headers).into_iter().rev() {
+        let Some(hop) = hop else {
+            // Obfuscated or malformed hop, nothing behind it can be trusted.
+            break;
+        };
+        client = hop;
+        if !trusted_proxies.contains(&hop) {
+            break;
+        }
+    }
+
+    client
+}
+
+/// Hops from the `Forwarded` header, or from `X-Forwarded-For` if there is none, in the order they were added.
+fn forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    let forwarded: Vec<Option<IpAddr>> = headers
+        .get_all("forwarded")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|element| {
+            element
+                .split(';')
+                .filter_map(|pair| pair.trim().split_once('='))
+                .find(|(key, _)| key.eq_ignore_ascii_case("for"))
+                .map(|(_, node)| parse_node(node.trim_matches('"')))
+        })
+        .collect();
+
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|node| parse_node(node.trim()))
+        .collect()
+}
+
+/// Parses `1.2.3.4`, `1.2.3.4:80`, `[2001:db8::1]:80` and bare IPv6 addresses.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    node.parse::<IpAddr>()
+        .ok()
+        .or_else(|| node.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| node.strip_prefix('[')?.strip_suffix(']')?.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROXY: &str = "10.0.0.1";
+    const INNER_PROXY: &str = "10.0.0.2";
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    fn header_map(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn trusted() -> Vec<IpAddr> {
+        vec![ip(PROXY), ip(INNER_PROXY)]
+    }
+
+    #[test]
+    fn untrusted_peer_is_the_client() {
+        let headers = header_map(&[("x-forwarded-for", "1.2.3.4"), ("forwarded", "for=1.2.3.4")]);
+        assert_eq!(resolve(ip("203.0.113.9"), &headers, &trusted()), ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn trusted_peer_without_headers_is_the_client() {
+        assert_eq!(resolve(ip(PROXY), &HeaderMap::new(), &trusted()), ip(PROXY));
+    }
+
+    #[test]
+    fn single_proxy() {
+        let headers = header_map(&[("x-forwarded-for", "203.0.113.9")]);
+        assert_eq!(resolve(ip(PROXY), &headers, &trusted()), ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn chain_of_proxies() {
+        let headers = header_map(&[("x-forwarded-for", "203.0.113.9, 10.0.0.2")]);
+        assert_eq!(resolve(ip(PROXY), &headers, &trusted()), ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn chain_split_over_several_headers() {
+        let headers = header_map(&[("x-forwarded-for", "203.0.113.9"), ("x-forwarded-for", "10.0.0.2")]);
+        assert_eq!(resolve(ip(PROXY), &headers, &trusted()), ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn spoofed_leftmost_entries_are_ignored() {
+        let headers = header_map(&[("x-forwarded-for", "127.0.0.1, 198.51.100.7, 203.0.113.9")]);
+        assert_eq!(resolve(ip(PROXY), &headers, &trusted()), ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn spoofed_trusted_address_behind_the_client_is_ignored() {
+        let headers = header_map(&[("x-forwarded-for", "10.0.0.2, 203.0.113.9")]);
+        assert_eq!(resolve(ip(PROXY), &headers, &trusted()), ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn malformed_hop_stops_the_walk() {
+        let headers = header_map(&[("x-forwarded-for", "198.51.100.7, garbage, 10.0.0.2")]);
+        assert_eq!(resolve(ip(PROXY), &headers, &trusted()), ip(INNER_PROXY));
+    }
+
+    #[test]
+    fn ipv6_hops() {
+        let headers = header_map(&[("x-forwarded-for", "2001:db8::1")]);
+        assert_eq!(resolve(ip(PROXY), &headers, &trusted()), ip("2001:db8::1"));
+
+        let headers = header_map(&[("x-forwarded-for", "[2001:db8::1]:4711")]);
+        assert_eq!(resolve(ip(PROXY), &headers, &trusted()), ip("2001:db8::1"));
+    }
+
+    #[test]
+    fn ipv6_peer_can_be_trusted() {
+        let headers = header_map(&[("x-forwarded-for", "203.0.113.9")]);
+        assert_eq!(resolve(ip("::1"), &headers, &[ip("::1")]), ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn forwarded_header_with_quoted_values() {
+        let headers = header_map(&[("forwarded", r#"for="[2001:db8:cafe::17]:4711";proto=https, for=10.0.0.2"#)]);
+        assert_eq!(resolve(ip(PROXY), &headers, &trusted()), ip("2001:db8:cafe::17"));
+    }
+
+    #[test]
+    fn forwarded_header_takes_precedence() {
+        let headers = header_map(&[("forwarded", "For=203.0.113.9;by=10.0.0.1"), ("x-forwarded-for", "198.51.100.7")]);
+        assert_eq!(resolve(ip(PROXY), &headers, &trusted()), ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn obfuscated_forwarded_node_stops_the_walk() {
+        let headers = header_map(&[("forwarded", "for=203.0.113.9, for=_hidden")]);
+        assert_eq!(resolve(ip(PROXY), &headers, &trusted()), ip(PROXY));
+    }
+}
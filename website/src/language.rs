@@ -0,0 +1,128 @@
+use poem::http::header;
+use poem::session::Session;
+use poem::{Endpoint, Middleware, Request, Result};
+
+/// Languages with a Fluent bundle in `locales`, the first one is the default.
+pub const SUPPORTED_LANGUAGES: [&str; 2] = ["en-US", "cs-CZ"];
+
+/// Picks the best supported language from an `Accept-Language` header value,
+/// matching either the full tag (`cs-CZ`) or just the language (`cs`).
+pub fn negotiate(accept_language: &str) -> Option<&'static str> {
+    let mut requested: Vec<(&str, f32)> = accept_language
+        .split(',')
+        .filter_map(|part| {
+            let mut params = part.trim().split(';');
+            let tag = params.next()?.trim();
+            let quality = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+        })
+        .collect();
+    requested.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let primary = |tag: &str| tag.split('-').next().unwrap_or(tag).to_ascii_lowercase();
+
+    requested.into_iter().find_map(|(tag, _)| {
+        SUPPORTED_LANGUAGES
+            .into_iter()
+            .find(|supported| supported.eq_ignore_ascii_case(tag))
+            .or_else(|| SUPPORTED_LANGUAGES.into_iter().find(|supported| primary(supported) == primary(tag)))
+    })
+}
+
+/// Stores the language negotiated from `Accept-Language` in the session, unless the user already has one.
+pub struct LanguageNegotiation;
+
+impl<E: Endpoint> Middleware<E> for LanguageNegotiation {
+    type Output = LanguageNegotiationEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        LanguageNegotiationEndpoint { inner: ep }
+    }
+}
+
+pub struct LanguageNegotiationEndpoint<E> {
+    inner: E,
+}
+
+impl<E: Endpoint> Endpoint for LanguageNegotiationEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        if let Some(session) = req.extensions().get::<Session>() {
+            if session.get::<String>("user_language").is_none() {
+                let negotiated = req
+                    .headers()
+                    .get(header::ACCEPT_LANGUAGE)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(negotiate);
+
+                if let Some(language) = negotiated {
+                    session.set("user_language", language);
+                }
+            }
+        }
+
+        self.inner.call(req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_tag_matches() {
+        assert_eq!(negotiate("cs-CZ"), Some("cs-CZ"));
+        assert_eq!(negotiate("en-US"), Some("en-US"));
+    }
+
+    #[test]
+    fn tags_are_case_insensitive() {
+        assert_eq!(negotiate("CS-cz"), Some("cs-CZ"));
+    }
+
+    #[test]
+    fn language_without_region_matches() {
+        assert_eq!(negotiate("cs"), Some("cs-CZ"));
+        assert_eq!(negotiate("en-GB"), Some("en-US"));
+    }
+
+    #[test]
+    fn highest_quality_wins() {
+        assert_eq!(negotiate("en;q=0.5, cs;q=0.8"), Some("cs-CZ"));
+        assert_eq!(negotiate("cs;q=0.3,en-US"), Some("en-US"));
+    }
+
+    #[test]
+    fn equal_quality_keeps_header_order() {
+        assert_eq!(negotiate("cs, en"), Some("cs-CZ"));
+        assert_eq!(negotiate("en;q=0.7, cs;q=0.7"), Some("en-US"));
+    }
+
+    #[test]
+    fn unsupported_languages_are_skipped() {
+        assert_eq!(negotiate("de-DE, fr;q=0.9, cs;q=0.1"), Some("cs-CZ"));
+    }
+
+    #[test]
+    fn zero_quality_excludes_a_language() {
+        assert_eq!(negotiate("cs;q=0, en;q=0.1"), Some("en-US"));
+        assert_eq!(negotiate("cs;q=0"), None);
+    }
+
+    #[test]
+    fn malformed_quality_counts_as_one() {
+        assert_eq!(negotiate("en;q=0.9, cs;q=abc"), Some("cs-CZ"));
+    }
+
+    #[test]
+    fn nothing_supported_falls_back_to_none() {
+        assert_eq!(negotiate(""), None);
+        assert_eq!(negotiate("*"), None);
+        assert_eq!(negotiate("de, fr;q=0.5"), None);
+        assert_eq!(negotiate(" , ;q=1"), None);
+    }
+}
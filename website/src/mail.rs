@@ -0,0 +1,36 @@
+use crate::filters::LOCALES;
+use crate::DEFAULT_LANGUAGE;
+use common::database::user::User;
+use common::mail::Email;
+use common::{AppState, PodelError};
+use fluent_templates::fluent_bundle::FluentValue;
+use fluent_templates::{LanguageIdentifier, Loader};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Mails the user a link, with subject and body looked up in their language.
+/// The body message receives `$name` and `$link` arguments.
+pub async fn send_link(
+    state: &AppState,
+    user: &User,
+    subject_key: &str,
+    body_key: &str,
+    link: String,
+) -> Result<(), PodelError> {
+    let Some(email) = &user.email else {
+        return Err(PodelError::UserError("User has no e-mail address.".into()));
+    };
+
+    let language = LanguageIdentifier::from_str(&user.language).unwrap_or_else(|_| DEFAULT_LANGUAGE.clone());
+    let args: HashMap<Cow<'static, str>, FluentValue<'static>> = HashMap::from([
+        ("name".into(), FluentValue::String(user.name.clone().into())),
+        ("link".into(), FluentValue::String(link.into())),
+    ]);
+
+    state.mailer.send(Email {
+        to: email.clone(),
+        subject: LOCALES.lookup(&language, subject_key),
+        body: LOCALES.lookup_with_args(&language, body_key, &args),
+    }).await
+}
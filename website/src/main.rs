@@ -1,4 +1,6 @@
-use crate::page::{index, partials, subject, user};
+use crate::auth::{BearerAuth, SessionAuth};
+use crate::language::LanguageNegotiation;
+use crate::page::{admin, index, organization, partials, search, subject, user};
 use common::database::{create_pool, migrate};
 use common::{AppState, PodelError};
 use poem::{
@@ -12,7 +14,13 @@ use fluent_templates::LanguageIdentifier;
 use lazy_static::lazy_static;
 use std::str::FromStr;
 use std::sync::Arc;
-use log::error;
+use log::{error, info};
+use std::time::Duration;
+use common::database::reviewable::app::App;
+use common::database::reviewable::eshop::Eshop;
+use common::database::reviewable::service::Service;
+use common::database::reviewable::{Karma, KINDS};
+use common::database::user::SessionData;
 use poem::endpoint::StaticFilesEndpoint;
 use poem::middleware::Csrf;
 use poem::session::{CookieConfig, CookieSession};
@@ -22,9 +30,20 @@ use rinja::Template;
 use tokio::signal;
 use tokio::task::AbortHandle;
 
+pub mod api;
+pub mod auth;
+pub mod client_ip;
+pub mod language;
+pub mod mail;
 pub mod page;
+pub mod rate_limit;
 pub mod filters;
 
+const KARMA_RECOMPUTE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const SESSION_CLEANUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Rate limit buckets idle this long are full again and can be forgotten.
+const RATE_LIMIT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
 pub type PoemResult = poem::Result<Html<String>, poem::error::NotFoundError>;
 
 lazy_static! {
@@ -39,15 +58,27 @@ async fn main() -> Result<(), PodelError> {
 
     migrate(&pool).await.unwrap();
 
-    let state =  Arc::new(AppState::new(pool));
+    let state =  Arc::new(AppState::new(pool)?);
+    let karma_task = tokio::spawn(recompute_karma(state.clone()));
+    let session_task = tokio::spawn(delete_expired_sessions(state.clone()));
+    let rate_limit_task = tokio::spawn(prune_rate_limits(state.clone()));
 
     let app = Route::new()
         .nest("/assets/", StaticFilesEndpoint::new("./assets"))
+        .nest("/api", api::route())
         .nest("/partials", partials::route())
         .nest("/user", user::route_user())
         .nest("/auth", user::route_auth())
         .nest("/web", subject::route_website())
+        .nest("/app", subject::route_subject::<App>())
+        .nest("/eshop", subject::route_subject::<Eshop>())
+        .nest("/service", subject::route_subject::<Service>())
+        .nest("/org", organization::route_organization())
+        .nest("/search", search::route_search())
+        .nest("/admin", admin::route_admin())
         .at("/", get(index::get))
+        .with(LanguageNegotiation)
+        .with(SessionAuth)
         .with(CookieSession::new(
             CookieConfig::new()
                 .name("cookie")
@@ -55,9 +86,20 @@ async fn main() -> Result<(), PodelError> {
                 .secure(false)
         ))
         .with(Csrf::new())
+        .with(BearerAuth)
         .with(AddData::new(state));
 
-    Server::new(TcpListener::bind("127.0.0.1:3000")).run(app).await?;
+    Server::new(TcpListener::bind("127.0.0.1:3000"))
+        .run_with_graceful_shutdown(
+            app,
+            shutdown_signal(vec![
+                karma_task.abort_handle(),
+                session_task.abort_handle(),
+                rate_limit_task.abort_handle(),
+            ]),
+            None,
+        )
+        .await?;
 
     Ok(())
 }
@@ -123,7 +165,47 @@ macro_rules! extend_with_app_state {
     };
 }
 
-async fn shutdown_signal(deletion_task_abort_handle: AbortHandle) {
+/// Periodically recomputes karma of all subjects.
+async fn recompute_karma(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(KARMA_RECOMPUTE_INTERVAL);
+    loop {
+        interval.tick().await;
+        for kind in KINDS {
+            match Karma::recompute_all(&state.pool, kind, &state.karma_weights).await {
+                Ok(count) => info!("Recomputed karma of {count} subjects of kind {kind}"),
+                Err(err) => error!("Failed to recompute karma of kind {kind}: {}", err),
+            }
+        }
+    }
+}
+
+/// Periodically deletes sessions that expired without logging out.
+async fn delete_expired_sessions(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(SESSION_CLEANUP_INTERVAL);
+    loop {
+        interval.tick().await;
+        match SessionData::delete_expired(&state.pool).await {
+            Ok(0) => {}
+            Ok(count) => info!("Deleted {count} expired sessions"),
+            Err(err) => error!("Failed to delete expired sessions: {}", err),
+        }
+    }
+}
+
+/// Periodically forgets idle rate limit buckets.
+async fn prune_rate_limits(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(SESSION_CLEANUP_INTERVAL);
+    loop {
+        interval.tick().await;
+        match state.rate_limiter.prune(RATE_LIMIT_MAX_AGE).await {
+            Ok(0) => {}
+            Ok(count) => info!("Pruned {count} rate limit buckets"),
+            Err(err) => error!("Failed to prune rate limit buckets: {}", err),
+        }
+    }
+}
+
+async fn shutdown_signal(task_abort_handles: Vec<AbortHandle>) {
     let ctrl_c = async {
         signal::ctrl_c()
             .await
@@ -142,8 +224,12 @@ async fn shutdown_signal(deletion_task_abort_handle: AbortHandle) {
     let terminate = std::future::pending::<()>();
 
     tokio::select! {
-        _ = ctrl_c => { deletion_task_abort_handle.abort() },
-        _ = terminate => { deletion_task_abort_handle.abort() },
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    for handle in task_abort_handles {
+        handle.abort();
     }
 }
 
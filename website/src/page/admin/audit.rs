@@ -0,0 +1,60 @@
+use crate::extend_with_app_state;
+use common::database::audit::{AuditEvent, AuditFilter};
+use poem::{get, handler, Route};
+use serde::Deserialize;
+use super::PAGE_SIZE;
+
+extend_with_app_state! {
+    #[template(path = "admin/audit.html")]
+    struct AdminAuditTemplate {
+        events: Vec<AuditEvent>,
+        filter: AuditFilter,
+        offset: i64,
+    };
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub offset: Option<i64>,
+}
+
+pub fn route() -> Route {
+    Route::new()
+        .at("/", get(get::events))
+}
+
+mod get {
+    use log::error;
+    use poem::web::Query;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::PoemResult;
+
+    /// Audit log, newest events first.
+    #[handler]
+    pub(crate) async fn events(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Query(query): Query<AuditQuery>,
+    ) -> PoemResult {
+        let offset = query.offset.unwrap_or(0).max(0);
+        let filter = AuditFilter {
+            actor: query.actor,
+            action: query.action,
+            target_type: query.target_type,
+            target_id: query.target_id,
+        };
+        let events = AuditEvent::find_multiple(&state.pool, &filter, PAGE_SIZE, offset).await.unwrap_or_else(|err| {
+            error!("Failed to fetch audit log: {}", err);
+            Vec::new()
+        });
+
+        let template = AdminAuditTemplate::from_app_state(state, session, events, filter, offset);
+        crate::render(&template)
+    }
+}
@@ -0,0 +1,127 @@
+use crate::extend_with_app_state;
+use common::database::audit::Actor;
+use common::database::claim::OrganizationClaim;
+use poem::{get, handler, post, web::Path, Route};
+use serde::Deserialize;
+use super::{AdminQuery, PAGE_SIZE};
+
+extend_with_app_state! {
+    #[template(path = "admin/claims.html")]
+    struct AdminClaimsTemplate {
+        claims: Vec<OrganizationClaim>,
+        offset: i64,
+    };
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DecisionForm {
+    /// Shown to the claimant.
+    pub note: Option<String>,
+}
+
+pub fn route() -> Route {
+    Route::new()
+        .at("/", get(get::claims))
+        .at("/:id/approve", post(post::approve))
+        .at("/:id/reject", post(post::reject))
+}
+
+mod get {
+    use log::error;
+    use poem::web::Query;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::PoemResult;
+
+    /// Pending organization claims, oldest first.
+    #[handler]
+    pub(crate) async fn claims(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Query(query): Query<AdminQuery>,
+    ) -> PoemResult {
+        let offset = query.offset.unwrap_or(0).max(0);
+        let claims = OrganizationClaim::find_pending(&state.pool, PAGE_SIZE, offset).await.unwrap_or_else(|err| {
+            error!("Failed to fetch organization claims: {}", err);
+            Vec::new()
+        });
+
+        let template = AdminClaimsTemplate::from_app_state(state, session, claims, offset);
+        crate::render(&template)
+    }
+}
+
+mod post {
+    use log::error;
+    use poem::http::StatusCode;
+    use poem::web::Form;
+    use common::PodelError;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::client_ip::ClientIp;
+    use crate::mail::send_link;
+
+    /// Tells the claimant about the decision. Failures are only logged, the decision stands.
+    async fn notify(state: &AppState, claim: &OrganizationClaim, subject_key: &str, body_key: &str) {
+        let link = format!("{}/org/{}", state.public_url, claim.organization_id);
+        if let Err(err) = send_link(state, &claim.user, subject_key, body_key, link).await {
+            error!("Failed to notify claimant: {}", err);
+        }
+    }
+
+    fn note(form: DecisionForm) -> Option<String> {
+        form.note.map(|note| note.trim().to_string()).filter(|note| !note.is_empty())
+    }
+
+    /// Makes the claimant the owner of the organization.
+    #[handler]
+    pub(crate) async fn approve(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+        Form(form): Form<DecisionForm>,
+    ) -> StatusCode {
+        let Ok(claim) = OrganizationClaim::find(&state.pool, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        match claim.approve(&state.pool, &Actor::new(&admin, ip), note(form)).await {
+            Ok(()) => {
+                notify(&state, &claim, "claim-approved-subject", "claim-approved-body").await;
+                StatusCode::OK
+            }
+            Err(PodelError::UserError(_) | PodelError::Empty()) => StatusCode::CONFLICT,
+            Err(err) => {
+                error!("Failed to approve organization claim: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    #[handler]
+    pub(crate) async fn reject(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+        Form(form): Form<DecisionForm>,
+    ) -> StatusCode {
+        let Ok(claim) = OrganizationClaim::find(&state.pool, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        match claim.reject(&state.pool, &Actor::new(&admin, ip), note(form)).await {
+            Ok(()) => {
+                notify(&state, &claim, "claim-rejected-subject", "claim-rejected-body").await;
+                StatusCode::OK
+            }
+            Err(PodelError::Empty()) => StatusCode::CONFLICT,
+            Err(err) => {
+                error!("Failed to reject organization claim: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
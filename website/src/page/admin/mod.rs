@@ -0,0 +1,216 @@
+use crate::extend_with_app_state;
+use common::database::audit::Actor;
+use common::database::comment::Comment;
+use common::database::reviewable::app::App;
+use common::database::reviewable::eshop::Eshop;
+use common::database::reviewable::service::Service;
+use common::database::user::SessionData;
+use poem::{get, handler, post, web::Path, Route};
+use serde::Deserialize;
+
+pub mod audit;
+pub mod claims;
+pub mod reports;
+pub mod subjects;
+pub mod users;
+
+/// Rows shown on one page of an admin list.
+const PAGE_SIZE: i64 = 50;
+/// Subject kinds whose comments can be moderated.
+const COMMENT_KINDS: [&str; 4] = ["website", "app", "eshop", "service"];
+
+extend_with_app_state! {
+    #[template(path = "admin/index.html")]
+    struct AdminIndexTemplate {};
+
+    #[template(path = "admin/comments.html")]
+    struct AdminCommentsTemplate {
+        comments: Vec<Comment>,
+        kind: String,
+        offset: i64,
+    };
+
+    #[template(path = "admin/sessions.html")]
+    struct AdminSessionsTemplate {
+        sessions: Vec<SessionData>,
+        offset: i64,
+    };
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdminQuery {
+    /// Filters the list, e.g. by user name.
+    pub q: Option<String>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommentQueueQuery {
+    /// Subject kind, `website` if not given.
+    pub kind: Option<String>,
+    pub offset: Option<i64>,
+}
+
+pub fn route_admin() -> Route {
+    Route::new()
+        .at("/", get(get::index))
+        .nest("/users", users::route())
+        .nest("/websites", subjects::route_website())
+        .nest("/apps", subjects::route_subject::<App>())
+        .nest("/eshops", subjects::route_subject::<Eshop>())
+        .nest("/services", subjects::route_subject::<Service>())
+        .nest("/organizations", subjects::route_organization())
+        .nest("/reports", reports::route())
+        .nest("/claims", claims::route())
+        .nest("/audit", audit::route())
+        .at("/comments", get(get::comments))
+        .at("/comments/:kind/:id/hide", post(post::hide_comment))
+        .at("/comments/:kind/:id/restore", post(post::restore_comment))
+        .at("/sessions", get(get::sessions))
+        .at("/sessions/:user_id/:id", poem::delete(delete::session))
+}
+
+mod get {
+    use log::error;
+    use poem::web::Query;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::PoemResult;
+
+    #[handler]
+    pub(crate) async fn index(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+    ) -> PoemResult {
+        let template = AdminIndexTemplate::from_app_state(state, session);
+        crate::render(&template)
+    }
+
+    /// Newest comments of one subject kind, the moderation queue.
+    #[handler]
+    pub(crate) async fn comments(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Query(query): Query<CommentQueueQuery>,
+    ) -> PoemResult {
+        let kind = query.kind.unwrap_or_else(|| COMMENT_KINDS[0].to_string());
+        if !COMMENT_KINDS.contains(&kind.as_str()) {
+            return Err(poem::error::NotFoundError);
+        }
+
+        let offset = query.offset.unwrap_or(0).max(0);
+        let comments = Comment::find_recent(&state.pool, &kind, PAGE_SIZE, offset).await.unwrap_or_else(|err| {
+            error!("Failed to fetch comments: {}", err);
+            Vec::new()
+        });
+
+        let template = AdminCommentsTemplate::from_app_state(state, session, comments, kind, offset);
+        crate::render(&template)
+    }
+
+    /// Live sessions of all users, most recently used first.
+    #[handler]
+    pub(crate) async fn sessions(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Query(query): Query<AdminQuery>,
+    ) -> PoemResult {
+        let offset = query.offset.unwrap_or(0).max(0);
+        let sessions = SessionData::find_recent(&state.pool, PAGE_SIZE, offset).await.unwrap_or_else(|err| {
+            error!("Failed to fetch sessions: {}", err);
+            Vec::new()
+        });
+
+        let template = AdminSessionsTemplate::from_app_state(state, session, sessions, offset);
+        crate::render(&template)
+    }
+}
+
+mod post {
+    use log::error;
+    use poem::http::StatusCode;
+    use common::PodelError;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::client_ip::ClientIp;
+
+    /// Replaces a comment with a moderator placeholder, keeping its content for [`restore_comment`].
+    #[handler]
+    pub(crate) async fn hide_comment(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path((kind, id)): Path<(String, String)>,
+    ) -> StatusCode {
+        if !COMMENT_KINDS.contains(&kind.as_str()) {
+            return StatusCode::NOT_FOUND;
+        }
+        let Ok(comment) = Comment::find(&state.pool, &kind, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        match comment.hide(&state.pool, &Actor::new(&admin, ip)).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to hide comment: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Shows a hidden comment again.
+    #[handler]
+    pub(crate) async fn restore_comment(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path((kind, id)): Path<(String, String)>,
+    ) -> StatusCode {
+        if !COMMENT_KINDS.contains(&kind.as_str()) {
+            return StatusCode::NOT_FOUND;
+        }
+        let Ok(comment) = Comment::find(&state.pool, &kind, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        match comment.restore(&state.pool, &Actor::new(&admin, ip)).await {
+            Ok(()) => StatusCode::OK,
+            // deleted comments have no content to bring back
+            Err(PodelError::Empty()) => StatusCode::CONFLICT,
+            Err(err) => {
+                error!("Failed to restore comment: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+mod delete {
+    use log::error;
+    use poem::http::StatusCode;
+    use common::PodelError;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::client_ip::ClientIp;
+
+    /// Logs a user out of one of their devices.
+    #[handler]
+    pub(crate) async fn session(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path((user_id, id)): Path<(String, String)>,
+    ) -> StatusCode {
+        match SessionData::revoke_as(&state.pool, &Actor::new(&admin, ip), &user_id, id).await {
+            Ok(()) => StatusCode::OK,
+            Err(PodelError::Empty()) => StatusCode::NOT_FOUND,
+            Err(err) => {
+                error!("Failed to revoke session: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use crate::extend_with_app_state;
+use common::database::audit::Actor;
+use common::database::comment::Comment;
+use common::database::report::{Report, ReportTarget};
+use common::database::review::Review;
+use common::database::reviewable::app::App;
+use common::database::reviewable::eshop::Eshop;
+use common::database::reviewable::service::Service;
+use common::database::reviewable::website::Website;
+use common::database::reviewable::{self, Organization, Reviewable};
+use common::database::Ulid;
+use common::database::user::User;
+use common::PodelError;
+use poem::{get, handler, post, web::Path, Route};
+use serde::Deserialize;
+use super::{AdminQuery, PAGE_SIZE};
+
+/// Characters of reported content shown in the queue.
+const PREVIEW_LENGTH: usize = 200;
+
+extend_with_app_state! {
+    #[template(path = "admin/reports.html")]
+    struct AdminReportsTemplate {
+        /// open reports with a preview of the reported content, `None` if it no longer exists
+        reports: Vec<(Report, Option<String>)>,
+        offset: i64,
+    };
+}
+
+/// What a moderator does about a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationAction {
+    /// Replaces a comment with a placeholder or takes a review out of listings.
+    Hide,
+    /// Deletes the content for good.
+    Delete,
+    /// Mails the author a warning.
+    Warn,
+    /// Bans the author and hides the content.
+    Ban,
+    /// Closes the report without acting on it.
+    Dismiss,
+}
+
+impl ModerationAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationAction::Hide => "hide",
+            ModerationAction::Delete => "delete",
+            ModerationAction::Warn => "warn",
+            ModerationAction::Ban => "ban",
+            ModerationAction::Dismiss => "dismiss",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveForm {
+    pub action: ModerationAction,
+}
+
+pub fn route() -> Route {
+    Route::new()
+        .at("/", get(get::reports))
+        .at("/:id/resolve", post(post::resolve))
+}
+
+/// Content a report points to.
+enum Reported {
+    Comment(Comment),
+    Review(Review),
+    /// reviewable subject with its name and details as the preview
+    Subject { kind: &'static str, id: Ulid, preview: String },
+    Organization(Organization),
+}
+
+impl Reported {
+    async fn find(state: &AppState, report: &Report) -> Result<Self, PodelError> {
+        match report.target {
+            ReportTarget::Comment => Comment::find(&state.pool, &report.subject_type, &report.target_id)
+                .await
+                .map(Self::Comment),
+            ReportTarget::Review => Review::find(&state.pool, &report.subject_type, &report.target_id)
+                .await
+                .map(Self::Review),
+            ReportTarget::Subject => match report.subject_type.as_str() {
+                "organization" => Organization::find_by_id(&state.pool, &report.target_id).await.map(Self::Organization),
+                App::KIND => App::find(&state.pool, &report.target_id).await.map(Self::subject),
+                Eshop::KIND => Eshop::find(&state.pool, &report.target_id).await.map(Self::subject),
+                Service::KIND => Service::find(&state.pool, &report.target_id).await.map(Self::subject),
+                _ => Website::find(&state.pool, &report.target_id).await.map(Self::subject),
+            },
+        }
+    }
+
+    fn subject<T: Reviewable>(subject: T) -> Self {
+        let details = subject.details().into_iter().map(|(_, value)| value).collect::<Vec<_>>();
+        Self::Subject {
+            kind: T::KIND,
+            id: subject.id().clone(),
+            preview: format!("{} ({})", subject.display_name(), details.join(", ")),
+        }
+    }
+
+    /// User who wrote the content. Subjects have no author.
+    fn author(&self) -> Option<&User> {
+        match self {
+            Reported::Comment(comment) => Some(&comment.user),
+            Reported::Review(review) => Some(&review.user),
+            Reported::Subject { .. } | Reported::Organization(_) => None,
+        }
+    }
+
+    fn preview(&self) -> String {
+        let text = match self {
+            Reported::Comment(comment) => comment.content.clone(),
+            Reported::Review(review) => format!("{}: {}", review.title, review.content),
+            Reported::Subject { preview, .. } => preview.clone(),
+            Reported::Organization(organization) => organization.name.clone(),
+        };
+
+        text.chars().take(PREVIEW_LENGTH).collect()
+    }
+}
+
+mod get {
+    use log::error;
+    use poem::web::Query;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::PoemResult;
+
+    /// Open reports, oldest first.
+    #[handler]
+    pub(crate) async fn reports(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Query(query): Query<AdminQuery>,
+    ) -> PoemResult {
+        let offset = query.offset.unwrap_or(0).max(0);
+        let reports = Report::find_open(&state.pool, PAGE_SIZE, offset).await.unwrap_or_else(|err| {
+            error!("Failed to fetch reports: {}", err);
+            Vec::new()
+        });
+
+        let mut previews = Vec::with_capacity(reports.len());
+        for report in reports {
+            let preview = Reported::find(&state, &report).await.ok().map(|reported| reported.preview());
+            previews.push((report, preview));
+        }
+
+        let template = AdminReportsTemplate::from_app_state(state, session, previews, offset);
+        crate::render(&template)
+    }
+}
+
+mod post {
+    use log::error;
+    use poem::http::StatusCode;
+    use poem::web::Form;
+    use common::database::report::ReportStatus;
+    use common::database::reviewable::Karma;
+    use common::database::suspension::Suspension;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::client_ip::ClientIp;
+    use crate::mail::send_link;
+
+    /// Hides the content. Subjects can only be deleted.
+    async fn hide(state: &AppState, actor: &Actor<'_>, reported: Reported) -> Result<(), StatusCode> {
+        let result = match reported {
+            Reported::Comment(comment) => comment.hide(&state.pool, actor).await,
+            Reported::Review(mut review) => {
+                let result = review.set_hidden(&state.pool, actor, true).await;
+                recompute_karma(state, &review.parent_type, &review.parent_id).await;
+                result
+            }
+            Reported::Subject { .. } | Reported::Organization(_) => return Err(StatusCode::BAD_REQUEST),
+        };
+
+        result.map_err(|err| {
+            error!("Failed to hide reported content: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+    }
+
+    async fn delete(state: &AppState, actor: &Actor<'_>, reported: Reported) -> Result<(), StatusCode> {
+        let result = match reported {
+            Reported::Comment(comment) => comment.delete(&state.pool, actor).await,
+            Reported::Review(review) => {
+                let result = review.delete(&state.pool, actor).await;
+                recompute_karma(state, &review.parent_type, &review.parent_id).await;
+                result
+            }
+            Reported::Subject { kind, id, .. } => reviewable::delete(&state.pool, actor, kind, id).await,
+            Reported::Organization(organization) => Organization::delete(&state.pool, actor, &organization.id).await,
+        };
+
+        result.map_err(|err| {
+            error!("Failed to delete reported content: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+    }
+
+    async fn recompute_karma(state: &AppState, kind: &str, id: &str) {
+        if let Err(err) = Karma::recompute(&state.pool, kind, id, &state.karma_weights).await {
+            error!("Failed to recompute karma: {}", err);
+        }
+    }
+
+    /// Page of the subject the reported content belongs to.
+    fn subject_url(state: &AppState, report: &Report) -> String {
+        let path = match report.subject_type.as_str() {
+            "organization" => "org",
+            "website" => "web",
+            kind => kind,
+        };
+        format!("{}/{path}/{}", state.public_url, report.subject_id)
+    }
+
+    async fn act(state: &AppState, actor: &Actor<'_>, report: &Report, action: ModerationAction) -> Result<(), StatusCode> {
+        if action == ModerationAction::Dismiss {
+            return Ok(());
+        }
+
+        let reported = Reported::find(state, report).await.map_err(|_| StatusCode::GONE)?;
+        let author = reported.author().cloned();
+
+        match (action, author) {
+            (ModerationAction::Hide, _) => hide(state, actor, reported).await,
+            (ModerationAction::Delete, _) => delete(state, actor, reported).await,
+            (ModerationAction::Warn, Some(author)) => {
+                let link = subject_url(state, report);
+                send_link(state, &author, "moderation-warning-subject", "moderation-warning-body", link)
+                    .await
+                    .map_err(|err| {
+                        error!("Failed to send moderation warning: {}", err);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })
+            }
+            (ModerationAction::Ban, Some(author)) => {
+                let reason = format!("Reported content: {}", report.reason.code());
+                if let Err(err) = Suspension::new(&author.id, reason, Some(&actor.user.id), None).save(&state.pool, actor).await {
+                    error!("Failed to ban author of reported content: {}", err);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+                hide(state, actor, reported).await
+            }
+            _ => Err(StatusCode::BAD_REQUEST),
+        }
+    }
+
+    /// Acts on a report and closes it together with all other open reports of the same content.
+    #[handler]
+    pub(crate) async fn resolve(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+        Form(form): Form<ResolveForm>,
+    ) -> StatusCode {
+        let Ok(report) = Report::find(&state.pool, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+        if report.status != ReportStatus::Open {
+            return StatusCode::CONFLICT;
+        }
+
+        let actor = Actor::new(&admin, ip);
+        if let Err(status) = act(&state, &actor, &report, form.action).await {
+            return status;
+        }
+
+        let status = if form.action == ModerationAction::Dismiss {
+            ReportStatus::Dismissed
+        } else {
+            ReportStatus::Actioned
+        };
+
+        match report.resolve(&state.pool, &actor, status, form.action.as_str()).await {
+            Ok(_) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to resolve report: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
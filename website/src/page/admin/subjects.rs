@@ -0,0 +1,418 @@
+use crate::extend_with_app_state;
+use common::database::reviewable::identifier::Ico;
+use common::database::reviewable::website::Website;
+use common::database::reviewable::{FormField, LegalForm, Organization, Reviewable, SubjectForm};
+use common::registry::{RegistryRecord, REGISTRY_COUNTRY};
+use poem::{get, handler, post, web::Path, Route};
+use log::error;
+use serde::Deserialize;
+use std::collections::HashMap;
+use super::{AdminQuery, PAGE_SIZE};
+use crate::page::organization::{all_legal_forms, non_empty};
+use crate::page::subject::SubjectCard;
+
+extend_with_app_state! {
+    #[template(path = "admin/websites.html")]
+    struct AdminWebsitesTemplate {
+        websites: Vec<Website>,
+        organizations: Vec<Organization>,
+        offset: i64,
+    };
+
+    #[template(path = "admin/website.html")]
+    struct AdminWebsiteTemplate {
+        website: Website,
+        organizations: Vec<Organization>,
+    };
+
+    #[template(path = "admin/subjects.html")]
+    struct AdminSubjectsTemplate {
+        kind: &'static str,
+        subjects: Vec<SubjectCard>,
+        /// inputs of the form creating a subject
+        fields: Vec<AdminField>,
+        organizations: Vec<Organization>,
+        offset: i64,
+    };
+
+    #[template(path = "admin/subject.html")]
+    struct AdminSubjectTemplate {
+        subject: SubjectCard,
+        fields: Vec<AdminField>,
+        organizations: Vec<Organization>,
+    };
+
+    #[template(path = "admin/organizations.html")]
+    struct AdminOrganizationsTemplate {
+        organizations: Vec<Organization>,
+        legal_forms: Vec<LegalForm>,
+        offset: i64,
+    };
+
+    #[template(path = "admin/organization.html")]
+    struct AdminOrganizationTemplate {
+        organization: Organization,
+        /// websites, apps, e-shops and services of the organization
+        subjects: Vec<SubjectCard>,
+        legal_forms: Vec<LegalForm>,
+    };
+
+    #[template(path = "admin/organization/lookup.html")]
+    struct AdminOrganizationLookupTemplate {
+        /// `None` if nothing is registered under the IČO
+        record: Option<RegistryRecord>,
+        /// form of the registered organization, `None` if the site doesn't know it and the admin has to pick one
+        legal_form: Option<LegalForm>,
+        legal_forms: Vec<LegalForm>,
+    };
+}
+
+/// Kind-specific input of an admin form with its current value.
+pub struct AdminField {
+    pub field: FormField,
+    pub value: String,
+}
+
+impl AdminField {
+    /// Inputs of the kind, filled in with the values of `subject` or left empty.
+    fn of<T: Reviewable>(subject: Option<&T>) -> Vec<Self> {
+        T::FORM_FIELDS
+            .iter()
+            .map(|field| Self {
+                field: *field,
+                value: subject.map(|subject| subject.form_value(field.name)).unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LookupQuery {
+    pub ico: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeForm {
+    /// Id of the subject that absorbs the merged one.
+    pub into: String,
+}
+
+pub fn route_website() -> Route {
+    Route::new()
+        .at("/", get(get::websites).post(post::create_subject::<Website>::default()))
+        .at("/:id", get(get::website).post(post::update_subject::<Website>::default()))
+        .at("/:id/merge", post(post::merge_website))
+}
+
+/// Admin pages of a kind without pages of its own, with forms built from its [`Reviewable::FORM_FIELDS`].
+pub fn route_subject<T: Reviewable + 'static>() -> Route {
+    Route::new()
+        .at("/", get(get::subjects::<T>::default()).post(post::create_subject::<T>::default()))
+        .at("/:id", get(get::subject::<T>::default()).post(post::update_subject::<T>::default()))
+}
+
+pub fn route_organization() -> Route {
+    Route::new()
+        .at("/", get(get::organizations))
+        .at("/lookup", get(get::lookup_organization))
+        .at("/:id", get(get::organization))
+        .at("/:id/merge", post(post::merge_organization))
+}
+
+/// Organizations to pick from in subject forms.
+async fn all_organizations(state: &AppState) -> Vec<Organization> {
+    Organization::find_all(&state.pool).await.unwrap_or_else(|err| {
+        error!("Failed to fetch organizations: {}", err);
+        Vec::new()
+    })
+}
+
+mod get {
+    use poem::web::Query;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::PoemResult;
+
+    #[handler]
+    pub(crate) async fn websites(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Query(query): Query<AdminQuery>,
+    ) -> PoemResult {
+        let offset = query.offset.unwrap_or(0).max(0);
+        let websites = Website::find_multiple(&state.pool, PAGE_SIZE, offset).await.unwrap_or_else(|err| {
+            error!("Failed to fetch websites: {}", err);
+            Vec::new()
+        });
+        let organizations = all_organizations(&state).await;
+
+        let template = AdminWebsitesTemplate::from_app_state(state, session, websites, organizations, offset);
+        crate::render(&template)
+    }
+
+    #[handler]
+    pub(crate) async fn website(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Path(id): Path<String>,
+    ) -> PoemResult {
+        let website = Website::find(&state.pool, id).await.map_err(|_| poem::error::NotFoundError)?;
+        let organizations = all_organizations(&state).await;
+
+        let template = AdminWebsiteTemplate::from_app_state(state, session, website, organizations);
+        crate::render(&template)
+    }
+
+    #[handler]
+    pub(crate) async fn subjects<T: Reviewable>(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Query(query): Query<AdminQuery>,
+    ) -> PoemResult {
+        let offset = query.offset.unwrap_or(0).max(0);
+        let subjects = T::find_multiple(&state.pool, PAGE_SIZE, offset).await.unwrap_or_else(|err| {
+            error!("Failed to fetch {} subjects: {}", T::KIND, err);
+            Vec::new()
+        });
+        let subjects = subjects.iter().map(SubjectCard::new).collect();
+        let organizations = all_organizations(&state).await;
+
+        let template = AdminSubjectsTemplate::from_app_state(
+            state,
+            session,
+            T::KIND,
+            subjects,
+            AdminField::of::<T>(None),
+            organizations,
+            offset,
+        );
+        crate::render(&template)
+    }
+
+    #[handler]
+    pub(crate) async fn subject<T: Reviewable>(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Path(id): Path<String>,
+    ) -> PoemResult {
+        let subject = T::find(&state.pool, id).await.map_err(|_| poem::error::NotFoundError)?;
+        let fields = AdminField::of(Some(&subject));
+        let organizations = all_organizations(&state).await;
+
+        let template = AdminSubjectTemplate::from_app_state(state, session, SubjectCard::new(&subject), fields, organizations);
+        crate::render(&template)
+    }
+
+    #[handler]
+    pub(crate) async fn organizations(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Query(query): Query<AdminQuery>,
+    ) -> PoemResult {
+        let offset = query.offset.unwrap_or(0).max(0);
+        let organizations = Organization::find_multiple(&state.pool, PAGE_SIZE, offset).await.unwrap_or_else(|err| {
+            error!("Failed to fetch organizations: {}", err);
+            Vec::new()
+        });
+
+        let legal_forms = all_legal_forms(&state).await;
+
+        let template = AdminOrganizationsTemplate::from_app_state(state, session, organizations, legal_forms, offset);
+        crate::render(&template)
+    }
+
+    #[handler]
+    pub(crate) async fn organization(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Path(id): Path<String>,
+    ) -> PoemResult {
+        let organization = Organization::find_by_id(&state.pool, id).await.map_err(|_| poem::error::NotFoundError)?;
+        let subjects = SubjectCard::find_by_organization(&state, &organization.id).await.unwrap_or_else(|err| {
+            error!("Failed to fetch subjects of organization: {}", err);
+            Vec::new()
+        });
+
+        let legal_forms = all_legal_forms(&state).await;
+
+        let template = AdminOrganizationTemplate::from_app_state(state, session, organization, subjects, legal_forms);
+        crate::render(&template)
+    }
+
+    /// Fields of the new organization form filled in from the business registry, swapped in by htmx.
+    #[handler]
+    pub(crate) async fn lookup_organization(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Query(query): Query<LookupQuery>,
+    ) -> PoemResult {
+        let record = match query.ico.parse::<Ico>() {
+            Ok(ico) => state.registry.lookup(&ico).await.unwrap_or_else(|err| {
+                error!("Failed to look up organization in the registry: {}", err);
+                None
+            }),
+            Err(_) => None,
+        };
+        let legal_forms = all_legal_forms(&state).await;
+        let legal_form = record.as_ref().and_then(|record| {
+            legal_forms
+                .iter()
+                .find(|form| form.country == REGISTRY_COUNTRY && record.form.as_ref() == Some(&form.code))
+                .cloned()
+        });
+
+        let template = AdminOrganizationLookupTemplate::from_app_state(state, session, record, legal_form, legal_forms);
+        crate::render(&template)
+    }
+}
+
+mod post {
+    use poem::http::StatusCode;
+    use poem::web::Form;
+    use common::database::reviewable::Karma;
+    use common::database::audit::Actor;
+    use common::PodelError;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::client_ip::ClientIp;
+
+    /// Organization picked in the form, `Err` if it does not exist.
+    async fn find_organization(state: &AppState, id: Option<&str>) -> Result<Option<Organization>, StatusCode> {
+        match id {
+            Some(id) => Organization::find_by_id(&state.pool, id).await.map(Some).map_err(|_| StatusCode::BAD_REQUEST),
+            None => Ok(None),
+        }
+    }
+
+    /// Checks the fields shared by all kinds and collects the kind-specific ones.
+    async fn subject_form<T: Reviewable>(state: &AppState, mut values: HashMap<String, String>) -> Result<SubjectForm, StatusCode> {
+        let name = non_empty(values.remove("name")).ok_or(StatusCode::BAD_REQUEST)?;
+        let description = non_empty(values.remove("description"));
+        let organization = find_organization(state, non_empty(values.remove("organization_id")).as_deref()).await?;
+        let fields = T::FORM_FIELDS
+            .iter()
+            .filter_map(|field| Some((field.name.to_string(), non_empty(values.remove(field.name))?)))
+            .collect();
+
+        Ok(SubjectForm { name, description, organization, fields })
+    }
+
+    #[handler]
+    pub(crate) async fn create_subject<T: Reviewable>(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Form(values): Form<HashMap<String, String>>,
+    ) -> StatusCode {
+        let form = match subject_form::<T>(&state, values).await {
+            Ok(form) => form,
+            Err(status) => return status,
+        };
+        let Ok(subject) = T::from_form(None, form) else {
+            return StatusCode::BAD_REQUEST;
+        };
+
+        match subject.save(&state.pool, Some(&Actor::new(&admin, ip))).await {
+            Ok(()) => StatusCode::CREATED,
+            Err(err) => {
+                error!("Failed to create {}: {}", T::KIND, err);
+                StatusCode::CONFLICT
+            }
+        }
+    }
+
+    #[handler]
+    pub(crate) async fn update_subject<T: Reviewable>(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+        Form(values): Form<HashMap<String, String>>,
+    ) -> StatusCode {
+        let Ok(subject) = T::find(&state.pool, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+        let form = match subject_form::<T>(&state, values).await {
+            Ok(form) => form,
+            Err(status) => return status,
+        };
+        let Ok(subject) = T::from_form(Some(subject), form) else {
+            return StatusCode::BAD_REQUEST;
+        };
+
+        match subject.save(&state.pool, Some(&Actor::new(&admin, ip))).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to update {}: {}", T::KIND, err);
+                StatusCode::CONFLICT
+            }
+        }
+    }
+
+    /// Merges a duplicate website into another one and recomputes the karma of the result.
+    #[handler]
+    pub(crate) async fn merge_website(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+        Form(form): Form<MergeForm>,
+    ) -> StatusCode {
+        let (Ok(source), Ok(target)) = (
+            Website::find(&state.pool, id).await,
+            Website::find(&state.pool, form.into.trim()).await,
+        ) else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        match Website::merge_into(&state.pool, &Actor::new(&admin, ip), &source.id, &target.id).await {
+            Ok(()) => {}
+            Err(PodelError::UserError(_)) => return StatusCode::BAD_REQUEST,
+            Err(PodelError::Empty()) => return StatusCode::NOT_FOUND,
+            Err(err) => {
+                error!("Failed to merge websites: {}", err);
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+        }
+
+        if let Err(err) = Karma::recompute(&state.pool, "website", &target.id, &state.karma_weights).await {
+            error!("Failed to recompute karma: {}", err);
+        }
+
+        StatusCode::OK
+    }
+
+    /// Merges a duplicate organization into another one, moving its websites.
+    #[handler]
+    pub(crate) async fn merge_organization(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+        Form(form): Form<MergeForm>,
+    ) -> StatusCode {
+        let (Ok(source), Ok(target)) = (
+            Organization::find_by_id(&state.pool, id).await,
+            Organization::find_by_id(&state.pool, form.into.trim()).await,
+        ) else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        match Organization::merge_into(&state.pool, &Actor::new(&admin, ip), &source.id, &target.id).await {
+            Ok(()) => StatusCode::OK,
+            Err(PodelError::UserError(_)) => StatusCode::BAD_REQUEST,
+            Err(PodelError::Empty()) => StatusCode::NOT_FOUND,
+            Err(err) => {
+                error!("Failed to merge organizations: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use crate::extend_with_app_state;
+use common::database::suspension::Suspension;
+use common::database::user::{SessionData, User};
+use poem::{get, handler, post, web::Path, Route};
+use serde::Deserialize;
+use std::collections::HashMap;
+use super::{AdminQuery, PAGE_SIZE};
+
+extend_with_app_state! {
+    #[template(path = "admin/users.html")]
+    struct AdminUsersTemplate {
+        users: Vec<User>,
+        /// active suspension of each listed user, by user id
+        suspensions: HashMap<String, Suspension>,
+        query: String,
+        offset: i64,
+    };
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuspendForm {
+    /// Shown to the suspended user.
+    pub reason: String,
+    /// Length of the suspension, `0` for a permanent ban.
+    pub days: u32,
+}
+
+pub fn route() -> Route {
+    Route::new()
+        .at("/", get(get::users))
+        .at("/:id/activate", post(post::activate))
+        .at("/:id/deactivate", post(post::deactivate))
+        .at("/:id/promote", post(post::promote))
+        .at("/:id/demote", post(post::demote))
+        .at("/:id/reset-password", post(post::reset_password))
+        .at("/:id/suspend", post(post::suspend))
+        .at("/:id/unsuspend", post(post::unsuspend))
+}
+
+mod get {
+    use log::error;
+    use poem::web::Query;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::PoemResult;
+
+    #[handler]
+    pub(crate) async fn users(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AdminUser(_): AdminUser,
+        Query(query): Query<AdminQuery>,
+    ) -> PoemResult {
+        let offset = query.offset.unwrap_or(0).max(0);
+        let users = User::find_multiple(&state.pool, query.q.as_deref(), PAGE_SIZE, offset)
+            .await
+            .unwrap_or_else(|err| {
+                error!("Failed to fetch users: {}", err);
+                Vec::new()
+            });
+
+        let ids = users.iter().map(|user| user.id.clone()).collect::<Vec<_>>();
+        let mut suspensions = HashMap::new();
+        // Longest suspension comes first, keep it.
+        for suspension in Suspension::find_active_multiple(&state.pool, &ids).await.unwrap_or_else(|err| {
+            error!("Failed to fetch suspensions: {}", err);
+            Vec::new()
+        }) {
+            suspensions.entry(suspension.user_id.clone()).or_insert(suspension);
+        }
+
+        let template = AdminUsersTemplate::from_app_state(
+            state,
+            session,
+            users,
+            suspensions,
+            query.q.unwrap_or_default(),
+            offset,
+        );
+        crate::render(&template)
+    }
+}
+
+mod post {
+    use log::error;
+    use poem::http::StatusCode;
+    use poem::web::Form;
+    use common::database::audit::{Actor, AuditEvent};
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::client_ip::ClientIp;
+    use crate::page::user::recovery::send_reset_link;
+
+    /// Account another admin may change. Admins can't lock themselves out.
+    async fn find_other(state: &AppState, admin: &User, id: String) -> Result<User, StatusCode> {
+        let user = User::find(id, &state.pool).await.map_err(|_| StatusCode::NOT_FOUND)?;
+        if user.id == admin.id {
+            Err(StatusCode::CONFLICT)
+        } else {
+            Ok(user)
+        }
+    }
+
+    #[handler]
+    pub(crate) async fn activate(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+    ) -> StatusCode {
+        let mut user = match find_other(&state, &admin, id).await {
+            Ok(user) => user,
+            Err(status) => return status,
+        };
+
+        match user.set_active(&state.pool, &Actor::new(&admin, ip), true).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to activate user: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Deactivates the account and logs it out everywhere.
+    #[handler]
+    pub(crate) async fn deactivate(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+    ) -> StatusCode {
+        let mut user = match find_other(&state, &admin, id).await {
+            Ok(user) => user,
+            Err(status) => return status,
+        };
+
+        if let Err(err) = user.set_active(&state.pool, &Actor::new(&admin, ip), false).await {
+            error!("Failed to deactivate user: {}", err);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+
+        match SessionData::revoke_all(&state.pool, &user.id).await {
+            Ok(_) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to revoke sessions of deactivated user: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    #[handler]
+    pub(crate) async fn promote(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+    ) -> StatusCode {
+        let mut user = match find_other(&state, &admin, id).await {
+            Ok(user) => user,
+            Err(status) => return status,
+        };
+
+        match user.set_admin(&state.pool, &Actor::new(&admin, ip), true).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to promote user: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    #[handler]
+    pub(crate) async fn demote(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+    ) -> StatusCode {
+        let mut user = match find_other(&state, &admin, id).await {
+            Ok(user) => user,
+            Err(status) => return status,
+        };
+
+        match user.set_admin(&state.pool, &Actor::new(&admin, ip), false).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to demote user: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Mails the user a password reset link.
+    #[handler]
+    pub(crate) async fn reset_password(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+    ) -> StatusCode {
+        let Ok(user) = User::find(id, &state.pool).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        if let Err(err) = send_reset_link(&state, &user).await {
+            error!("Failed to send password reset e-mail: {}", err);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+
+        // The account itself only changes once the link is used, so there is nothing to snapshot.
+        match AuditEvent::new(Some(&Actor::new(&admin, ip)), "reset-password", "auth.user", &user.id).record(&state.pool).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to record password reset: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Keeps the user from posting for a number of days, or for good if `days` is zero.
+    #[handler]
+    pub(crate) async fn suspend(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+        Form(form): Form<SuspendForm>,
+    ) -> StatusCode {
+        let user = match find_other(&state, &admin, id).await {
+            Ok(user) => user,
+            Err(status) => return status,
+        };
+        let reason = form.reason.trim();
+        if reason.is_empty() {
+            return StatusCode::BAD_REQUEST;
+        }
+
+        let duration = (form.days > 0).then(|| chrono::Duration::days(i64::from(form.days)));
+        match Suspension::new(&user.id, reason, Some(&admin.id), duration).save(&state.pool, &Actor::new(&admin, ip)).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to suspend user: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    #[handler]
+    pub(crate) async fn unsuspend(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+    ) -> StatusCode {
+        let Ok(user) = User::find(id, &state.pool).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        match Suspension::lift(&state.pool, &Actor::new(&admin, ip), &user.id).await {
+            Ok(0) => StatusCode::NOT_FOUND,
+            Ok(_) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to lift suspension: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
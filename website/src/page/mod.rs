@@ -1,4 +1,7 @@
+pub mod admin;
 pub mod index;
 pub mod user;
 pub mod partials;
-pub mod subject;
\ No newline at end of file
+pub mod subject;
+pub mod organization;
+pub mod search;
\ No newline at end of file
@@ -0,0 +1,275 @@
+use crate::extend_with_app_state;
+use common::database::reviewable::{LegalForm, Organization, Reviewable};
+use common::database::claim::OrganizationClaim;
+use common::database::report::ReportReason;
+use common::database::user::User;
+use common::PodelError;
+use log::error;
+use poem::http::StatusCode;
+use poem::{get, Route, handler, web::Path, EndpointExt};
+use crate::page::subject::SubjectCard;
+use crate::rate_limit::{RateLimit, CLAIM_PER_USER};
+use serde::Deserialize;
+
+extend_with_app_state! {
+    #[template(path = "subject/organization.html")]
+    struct OrganizationSubjectTemplate {
+        subject: Option<Organization>,
+        /// websites, apps, e-shops and services of the organization
+        subjects: Vec<SubjectCard>,
+        /// latest claim of the logged-in user
+        claim: Option<OrganizationClaim>
+    };
+
+    #[template(path = "subject/organization/list.html")]
+    struct OrganizationListTemplate {
+        subjects: Vec<Organization>,
+        /// choices of the form creating an organization, shown to admins
+        legal_forms: Vec<LegalForm>
+    };
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrganizationForm {
+    pub name: String,
+    /// Id of the legal form, e.g. `CZ-112`.
+    pub form: String,
+    /// Empty for organizations without an IČO, e.g. foreign ones.
+    pub ico: Option<String>,
+    pub dic: Option<String>,
+    pub address: Option<String>,
+}
+
+impl OrganizationForm {
+    /// Writes the trimmed fields to the organization, except the legal form which has to be looked up.
+    /// Fails if the name is empty or an identifier is invalid.
+    fn apply(self, organization: &mut Organization) -> Result<(), PodelError> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err(PodelError::UserError("The name can't be empty.".into()));
+        }
+
+        organization.ico = non_empty(self.ico).map(|ico| ico.parse()).transpose()?;
+        organization.dic = non_empty(self.dic).map(|dic| dic.parse()).transpose()?;
+        organization.address = non_empty(self.address);
+        organization.name = name.to_string();
+        Ok(())
+    }
+}
+
+/// Legal forms to pick from in organization forms.
+pub(crate) async fn all_legal_forms(state: &AppState) -> Vec<LegalForm> {
+    LegalForm::find_all(&state.pool).await.unwrap_or_else(|err| {
+        error!("Failed to fetch legal forms: {}", err);
+        Vec::new()
+    })
+}
+
+pub(crate) fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// Status of a failed save, `CONFLICT` if another organization has the same IČO or DIČ.
+fn save_error_status(err: &PodelError) -> StatusCode {
+    match err {
+        PodelError::SqlxError(sqlx::Error::Database(err)) if err.is_unique_violation() => StatusCode::CONFLICT,
+        _ => {
+            error!("Failed to save organization: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClaimForm {
+    /// Why the user should own the organization, checked by an admin.
+    pub evidence: String,
+}
+
+pub fn route_organization() -> Route {
+    Route::new()
+        .at("/", get(get::list).post(post::create))
+        .at("/:id", get(get::organization).put(put::update).delete(delete::organization))
+        .at("/:id/claim", poem::post(post::claim.with(RateLimit::new("claim").per_user(CLAIM_PER_USER))))
+}
+
+mod get {
+    use poem::web::Query;
+    use super::*;
+    use crate::page::partials::DbQuery;
+    use crate::PoemResult;
+
+    #[handler]
+    pub(crate) async fn list(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        Query(query_params): Query<DbQuery>
+    ) -> PoemResult {
+        let limit = query_params.limit.unwrap_or(10_i64);
+        let offset = query_params.offset.unwrap_or(0_i64);
+
+        let subjects =
+            Organization::find_multiple(&state.pool, limit, offset).await.unwrap_or_else(|err| {
+                error!("{:?}", err);
+                Vec::new()
+            });
+        let legal_forms = all_legal_forms(&state).await;
+        let template = OrganizationListTemplate::from_app_state(state, session, subjects, legal_forms);
+
+        crate::render(&template)
+    }
+
+    #[handler]
+    pub(crate) async fn organization(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        Path(id): Path<String>,
+    ) -> PoemResult {
+        let subject = Organization::find_public(&state.pool, &id).await.ok();
+        let subjects = match &subject {
+            Some(organization) => SubjectCard::find_by_organization(&state, &organization.id).await,
+            None => Ok(Vec::new()),
+        }
+        .unwrap_or_else(|err| {
+            error!("Failed to fetch subjects of organization: {}", err);
+            Vec::new()
+        });
+        let claim = match (&subject, User::from_session(session)) {
+            (Some(organization), Ok(user)) => OrganizationClaim::find_latest(&state.pool, &organization.id, &user.id)
+                .await
+                .unwrap_or_else(|err| {
+                    error!("Failed to fetch organization claim: {}", err);
+                    None
+                }),
+            _ => None,
+        };
+
+        let template = OrganizationSubjectTemplate::from_app_state(state, session, subject, subjects, claim);
+
+        crate::render(&template)
+    }
+}
+
+mod post {
+    use poem::web::Form;
+    use common::database::audit::Actor;
+    use super::*;
+    use crate::auth::{AdminUser, VerifiedUser};
+    use crate::client_ip::ClientIp;
+
+    /// Creates a new organization. Only admins can create organizations.
+    #[handler]
+    pub(crate) async fn create(
+        state: Data<&Arc<AppState>>,
+        AdminUser(admin): AdminUser,
+        ClientIp(ip): ClientIp,
+        Form(form): Form<OrganizationForm>,
+    ) -> StatusCode {
+        let Ok(legal_form) = LegalForm::find_by_id(&state.pool, &form.form).await else {
+            return StatusCode::BAD_REQUEST;
+        };
+        let mut organization = Organization::new(String::new(), legal_form, None);
+        if form.apply(&mut organization).is_err() {
+            return StatusCode::BAD_REQUEST;
+        }
+
+        match organization.save(&state.pool, Some(&Actor::new(&admin, ip))).await {
+            Ok(()) => StatusCode::CREATED,
+            Err(err) => save_error_status(&err),
+        }
+    }
+
+    /// Asks the admins to make the logged-in user the owner of an organization without one.
+    #[handler]
+    pub(crate) async fn claim(
+        state: Data<&Arc<AppState>>,
+        VerifiedUser(user): VerifiedUser,
+        Path(id): Path<String>,
+        Form(form): Form<ClaimForm>,
+    ) -> StatusCode {
+        let evidence = form.evidence.trim();
+        if evidence.is_empty() {
+            return StatusCode::BAD_REQUEST;
+        }
+
+        let Ok(organization) = Organization::find_by_id(&state.pool, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        match OrganizationClaim::create(&state.pool, &organization.id, &user, evidence).await {
+            Ok(()) => StatusCode::CREATED,
+            Err(PodelError::UserError(_)) => StatusCode::CONFLICT,
+            Err(err) => {
+                error!("Failed to claim organization: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+mod put {
+    use poem::web::Form;
+    use common::database::audit::Actor;
+    use super::*;
+    use crate::auth::{AuthUser, TokenAuth};
+    use crate::client_ip::ClientIp;
+
+    /// Renames the organization or changes its legal form, identifiers or address. Allowed for the owner
+    /// and for admins, who have to use a browser session like everywhere else in administration.
+    #[handler]
+    pub(crate) async fn update(
+        state: Data<&Arc<AppState>>,
+        AuthUser(user): AuthUser,
+        ClientIp(ip): ClientIp,
+        token: Option<Data<&TokenAuth>>,
+        Path(id): Path<String>,
+        Form(form): Form<OrganizationForm>,
+    ) -> StatusCode {
+        let Ok(mut organization) = Organization::find_by_id(&state.pool, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        let as_admin = user.is_admin && token.is_none();
+        if !as_admin && !organization.is_owned_by(&user) {
+            return StatusCode::FORBIDDEN;
+        }
+
+        let Ok(legal_form) = LegalForm::find_by_id(&state.pool, &form.form).await else {
+            return StatusCode::BAD_REQUEST;
+        };
+        organization.form = legal_form;
+        if form.apply(&mut organization).is_err() {
+            return StatusCode::BAD_REQUEST;
+        }
+
+        match organization.save(&state.pool, Some(&Actor::new(&user, ip))).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => save_error_status(&err),
+        }
+    }
+}
+
+mod delete {
+    use common::database::audit::Actor;
+    use super::*;
+    use crate::auth::AdminUser;
+    use crate::client_ip::ClientIp;
+
+    /// Deletes the organization. Only admins can delete organizations.
+    #[handler]
+    pub(crate) async fn organization(
+        state: Data<&Arc<AppState>>,
+        AdminUser(user): AdminUser,
+        ClientIp(ip): ClientIp,
+        Path(id): Path<String>,
+    ) -> StatusCode {
+        match Organization::delete(&state.pool, &Actor::new(&user, ip), id).await {
+            Ok(()) => StatusCode::OK,
+            Err(PodelError::Empty()) => StatusCode::NOT_FOUND,
+            Err(err) => {
+                error!("Failed to delete organization: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
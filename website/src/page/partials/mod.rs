@@ -1,7 +1,16 @@
-use poem::{get, Route, handler};
+use poem::{get, EndpointExt, Route, handler};
 use crate::{extend_with_app_state, PoemResult};
+use crate::rate_limit::{RateLimit, COMMENT_PER_IP, COMMENT_PER_USER, REPORT_PER_USER};
 use common::{database::user::User};
-use common::database::comment::{Comment, CommentParent, Commentable};
+use common::database::comment::{Comment, CommentEdit, CommentParent, CommentSort, Commentable};
+use common::database::audit::Actor;
+use common::database::report::{ReportReason, ReportTarget};
+use common::database::reviewable;
+use common::database::reviewable::website::Website;
+use common::database::reviewable::Reviewable;
+use common::database::verification::WebsiteVerification;
+use crate::client_ip::ClientIp;
+use common::PodelError;
 use serde::Deserialize;
 
 extend_with_app_state! {
@@ -17,6 +26,13 @@ extend_with_app_state! {
     #[template(path = "partials/comments.html")]
     struct CommentsTemplate {
         comments: Vec<Comment>,
+        /// whether the logged-in user may post official responses
+        can_respond_officially: bool,
+    };
+
+    #[template(path = "partials/comment-history.html")]
+    struct CommentHistoryTemplate {
+        history: Vec<CommentEdit>,
     };
 }
 
@@ -26,17 +42,86 @@ pub struct DbQuery {
     pub offset: Option<i64>,
 }
 
+#[derive(Debug, serde::Deserialize)]
+pub struct CommentQuery {
+    /// Load replies to this comment instead of top-level comments.
+    pub reply_to: Option<String>,
+    pub sort: Option<CommentSort>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct VoteForm {
+    /// `1` for an upvote, `-1` for a downvote.
+    pub value: i16,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CommentForm {
+    pub content: String,
+    /// Id of the comment being replied to.
+    pub reply_to: Option<String>,
+    /// Post as an official response of the subject's owner.
+    pub official: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ReportForm {
+    pub target: ReportTarget,
+    /// Subject kind the target belongs to, e.g. `website`.
+    pub subject_type: String,
+    pub subject_id: String,
+    /// Same as `subject_id` when reporting the subject itself.
+    pub target_id: String,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+}
+
+/// Subject kinds that have a comment table.
+fn is_comment_parent(parent_type: &str) -> bool {
+    reviewable::is_kind(parent_type)
+}
+
+/// Whether the user owns the organization of the subject and may respond in its name.
+/// Websites additionally need the owner to have verified control of their domain.
+pub(crate) async fn can_respond_officially(state: &AppState, parent_type: &str, parent_id: &str, user: Option<&User>) -> bool {
+    let Some(user) = user else {
+        return false;
+    };
+
+    let owner = async {
+        Ok::<_, PodelError>(
+            reviewable::is_organization_owner(&state.pool, parent_type, parent_id, &user.id).await?
+                && (parent_type != Website::KIND
+                    || WebsiteVerification::is_verified_owner(&state.pool, parent_id, &user.id).await?),
+        )
+    };
+
+    owner.await.unwrap_or_else(|err| {
+        log::error!("Failed to check subject ownership: {}", err);
+        false
+    })
+}
+
 pub fn route() -> Route {
     Route::new()
-        .at("/comments/:parent_type/:parent_id", get(get::comments))
+        .at(
+            "/comments/:parent_type/:parent_id",
+            get(get::comments).post(
+                post::comment.with(RateLimit::new("comment").per_ip(COMMENT_PER_IP).per_user(COMMENT_PER_USER)),
+            ),
+        )
+        .at("/comments/:parent_type/:parent_id/:id", poem::put(put::comment).delete(delete::comment))
+        .at("/comments/:parent_type/:parent_id/:id/history", get(get::comment_history))
+        .at("/comments/:parent_type/:parent_id/:id/vote", poem::post(post::vote).delete(delete::vote))
+        .at("/reports", poem::post(post::report.with(RateLimit::new("report").per_user(REPORT_PER_USER))))
         .at("/navbar", get(get::navbar))
+        .at("/search", get(crate::page::search::get::typeahead))
         .at("/modals/user/profile/:id", get(get::modals::profile))
 }
 
 mod get {
     use log::error;
     use poem::web::{Path, Query};
-    use common::database::reviewable::website::Website;
     use super::*;
 
     #[handler]
@@ -44,21 +129,50 @@ mod get {
         state: Data<&Arc<AppState>>,
         session: &Session,
         Path((parent_type, parent_id)): Path<(String, String)>,
-        Query(query_params): Query<DbQuery>
+        Query(query_params): Query<DbQuery>,
+        Query(comment_query): Query<CommentQuery>
     ) -> PoemResult {
         let limit = query_params.limit.unwrap_or(10_i64);
         let offset = query_params.offset.unwrap_or(0_i64);
 
-        let comments = match parent_type.as_str() {
-                "website"|"user" => Comment::find_multiple(&state.pool, parent_type, parent_id, limit, offset).await,
-                _ => return Err(poem::error::NotFoundError),
+        if !is_comment_parent(&parent_type) {
+            return Err(poem::error::NotFoundError);
+        }
+
+        let user = User::from_session(session).ok();
+        let official = can_respond_officially(&state, &parent_type, &parent_id, user.as_deref()).await;
+        let comments = match comment_query.reply_to {
+                Some(reply_to) => Comment::find_replies(&state.pool, parent_type, reply_to, limit, offset).await,
+                None => Comment::find_multiple(&state.pool, parent_type, parent_id, comment_query.sort.unwrap_or_default(), limit, offset).await,
             }
             .map_err(|err| {
                 error!("Failed to fetch comments: {}", err);
                 poem::error::NotFoundError
             })?;
-        println!("{:?}", comments);
-        let template = CommentsTemplate::from_app_state(state, session, comments);
+        let template = CommentsTemplate::from_app_state(state, session, comments, official);
+        crate::render(&template)
+    }
+
+    /// Previous versions of a comment. Only visible to moderators.
+    #[handler]
+    pub(crate) async fn comment_history(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        Path((parent_type, _parent_id, id)): Path<(String, String, String)>,
+    ) -> PoemResult {
+        let is_admin = User::from_session(session).is_ok_and(|user| user.is_admin);
+        if !is_admin || !is_comment_parent(&parent_type) {
+            return Err(poem::error::NotFoundError);
+        }
+
+        let history = CommentEdit::find_multiple(&state.pool, parent_type, id)
+            .await
+            .map_err(|err| {
+                error!("Failed to fetch comment history: {}", err);
+                poem::error::NotFoundError
+            })?;
+
+        let template = CommentHistoryTemplate::from_app_state(state, session, history);
         crate::render(&template)
     }
 
@@ -87,4 +201,252 @@ mod get {
 
     }
 
-}
\ No newline at end of file
+}
+
+mod post {
+    use std::ops::Deref;
+    use log::error;
+    use common::database::report::Report;
+    use common::database::review::Review;
+    use common::database::reviewable::Organization;
+    use poem::http::StatusCode;
+    use poem::web::{Form, Path};
+    use super::*;
+    use crate::auth::{AuthUser, VerifiedUser};
+
+    /// Posts a new comment, or a reply when `reply_to` is set, as the logged-in user.
+    #[handler]
+    pub(crate) async fn comment(
+        state: Data<&Arc<AppState>>,
+        VerifiedUser(user): VerifiedUser,
+        Path((parent_type, parent_id)): Path<(String, String)>,
+        Form(form): Form<CommentForm>,
+    ) -> StatusCode {
+        if !is_comment_parent(&parent_type) {
+            return StatusCode::NOT_FOUND;
+        }
+
+        if form.content.trim().is_empty() {
+            return StatusCode::BAD_REQUEST;
+        }
+
+        let official = form.official.unwrap_or(false);
+        if official && !can_respond_officially(&state, &parent_type, &parent_id, Some(user.as_ref())).await {
+            return StatusCode::FORBIDDEN;
+        }
+
+        let mut comment = Comment::new(&parent_type, &parent_id, form.content.trim(), user);
+        if official {
+            comment = comment.as_official();
+        }
+
+        if let Some(reply_to) = form.reply_to.filter(|id| !id.is_empty()) {
+            match Comment::find(&state.pool, &parent_type, &reply_to).await {
+                Ok(parent) if parent.parent_id == parent_id && !parent.is_removed() => {
+                    comment = comment.with_reply_to(parent.id)
+                }
+                _ => return StatusCode::BAD_REQUEST,
+            }
+        }
+
+        match comment.save(&state.pool).await {
+            Ok(()) => StatusCode::CREATED,
+            Err(err) => {
+                error!("Failed to save comment: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Casts or changes the logged-in user's vote on a comment.
+    #[handler]
+    pub(crate) async fn vote(
+        state: Data<&Arc<AppState>>,
+        VerifiedUser(user): VerifiedUser,
+        Path((parent_type, _parent_id, id)): Path<(String, String, String)>,
+        Form(form): Form<VoteForm>,
+    ) -> StatusCode {
+        if !is_comment_parent(&parent_type) {
+            return StatusCode::NOT_FOUND;
+        }
+
+        let Ok(comment) = Comment::find(&state.pool, &parent_type, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        if comment.is_removed() {
+            return StatusCode::GONE;
+        }
+
+        match comment.vote(&state.pool, &user, form.value).await {
+            Ok(()) => StatusCode::OK,
+            Err(PodelError::UserError(_)) => StatusCode::BAD_REQUEST,
+            Err(err) => {
+                error!("Failed to vote on comment: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Whether the reported comment, review or subject exists and belongs to the given subject.
+    async fn is_reportable(state: &AppState, form: &ReportForm) -> bool {
+        match form.target {
+            ReportTarget::Comment => {
+                is_comment_parent(&form.subject_type)
+                    && Comment::find(&state.pool, &form.subject_type, &form.target_id)
+                        .await
+                        .is_ok_and(|comment| comment.parent_id == form.subject_id && !comment.is_removed())
+            }
+            ReportTarget::Review => {
+                reviewable::is_kind(&form.subject_type)
+                    && Review::find(&state.pool, &form.subject_type, &form.target_id)
+                        .await
+                        .is_ok_and(|review| review.parent_id == form.subject_id)
+            }
+            ReportTarget::Subject => {
+                form.target_id == form.subject_id
+                    && match form.subject_type.as_str() {
+                        "organization" => Organization::find_by_id(&state.pool, &form.subject_id).await.is_ok(),
+                        kind => reviewable::exists(&state.pool, kind, &form.subject_id).await.unwrap_or_else(|err| {
+                            error!("Failed to look up reported subject: {}", err);
+                            false
+                        }),
+                    }
+            }
+        }
+    }
+
+    /// Flags content for the moderation queue.
+    #[handler]
+    pub(crate) async fn report(
+        state: Data<&Arc<AppState>>,
+        AuthUser(user): AuthUser,
+        Form(form): Form<ReportForm>,
+    ) -> StatusCode {
+        if !is_reportable(&state, &form).await {
+            return StatusCode::NOT_FOUND;
+        }
+
+        let details = form.details.as_deref().map(str::trim).filter(|details| !details.is_empty());
+        let report = Report::new(
+            form.target,
+            &form.subject_type,
+            &form.subject_id,
+            &form.target_id,
+            user.deref().clone(),
+            form.reason,
+            details,
+        );
+
+        match report.save(&state.pool).await {
+            Ok(()) => StatusCode::CREATED,
+            Err(err) => {
+                error!("Failed to save report: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+mod put {
+    use log::error;
+    use poem::http::StatusCode;
+    use poem::web::{Form, Path};
+    use super::*;
+    use crate::auth::VerifiedUser;
+
+    /// Edits a comment. Allowed for its author and admins.
+    #[handler]
+    pub(crate) async fn comment(
+        state: Data<&Arc<AppState>>,
+        VerifiedUser(user): VerifiedUser,
+        ClientIp(ip): ClientIp,
+        Path((parent_type, _parent_id, id)): Path<(String, String, String)>,
+        Form(form): Form<CommentForm>,
+    ) -> StatusCode {
+        if !is_comment_parent(&parent_type) {
+            return StatusCode::NOT_FOUND;
+        }
+
+        if form.content.trim().is_empty() {
+            return StatusCode::BAD_REQUEST;
+        }
+
+        let Ok(mut comment) = Comment::find(&state.pool, &parent_type, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        if !comment.can_edit(Some(&user)) {
+            return StatusCode::FORBIDDEN;
+        }
+
+        match comment.update(&state.pool, &Actor::new(&user, ip), form.content.trim()).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to update comment: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+mod delete {
+    use log::error;
+    use poem::http::StatusCode;
+    use poem::web::Path;
+    use super::*;
+    use crate::auth::AuthUser;
+
+    /// Replaces a comment with a placeholder. Allowed for its author and admins.
+    #[handler]
+    pub(crate) async fn comment(
+        state: Data<&Arc<AppState>>,
+        AuthUser(user): AuthUser,
+        ClientIp(ip): ClientIp,
+        Path((parent_type, _parent_id, id)): Path<(String, String, String)>,
+    ) -> StatusCode {
+        if !is_comment_parent(&parent_type) {
+            return StatusCode::NOT_FOUND;
+        }
+
+        let Ok(comment) = Comment::find(&state.pool, &parent_type, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        if !comment.can_edit(Some(&user)) {
+            return StatusCode::FORBIDDEN;
+        }
+
+        match comment.delete(&state.pool, &Actor::new(&user, ip)).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to delete comment: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Withdraws the logged-in user's vote on a comment.
+    #[handler]
+    pub(crate) async fn vote(
+        state: Data<&Arc<AppState>>,
+        AuthUser(user): AuthUser,
+        Path((parent_type, _parent_id, id)): Path<(String, String, String)>,
+    ) -> StatusCode {
+        if !is_comment_parent(&parent_type) {
+            return StatusCode::NOT_FOUND;
+        }
+
+        let Ok(comment) = Comment::find(&state.pool, &parent_type, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        match comment.unvote(&state.pool, &user).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to withdraw vote: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::extend_with_app_state;
+use common::database::search::SearchResult;
+use poem::{get, Route, handler};
+use serde::Deserialize;
+
+extend_with_app_state! {
+    #[template(path = "search.html")]
+    struct SearchTemplate {
+        query: String,
+        results: Vec<SearchResult>
+    };
+
+    #[template(path = "partials/search-results.html")]
+    struct SearchResultsTemplate {
+        query: String,
+        results: Vec<SearchResult>
+    };
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+pub fn route_search() -> Route {
+    Route::new()
+        .at("/", get(get::search))
+}
+
+pub(crate) mod get {
+    use log::error;
+    use poem::web::Query;
+    use super::*;
+    use crate::PoemResult;
+
+    async fn find(state: &AppState, query: &SearchQuery) -> Vec<SearchResult> {
+        let Some(q) = query.q.as_deref() else {
+            return Vec::new();
+        };
+
+        SearchResult::find_multiple(&state.pool, q, query.limit.unwrap_or(20_i64), query.offset.unwrap_or(0_i64))
+            .await
+            .unwrap_or_else(|err| {
+                error!("Search failed: {}", err);
+                Vec::new()
+            })
+    }
+
+    #[handler]
+    pub(crate) async fn search(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        Query(query): Query<SearchQuery>,
+    ) -> PoemResult {
+        let results = find(&state, &query).await;
+        let template = SearchTemplate::from_app_state(state, session, query.q.unwrap_or_default(), results);
+
+        crate::render(&template)
+    }
+
+    /// Typeahead results for the navbar search box.
+    #[handler]
+    pub(crate) async fn typeahead(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        Query(query): Query<SearchQuery>,
+    ) -> PoemResult {
+        let query = SearchQuery { limit: Some(query.limit.unwrap_or(8_i64)), ..query };
+        let results = find(&state, &query).await;
+        let template = SearchResultsTemplate::from_app_state(state, session, query.q.unwrap_or_default(), results);
+
+        crate::render(&template)
+    }
+}
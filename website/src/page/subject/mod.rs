@@ -1,31 +1,191 @@
 use crate::extend_with_app_state;
+use common::database::reviewable::app::App;
+use common::database::reviewable::eshop::Eshop;
+use common::database::reviewable::service::Service;
 use common::database::reviewable::website::Website;
-use common::database::comment::Comment;
-use poem::{get, Route, handler, web::Path};
+use common::database::reviewable::{Karma, Organization, Reviewable};
+use common::database::comment::{Comment, CommentSort};
+use common::database::report::ReportReason;
+use common::database::review::Review;
+use common::database::user::User;
+use common::database::verification::WebsiteVerification;
+use common::ownership::{OwnershipProbe, TXT_PREFIX};
+use common::PodelError;
+use poem::{get, Route, handler, web::Path, EndpointExt};
+use crate::rate_limit::{RateLimit, VERIFICATION_PER_USER};
+use serde::Deserialize;
 
 extend_with_app_state! {
     #[template(path = "subject/website.html")]
     struct WebsiteSubjectTemplate {
         subject: Option<Website>,
-        comments: Option<Vec<Comment>>
+        comments: Option<Vec<Comment>>,
+        /// verified ownership by the owner of the website's organization
+        ownership: Option<WebsiteVerification>,
+        /// whether the logged-in user is the verified owner
+        can_respond_officially: bool
+    };
+
+    #[template(path = "subject/website/verification.html")]
+    struct WebsiteVerificationTemplate {
+        website: Website,
+        verification: WebsiteVerification,
+        txt_record: String,
+        well_known_url: String
     };
 
     #[template(path = "subject/website/list.html")]
     struct WebsiteListTemplate {
         subjects: Vec<Website>
     };
+
+    #[template(path = "subject/reviews.html")]
+    struct ReviewsTemplate {
+        parent_id: String,
+        /// public page of the subject the reviews belong to
+        subject_url: String,
+        reviews: Vec<Review>,
+        can_respond_officially: bool
+    };
+
+    #[template(path = "subject/list.html")]
+    struct SubjectListTemplate {
+        kind: &'static str,
+        subjects: Vec<SubjectCard>
+    };
+
+    #[template(path = "subject/detail.html")]
+    struct SubjectTemplate {
+        kind: &'static str,
+        subject: Option<SubjectCard>,
+        comments: Vec<Comment>,
+        /// whether the logged-in user is the verified owner
+        can_respond_officially: bool
+    };
+}
+
+/// What the shared list and detail pages show of a subject of any kind.
+pub struct SubjectCard {
+    pub kind: &'static str,
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub karma: Karma,
+    pub organization: Option<Organization>,
+    /// key of the label in the Fluent bundles and the value
+    pub details: Vec<(&'static str, String)>,
+}
+
+impl SubjectCard {
+    pub fn new<T: Reviewable>(subject: &T) -> Self {
+        Self {
+            kind: T::KIND,
+            id: subject.id().clone(),
+            name: subject.display_name().to_string(),
+            description: subject.description().map(str::to_string),
+            karma: subject.karma().clone(),
+            organization: subject.organization().cloned(),
+            details: subject.details(),
+        }
+    }
+
+    /// Subjects of every kind belonging to the organization, by kind and name.
+    pub async fn find_by_organization(state: &AppState, organization_id: &str) -> Result<Vec<Self>, PodelError> {
+        let (websites, apps, eshops, services) = tokio::try_join!(
+            Website::find_by_organization(&state.pool, organization_id),
+            App::find_by_organization(&state.pool, organization_id),
+            Eshop::find_by_organization(&state.pool, organization_id),
+            Service::find_by_organization(&state.pool, organization_id),
+        )?;
+
+        Ok(websites
+            .iter()
+            .map(Self::new)
+            .chain(apps.iter().map(Self::new))
+            .chain(eshops.iter().map(Self::new))
+            .chain(services.iter().map(Self::new))
+            .collect())
+    }
+
+    /// Public page of the subject.
+    pub fn url(&self) -> String {
+        subject_url(self.kind, &self.id)
+    }
+
+    /// Admin page where the subject is edited.
+    pub fn admin_url(&self) -> String {
+        format!("/admin/{}s/{}", self.kind, self.id)
+    }
+
+    pub fn organization_id(&self) -> Option<&str> {
+        self.organization.as_ref().map(|organization| organization.id.as_str())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewForm {
+    pub title: String,
+    pub content: String,
+    pub overall: i16,
+    pub reliability: i16,
+    pub quality: i16,
+    pub support: i16,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResponseForm {
+    /// Empty to withdraw the response.
+    pub response: String,
 }
 
 pub fn route_website() -> Route {
     Route::new()
         .at("/", get(get::list))
         .at("/:id", get(get::website))
+        .at("/:id/reviews", get(get::reviews::<Website>::default()).post(post::review::<Website>::default()))
+        .at("/:id/reviews/:review_id/response", poem::post(post::respond::<Website>::default()))
+        .at(
+            "/:id/verification",
+            get(get::verification).post(post::verify.with(RateLimit::new("verification").per_user(VERIFICATION_PER_USER))),
+        )
+}
+
+/// Shared pages of a reviewable kind, for kinds without pages of their own.
+pub fn route_subject<T: Reviewable + 'static>() -> Route {
+    Route::new()
+        .at("/", get(get::subject_list::<T>::default()))
+        .at("/:id", get(get::subject_detail::<T>::default()))
+        .at("/:id/reviews", get(get::reviews::<T>::default()).post(post::review::<T>::default()))
+        .at("/:id/reviews/:review_id/response", poem::post(post::respond::<T>::default()))
+}
+
+/// Public page of a subject. Websites live under `/web`, the other kinds under their name.
+pub fn subject_url(kind: &str, id: &str) -> String {
+    match kind {
+        "website" => format!("/web/{id}"),
+        kind => format!("/{kind}/{id}"),
+    }
+}
+
+/// Website of the id, `Err` unless `user` owns its organization.
+async fn find_owned(state: &AppState, id: String, user: &User) -> Result<Website, poem::http::StatusCode> {
+    let website = Website::find(&state.pool, id).await.map_err(|_| poem::http::StatusCode::NOT_FOUND)?;
+    let owner = website.organization.as_ref().and_then(|organization| organization.user.as_ref());
+
+    if owner.is_some_and(|owner| owner.id == user.id) {
+        Ok(website)
+    } else {
+        Err(poem::http::StatusCode::FORBIDDEN)
+    }
 }
 
 mod get {
     use log::error;
+    use poem::web::Query;
     use tokio::join;
     use super::*;
+    use crate::auth::VerifiedUser;
+    use crate::page::partials::{can_respond_officially, DbQuery};
     use crate::PoemResult;
 
     #[handler]
@@ -43,6 +203,62 @@ mod get {
         crate::render(&template)
     }
 
+    #[handler]
+    pub(crate) async fn subject_list<T: Reviewable>(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+    ) -> PoemResult {
+        let subjects = T::find_multiple(&state.pool, 10_i64, 0_i64).await.unwrap_or_else(|err| {
+            error!("{:?}", err);
+            Vec::new()
+        });
+        let subjects = subjects.iter().map(SubjectCard::new).collect();
+        let template = SubjectListTemplate::from_app_state(state, session, T::KIND, subjects);
+
+        crate::render(&template)
+    }
+
+    #[handler]
+    pub(crate) async fn subject_detail<T: Reviewable>(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        Path(id): Path<String>,
+    ) -> PoemResult {
+        let subject = T::find(&state.pool, id).await.ok();
+        let (comments, can_respond_officially) = match &subject {
+            Some(subject) => {
+                let user = User::from_session(session).ok();
+                let comments = Comment::find_multiple(&state.pool, T::KIND, subject.id(), CommentSort::default(), 20, 0);
+                let visit = T::record_visit(&state.pool, subject.id());
+                let can_respond = can_respond_officially(&state, T::KIND, subject.id(), user.as_deref());
+
+                let (comments, visit, can_respond) = join!(comments, visit, can_respond);
+
+                if let Err(err) = visit {
+                    error!("Failed to record visit: {}", err);
+                }
+
+                let comments = comments.unwrap_or_else(|err| {
+                    error!("Failed to fetch comments: {}", err);
+                    Vec::new()
+                });
+                (comments, can_respond)
+            }
+            None => (Vec::new(), false),
+        };
+
+        let template = SubjectTemplate::from_app_state(
+            state,
+            session,
+            T::KIND,
+            subject.as_ref().map(SubjectCard::new),
+            comments,
+            can_respond_officially,
+        );
+
+        crate::render(&template)
+    }
+
     #[handler]
     pub(crate) async fn website(
         state: Data<&Arc<AppState>>,
@@ -50,15 +266,215 @@ mod get {
         Path(id): Path<String>,
     ) -> PoemResult {
         let subject = Website::find(&state.pool, &id);
-        let comments = Comment::find_multiple(&state.pool, "website", &id, 20, 0);
+        let comments = Comment::find_multiple(&state.pool, "website", &id, CommentSort::default(), 20, 0);
+        let visit = Website::record_visit(&state.pool, &id);
+
+        let (subject, comments, visit) = join!(subject, comments, visit);
+
+        if let Err(err) = visit {
+            error!("Failed to record visit: {}", err);
+        }
+
+        let comments = comments.unwrap_or_else(|err| {
+            error!("Failed to fetch comments: {}", err);
+            Vec::new()
+        });
+        let ownership = match &subject {
+            Ok(website) => WebsiteVerification::find_verified(&state.pool, &website.id).await.unwrap_or_else(|err| {
+                error!("Failed to fetch website ownership: {}", err);
+                None
+            }),
+            Err(_) => None,
+        };
+        let user = User::from_session(session).ok();
+        let can_respond_officially = match &subject {
+            Ok(website) => can_respond_officially(&state, Website::KIND, &website.id, user.as_deref()).await,
+            Err(_) => false,
+        };
+
+        let template = WebsiteSubjectTemplate::from_app_state(
+            state,
+            session,
+            subject.ok(),
+            Some(comments),
+            ownership,
+            can_respond_officially,
+        );
+
+        crate::render(&template)
+    }
+
+    #[handler]
+    pub(crate) async fn reviews<T: Reviewable>(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        Path(id): Path<String>,
+        Query(query_params): Query<DbQuery>
+    ) -> PoemResult {
+        let limit = query_params.limit.unwrap_or(10_i64);
+        let offset = query_params.offset.unwrap_or(0_i64);
+
+        let reviews = Review::find_multiple(&state.pool, T::KIND, &id, limit, offset)
+            .await
+            .map_err(|err| {
+                error!("Failed to fetch reviews: {}", err);
+                poem::error::NotFoundError
+            })?;
 
-        let (subject, comments) = join!(subject, comments);
+        let user = User::from_session(session).ok();
+        let can_respond_officially = can_respond_officially(&state, T::KIND, &id, user.as_deref()).await;
 
-        let comments = comments.unwrap();
+        let subject_url = subject_url(T::KIND, &id);
+        let template = ReviewsTemplate::from_app_state(state, session, id, subject_url, reviews, can_respond_officially);
+        crate::render(&template)
+    }
+
+    /// Instructions for proving control of the website's domain, for the owner of its organization.
+    #[handler]
+    pub(crate) async fn verification(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        VerifiedUser(user): VerifiedUser,
+        Path(id): Path<String>,
+    ) -> PoemResult {
+        let website = find_owned(&state, id, &user).await.map_err(|_| poem::error::NotFoundError)?;
+        let verification = WebsiteVerification::issue(&state.pool, &website.id, &user.id).await.map_err(|err| {
+            error!("Failed to issue verification token: {}", err);
+            poem::error::NotFoundError
+        })?;
 
-        let template = WebsiteSubjectTemplate::from_app_state(state, session, subject.ok(), Some(comments));
+        let txt_record = format!("{TXT_PREFIX}{}", verification.token);
+        let well_known_url = OwnershipProbe::well_known_url(&website.domain_name);
 
+        let template = WebsiteVerificationTemplate::from_app_state(
+            state,
+            session,
+            website,
+            verification,
+            txt_record,
+            well_known_url,
+        );
         crate::render(&template)
     }
+}
+
+mod post {
+    use log::error;
+    use poem::http::StatusCode;
+    use poem::web::Form;
+    use common::database::review::Rating;
+    use common::database::audit::Actor;
+    use common::PodelError;
+    use super::*;
+    use crate::auth::VerifiedUser;
+    use crate::client_ip::ClientIp;
+    use crate::page::partials::can_respond_officially;
+
+    /// Creates the logged-in user's review of a subject, or updates it if they already wrote one.
+    #[handler]
+    pub(crate) async fn review<T: Reviewable>(
+        state: Data<&Arc<AppState>>,
+        VerifiedUser(user): VerifiedUser,
+        Path(id): Path<String>,
+        Form(form): Form<ReviewForm>,
+    ) -> StatusCode {
+        if form.title.trim().is_empty() || form.content.trim().is_empty() {
+            return StatusCode::BAD_REQUEST;
+        }
+
+        let Ok(rating) = Rating::new(form.overall, form.reliability, form.quality, form.support) else {
+            return StatusCode::BAD_REQUEST;
+        };
+
+        let Ok(subject) = T::find(&state.pool, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+        let subject_id = subject.id();
+
+        let result = match Review::find_by_user(&state.pool, T::KIND, subject_id, &user.id).await {
+            Ok(Some(mut review)) => {
+                review.title = form.title;
+                review.content = form.content;
+                review.rating = rating;
+                review.update(&state.pool).await
+            }
+            Ok(None) => Review::new(T::KIND, subject_id, form.title, form.content, rating, user)
+                .save(&state.pool)
+                .await,
+            Err(err) => Err(err),
+        };
+
+        if result.is_ok() {
+            if let Err(err) = Karma::recompute(&state.pool, T::KIND, subject_id, &state.karma_weights).await {
+                error!("Failed to recompute karma: {}", err);
+            }
+        }
+
+        match result {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to save review: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Posts, replaces or withdraws the official response of the subject's owner to a review.
+    #[handler]
+    pub(crate) async fn respond<T: Reviewable>(
+        state: Data<&Arc<AppState>>,
+        VerifiedUser(user): VerifiedUser,
+        ClientIp(ip): ClientIp,
+        Path((id, review_id)): Path<(String, String)>,
+        Form(form): Form<ResponseForm>,
+    ) -> StatusCode {
+        let Ok(subject) = T::find(&state.pool, id).await else {
+            return StatusCode::NOT_FOUND;
+        };
+        let mut review = match Review::find(&state.pool, T::KIND, review_id).await {
+            Ok(review) if &review.parent_id == subject.id() && review.hidden_at.is_none() => review,
+            _ => return StatusCode::NOT_FOUND,
+        };
+        if !can_respond_officially(&state, T::KIND, subject.id(), Some(user.as_ref())).await {
+            return StatusCode::FORBIDDEN;
+        }
+
+        let response = Some(form.response.trim().to_string()).filter(|response| !response.is_empty());
+        match review.respond(&state.pool, &Actor::new(&user, ip), response).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to respond to review: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
 
-}
\ No newline at end of file
+    /// Looks for the verification token of the logged-in owner on the website's domain.
+    #[handler]
+    pub(crate) async fn verify(
+        state: Data<&Arc<AppState>>,
+        VerifiedUser(user): VerifiedUser,
+        Path(id): Path<String>,
+    ) -> StatusCode {
+        let website = match find_owned(&state, id, &user).await {
+            Ok(website) => website,
+            Err(status) => return status,
+        };
+        let mut verification = match WebsiteVerification::issue(&state.pool, &website.id, &user.id).await {
+            Ok(verification) => verification,
+            Err(err) => {
+                error!("Failed to issue verification token: {}", err);
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+        };
+
+        match verification.verify(&state.pool, &state.ownership_probe, &website.domain_name).await {
+            Ok(_) => StatusCode::OK,
+            Err(PodelError::Empty()) => StatusCode::UNPROCESSABLE_ENTITY,
+            Err(err) => {
+                error!("Failed to verify website ownership: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
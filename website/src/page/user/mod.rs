@@ -1,11 +1,21 @@
 use crate::extend_with_app_state;
-use poem::{get, post, Route, handler, web::Path};
+use poem::{get, post, EndpointExt, Route, handler, web::Path};
+use common::database::suspension::Suspension;
 use common::database::user::User;
+use crate::rate_limit::{RateLimit, AUTH_PER_IP};
+use serde::Deserialize;
+
+pub mod recovery;
+pub mod settings;
+pub mod two_factor;
+pub mod verification;
 
 extend_with_app_state! {
     #[template(path = "user/profile.html")]
     struct UserProfileTemplate {
         profile: Option<User>,
+        /// only shown to the user themselves and to admins
+        suspension: Option<Suspension>,
     };
 
     #[template(path = "user/auth.html")]
@@ -14,16 +24,35 @@ extend_with_app_state! {
 
 pub fn route_user() -> Route {
     Route::new()
+        .nest("/settings", settings::route())
         .at("/:id", get(get::profile))
 }
 
 pub fn route_auth() -> Route {
     Route::new()
-        .at("/", get(get::auth).post(post::auth))
+        .at("/", get(get::auth).post(post::auth.with(RateLimit::new("auth").per_ip(AUTH_PER_IP))))
         .at("/logout", post(post::logout))
+        .at("/2fa", post(two_factor::post::login.with(RateLimit::new("2fa").per_ip(AUTH_PER_IP))))
+        .at("/language", post(post::language))
+        .at("/verify", post(verification::post::resend.with(RateLimit::new("verify").per_ip(AUTH_PER_IP))))
+        .at("/verify/:token", get(verification::get::verify))
+        .at(
+            "/forgot",
+            get(recovery::get::forgot).post(recovery::post::forgot.with(RateLimit::new("forgot").per_ip(AUTH_PER_IP))),
+        )
+        .at(
+            "/reset/:token",
+            get(recovery::get::reset).post(recovery::post::reset.with(RateLimit::new("reset").per_ip(AUTH_PER_IP))),
+        )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LanguageForm {
+    pub language: String,
 }
 
 mod get {
+    use log::error;
     use super::*;
     use crate::PoemResult;
 
@@ -33,8 +62,19 @@ mod get {
         session: &Session,
         Path(user_id): Path<String>,
     ) -> PoemResult {
-        let profile = User::find(user_id, &state.pool).await;
-        let template = UserProfileTemplate::from_app_state(state, session, profile.ok());
+        let profile = User::find(user_id, &state.pool).await.ok();
+        let viewer = User::from_session(session).ok();
+        let suspension = match (&profile, viewer) {
+            (Some(profile), Some(viewer)) if viewer.id == profile.id || viewer.is_admin => {
+                Suspension::find_active(&state.pool, &profile.id).await.unwrap_or_else(|err| {
+                    error!("Failed to fetch suspension: {}", err);
+                    None
+                })
+            }
+            _ => None,
+        };
+
+        let template = UserProfileTemplate::from_app_state(state, session, profile, suspension);
         crate::render(&template)
     }
 
@@ -51,56 +91,110 @@ mod get {
 mod post {
     use log::error;
     use super::*;
-    use poem::{IntoResponse, Response};
-    use poem::http::StatusCode;
+    use poem::{Error, IntoResponse, Response};
+    use poem::http::{header, HeaderMap, StatusCode};
     use poem::web::Form;
-    use common::database::user::{is_valid, verify_password, Credentials, SessionData};
+    use common::database::user::{hash_password, is_valid, needs_rehash, verify_password, Credentials, SessionData};
+    use common::database::lockout::Lockout;
+    use common::database::totp::Totp;
+    use crate::client_ip::ClientIp;
+    use crate::rate_limit::{LOGIN_PER_ACCOUNT, REGISTER_PER_IP};
+    use crate::language::SUPPORTED_LANGUAGES;
     use crate::PoemResult;
+    use super::two_factor::{PendingLogin, TwoFactorLoginTemplate};
 
     #[handler]
     pub(crate) async fn logout(
+        state: Data<&Arc<AppState>>,
         session: &Session
     ) -> impl IntoResponse {
+        if let Some(data) = SessionData::from_session(session) {
+            if let Err(err) = SessionData::revoke(&state.pool, &data.user.id, &data.id).await {
+                error!("Failed to revoke session: {}", err);
+            }
+        }
         session.clear();
         Response::builder().status(StatusCode::OK).finish()
     }
 
+    /// Switches the interface language and remembers it for logged-in users.
+    #[handler]
+    pub(crate) async fn language(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        Form(form): Form<LanguageForm>,
+    ) -> StatusCode {
+        let Some(language) = SUPPORTED_LANGUAGES.into_iter().find(|supported| *supported == form.language) else {
+            return StatusCode::BAD_REQUEST;
+        };
+
+        session.set("user_language", language);
+
+        if let Ok(user) = User::from_session(session) {
+            if let Err(err) = user.as_ref().clone().set_language(&state.pool, language).await {
+                error!("Failed to save language: {}", err);
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+        }
+
+        StatusCode::OK
+    }
+
     #[handler]
     pub(crate) async fn auth(
         state: Data<&Arc<AppState>>,
         session: &Session,
+        headers: &HeaderMap,
+        ClientIp(ip): ClientIp,
         Form(creds): Form<Credentials>,
-    ) -> StatusCode {
-        let status = if creds.authentication {
-            login(state, session, creds).await
+    ) -> Response {
+        let user_agent = headers.get(header::USER_AGENT).and_then(|value| value.to_str().ok());
+        let ip = ip.map(|ip| ip.to_string());
+        if creds.authentication {
+            login(state, session, ip, user_agent, creds).await
         } else {
-            register(state, session, creds).await
-        };
-        status
+            if let Some(ip) = &ip {
+                if let Some(response) = crate::rate_limit::check(&state, "register-ip", ip, REGISTER_PER_IP).await {
+                    return response;
+                }
+            }
+            register(state, session, ip, user_agent, creds).await.into_response()
+        }
     }
 
     async fn register(
         state: Data<&Arc<AppState>>,
         session: &Session,
+        ip: Option<String>,
+        user_agent: Option<&str>,
         creds: Credentials) -> StatusCode {
-        if creds.username.is_empty() || creds.password.is_empty() {
+        let email = creds.email.as_deref().map(str::trim).unwrap_or_default().to_string();
+        if creds.username.is_empty() || creds.password.is_empty() || !email.contains('@') {
             return StatusCode::BAD_REQUEST;
         }
 
-        if User::find(&creds.username, &state.pool).await.is_ok() {
+        if User::find(&creds.username, &state.pool).await.is_ok() || User::find_by_email(&email, &state.pool).await.is_ok() {
             return StatusCode::CONFLICT;
         }
 
         match User::register(
             &state.pool,
             creds.username,
-            None::<String>,
+            Some(email),
             creds.password,
             false,
         )
         .await {
-            Ok(user) => {
-                if let Err(err) = user.create_session(&state.pool, session, None::<String>).await { // TODO: fetch ip
+            Ok(mut user) => {
+                if let Some(language) = session.get::<String>("user_language") {
+                    if let Err(err) = user.set_language(&state.pool, language).await {
+                        error!("{}", err);
+                    }
+                }
+                if let Err(err) = verification::send_verification(&state, &user).await {
+                    error!("Failed to send verification e-mail: {}", err);
+                }
+                if let Err(err) = user.create_session(&state.pool, session, ip, user_agent, creds.enforce_ip).await {
                    error!("{}", err);
                 }
                 StatusCode::OK
@@ -112,25 +206,93 @@ mod post {
     async fn login(
         state: Data<&Arc<AppState>>,
         session: &Session,
+        ip: Option<String>,
+        user_agent: Option<&str>,
         creds: Credentials
-    ) -> StatusCode {
-        let user = match User::find(&creds.username, &state.pool).await {
+    ) -> Response {
+        let account = creds.username.trim().to_lowercase();
+        if let Some(response) = crate::rate_limit::check(&state, "login-account", &account, LOGIN_PER_ACCOUNT).await {
+            return response;
+        }
+
+        let mut user = match User::find(&creds.username, &state.pool).await {
             Ok(user) => user,
-            Err(_) => return StatusCode::UNAUTHORIZED,
+            Err(_) => return StatusCode::UNAUTHORIZED.into_response(),
         };
 
         if is_valid(&session) {
-            return StatusCode::CONFLICT;
+            return StatusCode::CONFLICT.into_response();
+        }
+
+        match Lockout::locked_until(&state.pool, &user.id).await {
+            Ok(Some(locked_until)) => return crate::rate_limit::locked_out(locked_until),
+            Ok(None) => {}
+            Err(err) => {
+                error!("Failed to fetch lockout: {}", err);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+
+        if verify_password(&creds.password, &user.password_hash).is_err() {
+            return match Lockout::record_failure(&state.pool, &user.id).await {
+                Ok(Some(locked_until)) => crate::rate_limit::locked_out(locked_until),
+                Ok(None) => StatusCode::UNAUTHORIZED.into_response(),
+                Err(err) => {
+                    error!("Failed to record failed login: {}", err);
+                    StatusCode::UNAUTHORIZED.into_response()
+                }
+            };
+        }
+
+        // Only tell whoever knows the password that the account is deactivated.
+        if !user.is_active {
+            return Error::from_string("Account is deactivated.", StatusCode::FORBIDDEN).into_response();
+        }
+
+        match Totp::is_enabled(&state.pool, &user.id).await {
+            Ok(true) => {
+                // The new hash is only stored once the second factor is passed too.
+                let rehash = if needs_rehash(&user.password_hash) {
+                    hash_password(creds.password)
+                        .inspect_err(|err| error!("Failed to rehash password: {}", err))
+                        .ok()
+                } else {
+                    None
+                };
+                session.set("pending_login", PendingLogin::begin(&user.id, creds.enforce_ip, rehash));
+                let template = TwoFactorLoginTemplate::from_app_state(state, session);
+                return crate::render(&template).into_response();
+            }
+            Ok(false) => {}
+            Err(err) => {
+                error!("Failed to fetch 2FA status: {}", err);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
         }
 
-        if verify_password(creds.password, &user.password_hash).is_err() {
-            return StatusCode::UNAUTHORIZED;
+        if let Err(err) = Lockout::clear(&state.pool, &user.id).await {
+            error!("Failed to clear lockout: {}", err);
         }
 
-        if let Err(err) = user.create_session(&state.pool, session, None::<String>).await { // TODO: fetch ip
+        if needs_rehash(&user.password_hash) {
+            if let Err(err) = user.set_password(&state.pool, creds.password).await {
+                error!("Failed to rehash password: {}", err);
+            }
+        }
+
+        let needs_enrolment = two_factor::needs_enrolment(&state, &user).await;
+
+        if let Err(err) = user.create_session(&state.pool, session, ip, user_agent, creds.enforce_ip).await {
            error!("{}", err);
         }
 
-        StatusCode::OK
+        if needs_enrolment {
+            Response::builder()
+                .status(StatusCode::OK)
+                .header("HX-Redirect", "/user/settings/two-factor")
+                .finish()
+        } else {
+            StatusCode::OK.into_response()
+        }
     }
 }
@@ -0,0 +1,132 @@
+use crate::extend_with_app_state;
+use crate::mail::send_link;
+use common::database::user::User;
+use common::signing::SignedToken;
+use common::PodelError;
+use poem::{handler, web::Path};
+use serde::Deserialize;
+use sqlx::{Pool, Postgres};
+
+const PURPOSE: &str = "reset-password";
+const LIFETIME_HOURS: i64 = 1;
+
+extend_with_app_state! {
+    #[template(path = "user/forgot.html")]
+    struct ForgotPasswordTemplate {};
+
+    #[template(path = "user/reset.html")]
+    struct ResetPasswordTemplate {
+        token: String,
+        valid: bool,
+    };
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordForm {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordForm {
+    pub password: String,
+}
+
+/// User the reset token was issued for. Tokens are bound to the password hash,
+/// so each of them stops working as soon as the password changes.
+async fn find_user(pool: &Pool<Postgres>, secret_key: &[u8], token: &str) -> Result<User, PodelError> {
+    let token = SignedToken::decode(PURPOSE, token)?;
+    let user = User::find_by_id(token.subject(), pool).await?;
+    token.verify(secret_key, &user.password_hash)?;
+    Ok(user)
+}
+
+/// Mails the user a link to set a new password.
+pub async fn send_reset_link(state: &AppState, user: &User) -> Result<(), PodelError> {
+    let token = SignedToken::new(PURPOSE, &user.id, chrono::Duration::hours(LIFETIME_HOURS))
+        .encode(&state.secret_key, &user.password_hash)?;
+    let link = format!("{}/auth/reset/{token}", state.public_url);
+
+    send_link(state, user, "reset-password-subject", "reset-password-body", link).await
+}
+
+pub(crate) mod get {
+    use super::*;
+    use crate::PoemResult;
+
+    #[handler]
+    pub(crate) async fn forgot(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+    ) -> PoemResult {
+        let template = ForgotPasswordTemplate::from_app_state(state, session);
+        crate::render(&template)
+    }
+
+    #[handler]
+    pub(crate) async fn reset(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        Path(token): Path<String>,
+    ) -> PoemResult {
+        let valid = find_user(&state.pool, &state.secret_key, &token).await.is_ok();
+        let template = ResetPasswordTemplate::from_app_state(state, session, token, valid);
+        crate::render(&template)
+    }
+}
+
+pub(crate) mod post {
+    use log::error;
+    use poem::http::StatusCode;
+    use poem::web::Form;
+    use common::database::token::ApiToken;
+    use common::database::user::SessionData;
+    use super::*;
+
+    /// Mails a reset link if the address belongs to an account. Always succeeds, so it can't be used
+    /// to find out who is registered.
+    #[handler]
+    pub(crate) async fn forgot(
+        state: Data<&Arc<AppState>>,
+        Form(form): Form<ForgotPasswordForm>,
+    ) -> StatusCode {
+        let Ok(user) = User::find_by_email(form.email.trim(), &state.pool).await else {
+            return StatusCode::OK;
+        };
+
+        if let Err(err) = send_reset_link(&state, &user).await {
+            error!("Failed to send password reset e-mail: {}", err);
+        }
+
+        StatusCode::OK
+    }
+
+    /// Sets a new password, logs the user out everywhere and revokes their API tokens.
+    #[handler]
+    pub(crate) async fn reset(
+        state: Data<&Arc<AppState>>,
+        Path(token): Path<String>,
+        Form(form): Form<ResetPasswordForm>,
+    ) -> StatusCode {
+        if form.password.is_empty() {
+            return StatusCode::BAD_REQUEST;
+        }
+
+        let Ok(mut user) = find_user(&state.pool, &state.secret_key, &token).await else {
+            return StatusCode::GONE;
+        };
+
+        if let Err(err) = user.set_password(&state.pool, form.password).await {
+            error!("Failed to reset password: {}", err);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+
+        if let Err(err) = SessionData::revoke_all(&state.pool, &user.id).await {
+            error!("Failed to revoke sessions after password reset: {}", err);
+        }
+        if let Err(err) = ApiToken::revoke_all(&state.pool, &user.id).await {
+            error!("Failed to revoke API tokens after password reset: {}", err);
+        }
+
+        StatusCode::OK
+    }
+}
@@ -0,0 +1,264 @@
+use crate::extend_with_app_state;
+use common::database::token::{ApiToken, TokenScope};
+use common::database::user::{deserialize_checkbox, SessionData};
+use poem::{get, Route, handler, web::Path};
+use serde::Deserialize;
+
+extend_with_app_state! {
+    #[template(path = "user/settings/tokens.html")]
+    struct TokensTemplate {
+        tokens: Vec<ApiToken>,
+    };
+
+    #[template(path = "user/settings/token-created.html")]
+    struct TokenCreatedTemplate {
+        token: ApiToken,
+        plaintext: String,
+    };
+
+    #[template(path = "user/settings/password.html")]
+    struct PasswordTemplate {};
+
+    #[template(path = "user/settings/sessions.html")]
+    struct SessionsTemplate {
+        sessions: Vec<SessionData>,
+        current_session: Option<String>,
+    };
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenForm {
+    pub name: String,
+    #[serde(default, deserialize_with = "deserialize_checkbox")]
+    pub read: bool,
+    #[serde(default, deserialize_with = "deserialize_checkbox")]
+    pub write_comments: bool,
+    #[serde(default, deserialize_with = "deserialize_checkbox")]
+    pub admin: bool,
+    /// `0` for a token that never expires.
+    pub expires_in_days: u32,
+}
+
+impl TokenForm {
+    fn scopes(&self) -> Vec<TokenScope> {
+        [
+            (self.read, TokenScope::Read),
+            (self.write_comments, TokenScope::WriteComments),
+            (self.admin, TokenScope::Admin),
+        ]
+        .into_iter()
+        .filter_map(|(checked, scope)| checked.then_some(scope))
+        .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasswordForm {
+    pub old_password: String,
+    pub new_password: String,
+}
+
+pub fn route() -> Route {
+    Route::new()
+        .at("/tokens", get(get::tokens).post(post::token))
+        .at("/tokens/:id", poem::delete(delete::token))
+        .at("/password", get(get::password).post(post::password))
+        .nest("/two-factor", super::two_factor::route())
+        .at("/sessions", get(get::sessions).delete(delete::sessions))
+        .at("/sessions/:id", poem::delete(delete::session))
+}
+
+mod get {
+    use log::error;
+    use common::database::user::User;
+    use super::*;
+    use crate::PoemResult;
+
+    #[handler]
+    pub(crate) async fn tokens(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+    ) -> PoemResult {
+        let tokens = match User::from_session(session) {
+            Ok(user) => ApiToken::find_multiple(&state.pool, &user.id).await.unwrap_or_else(|err| {
+                error!("Failed to fetch API tokens: {}", err);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+
+        let template = TokensTemplate::from_app_state(state, session, tokens);
+        crate::render(&template)
+    }
+
+    #[handler]
+    pub(crate) async fn password(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+    ) -> PoemResult {
+        let template = PasswordTemplate::from_app_state(state, session);
+        crate::render(&template)
+    }
+
+    /// Devices the user is currently logged in on.
+    #[handler]
+    pub(crate) async fn sessions(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+    ) -> PoemResult {
+        let current = SessionData::from_session(session);
+        let sessions = match &current {
+            Some(current) => SessionData::find_by_user(&state.pool, &current.user.id).await.unwrap_or_else(|err| {
+                error!("Failed to fetch sessions: {}", err);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+
+        let template = SessionsTemplate::from_app_state(state, session, sessions, current.map(|current| current.id.clone()));
+        crate::render(&template)
+    }
+}
+
+mod post {
+    use log::error;
+    use poem::http::StatusCode;
+    use poem::web::{Form, Html};
+    use common::database::user::{verify_password, User};
+    use super::*;
+    use crate::auth::AuthUser;
+
+    /// Creates a token and shows its plaintext form once.
+    #[handler]
+    pub(crate) async fn token(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AuthUser(user): AuthUser,
+        Form(form): Form<TokenForm>,
+    ) -> poem::Result<Html<String>> {
+        let scopes = form.scopes();
+        if form.name.trim().is_empty() || scopes.is_empty() {
+            return Err(poem::Error::from_status(StatusCode::BAD_REQUEST));
+        }
+
+        let expires_at = (form.expires_in_days > 0)
+            .then(|| chrono::Utc::now() + chrono::Days::new(u64::from(form.expires_in_days)));
+
+        let (token, plaintext) = ApiToken::create(&state.pool, &user, form.name.trim(), scopes, expires_at)
+            .await
+            .map_err(|err| {
+                error!("Failed to create API token: {}", err);
+                poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+            })?;
+
+        let template = TokenCreatedTemplate::from_app_state(state, session, token, plaintext);
+        Ok(crate::render(&template)?)
+    }
+
+    /// Changes the password of the logged-in user, logs out all their other devices and revokes their API tokens.
+    #[handler]
+    pub(crate) async fn password(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AuthUser(user): AuthUser,
+        Form(form): Form<PasswordForm>,
+    ) -> StatusCode {
+        if form.new_password.is_empty() {
+            return StatusCode::BAD_REQUEST;
+        }
+
+        let Ok(mut user) = User::find_by_id(&user.id, &state.pool).await else {
+            return StatusCode::NOT_FOUND;
+        };
+
+        if verify_password(form.old_password, &user.password_hash).is_err() {
+            return StatusCode::FORBIDDEN;
+        }
+
+        if let Err(err) = user.set_password(&state.pool, form.new_password).await {
+            error!("Failed to change password: {}", err);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+
+        let revoked = match SessionData::from_session(session) {
+            Some(current) => SessionData::revoke_others(&state.pool, &user.id, &current.id).await,
+            None => SessionData::revoke_all(&state.pool, &user.id).await,
+        };
+
+        if let Err(err) = revoked {
+            error!("Failed to revoke sessions after password change: {}", err);
+        }
+        // Tokens could have been created by whoever knew the old password.
+        if let Err(err) = ApiToken::revoke_all(&state.pool, &user.id).await {
+            error!("Failed to revoke API tokens after password change: {}", err);
+        }
+
+        StatusCode::OK
+    }
+}
+
+mod delete {
+    use log::error;
+    use poem::http::StatusCode;
+    use common::PodelError;
+    use super::*;
+    use crate::auth::AuthUser;
+
+    #[handler]
+    pub(crate) async fn token(
+        state: Data<&Arc<AppState>>,
+        AuthUser(user): AuthUser,
+        Path(id): Path<String>,
+    ) -> StatusCode {
+        match ApiToken::revoke(&state.pool, &user.id, id).await {
+            Ok(()) => StatusCode::OK,
+            Err(PodelError::Empty()) => StatusCode::NOT_FOUND,
+            Err(err) => {
+                error!("Failed to revoke API token: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Logs out a single device.
+    #[handler]
+    pub(crate) async fn session(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AuthUser(user): AuthUser,
+        Path(id): Path<String>,
+    ) -> StatusCode {
+        match SessionData::revoke(&state.pool, &user.id, &id).await {
+            Ok(()) => {
+                if session.get::<String>("session_id").is_some_and(|current| current == id) {
+                    session.clear();
+                }
+                StatusCode::OK
+            }
+            Err(PodelError::Empty()) => StatusCode::NOT_FOUND,
+            Err(err) => {
+                error!("Failed to revoke session: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Logs out everywhere, including the current device.
+    #[handler]
+    pub(crate) async fn sessions(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AuthUser(user): AuthUser,
+    ) -> StatusCode {
+        match SessionData::revoke_all(&state.pool, &user.id).await {
+            Ok(_) => {
+                session.clear();
+                StatusCode::OK
+            }
+            Err(err) => {
+                error!("Failed to revoke sessions: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
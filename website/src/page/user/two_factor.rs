@@ -0,0 +1,433 @@
+use crate::extend_with_app_state;
+use common::database::setting::Setting;
+use common::database::totp::{RecoveryCode, Totp};
+use common::database::user::User;
+use common::signing::random_nonce;
+use common::PodelError;
+use poem::http::StatusCode;
+use poem::web::Form;
+use poem::{handler, IntoResponse, Response, Route};
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Minutes the user has to enter the code after the password was accepted.
+const PENDING_LOGIN_MINUTES: u64 = 5;
+/// Wrong codes accepted within [`PENDING_LOGIN_MINUTES`] before the login has to start over.
+const MAX_ATTEMPTS: u8 = 5;
+
+/// Logins waiting for a code, keyed by the nonce stored in the session. Everything else stays on the server,
+/// as the session cookie is neither signed nor encrypted.
+static PENDING_LOGINS: LazyLock<Mutex<HashMap<String, PendingLogin>>> = LazyLock::new(Default::default);
+
+extend_with_app_state! {
+    #[template(path = "user/two-factor-login.html")]
+    pub struct TwoFactorLoginTemplate {};
+
+    #[template(path = "user/settings/two-factor.html")]
+    struct TwoFactorTemplate {
+        enabled: bool,
+        remaining_codes: i64,
+        required_for_admins: bool,
+    };
+
+    #[template(path = "user/settings/two-factor-enrol.html")]
+    struct TwoFactorEnrolTemplate {
+        qr_code: String,
+        secret: String,
+    };
+
+    #[template(path = "user/settings/recovery-codes.html")]
+    struct RecoveryCodesTemplate {
+        codes: Vec<String>,
+    };
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorForm {
+    /// code from the authenticator app or a recovery code
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RequireTwoFactorForm {
+    #[serde(default, deserialize_with = "common::database::user::deserialize_checkbox")]
+    pub required: bool,
+}
+
+/// Login whose password was accepted and which waits for the second factor.
+pub struct PendingLogin {
+    pub user_id: String,
+    pub enforce_ip: bool,
+    attempts: u8,
+    /// password hash with the current parameters, stored once the second factor is passed
+    rehash: Option<String>,
+    started_at: Instant,
+}
+
+impl PendingLogin {
+    fn window() -> Duration {
+        Duration::from_secs(60 * PENDING_LOGIN_MINUTES)
+    }
+
+    fn is_live(&self) -> bool {
+        self.started_at.elapsed() < Self::window()
+    }
+
+    /// Starts the second step and returns the nonce to keep in the session. Attempts of a recent second step
+    /// of the user carry over, so entering the password again does not reset them.
+    pub fn begin(user_id: impl Into<String>, enforce_ip: bool, rehash: Option<String>) -> String {
+        let user_id = user_id.into();
+        let mut logins = PENDING_LOGINS.lock().unwrap_or_else(PoisonError::into_inner);
+        logins.retain(|_, login| login.is_live());
+
+        let attempts = logins.values().filter(|login| login.user_id == user_id).map(|login| login.attempts).max();
+        logins.retain(|_, login| login.user_id != user_id);
+
+        let nonce = random_nonce();
+        logins.insert(nonce.clone(), Self {
+            user_id,
+            enforce_ip,
+            attempts: attempts.unwrap_or(0),
+            rehash,
+            started_at: Instant::now(),
+        });
+        nonce
+    }
+
+    /// Counts an attempt before the code is checked, so concurrent requests can't exceed the limit.
+    /// Returns the user and the attempts left afterwards, `None` if there were none or the login is unknown.
+    fn take_attempt(nonce: &str) -> Option<(String, u8)> {
+        let mut logins = PENDING_LOGINS.lock().unwrap_or_else(PoisonError::into_inner);
+        let login = logins
+            .get_mut(nonce)
+            .filter(|login| login.is_live() && login.attempts < MAX_ATTEMPTS)?;
+        login.attempts += 1;
+        Some((login.user_id.clone(), MAX_ATTEMPTS - login.attempts))
+    }
+
+    /// Ends the second step after a correct code.
+    fn finish(nonce: &str) -> Option<Self> {
+        let mut logins = PENDING_LOGINS.lock().unwrap_or_else(PoisonError::into_inner);
+        logins.remove(nonce)
+    }
+}
+
+pub fn route() -> Route {
+    Route::new()
+        .at("/", poem::get(get::settings))
+        .at("/enrol", poem::post(post::enrol))
+        .at("/disable", poem::post(post::disable))
+        .at("/enable", poem::post(post::enable))
+        .at("/recovery-codes", poem::post(post::recovery_codes))
+        .at("/require", poem::post(post::require))
+}
+
+/// Accepts either a current TOTP code or an unused recovery code.
+async fn verify_code(state: &AppState, user_id: &str, code: &str) -> Result<(), PodelError> {
+    let totp = Totp::find(&state.pool, user_id)
+        .await?
+        .filter(|totp| totp.enabled_at.is_some())
+        .ok_or(PodelError::Empty())?;
+
+    if code.trim().chars().all(|char| char.is_ascii_digit()) {
+        totp.verify(&state.pool, code).await
+    } else {
+        RecoveryCode::redeem(&state.pool, user_id, code).await
+    }
+}
+
+/// Whether an admin has to enable 2FA before their privileges apply.
+pub async fn needs_enrolment(state: &AppState, user: &User) -> bool {
+    if !user.is_admin {
+        return false;
+    }
+
+    let required = Setting::get_bool(&state.pool, Setting::REQUIRE_ADMIN_TWO_FACTOR).await.unwrap_or(false);
+    required && !Totp::is_enabled(&state.pool, &user.id).await.unwrap_or(false)
+}
+
+fn qr_code(uri: &str) -> String {
+    QrCode::new(uri.as_bytes())
+        .map(|code| code.render::<svg::Color>().min_dimensions(200, 200).build())
+        .map(|svg| svg.find("<svg").map_or(svg.clone(), |start| svg[start..].to_string()))
+        .unwrap_or_default()
+}
+
+pub(crate) mod get {
+    use log::error;
+    use super::*;
+    use crate::PoemResult;
+
+    #[handler]
+    pub(crate) async fn settings(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+    ) -> PoemResult {
+        let (enabled, remaining_codes) = match User::from_session(session) {
+            Ok(user) => {
+                let enabled = Totp::is_enabled(&state.pool, &user.id).await.unwrap_or_else(|err| {
+                    error!("Failed to fetch 2FA status: {}", err);
+                    false
+                });
+                let remaining = RecoveryCode::remaining(&state.pool, &user.id).await.unwrap_or_default();
+                (enabled, remaining)
+            }
+            Err(_) => (false, 0),
+        };
+        let required_for_admins = Setting::get_bool(&state.pool, Setting::REQUIRE_ADMIN_TWO_FACTOR)
+            .await
+            .unwrap_or(false);
+
+        let template = TwoFactorTemplate::from_app_state(state, session, enabled, remaining_codes, required_for_admins);
+        crate::render(&template)
+    }
+}
+
+pub(crate) mod post {
+    use log::error;
+    use common::database::audit::Actor;
+    use common::database::lockout::Lockout;
+    use common::database::user::SessionData;
+    use super::*;
+    use crate::auth::{AdminUser, AuthUser};
+    use crate::client_ip::ClientIp;
+
+    /// Second login step, finishes the login started by a correct password.
+    #[handler]
+    pub(crate) async fn login(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        headers: &poem::http::HeaderMap,
+        ClientIp(ip): ClientIp,
+        Form(form): Form<TwoFactorForm>,
+    ) -> Response {
+        let Some(nonce) = session.get::<String>("pending_login") else {
+            return StatusCode::UNAUTHORIZED.into_response();
+        };
+        let Some((user_id, attempts_left)) = PendingLogin::take_attempt(&nonce) else {
+            session.remove("pending_login");
+            return StatusCode::UNAUTHORIZED.into_response();
+        };
+
+        match Lockout::locked_until(&state.pool, &user_id).await {
+            Ok(Some(locked_until)) => return crate::rate_limit::locked_out(locked_until),
+            Ok(None) => {}
+            Err(err) => {
+                error!("Failed to fetch lockout: {}", err);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+
+        if let Err(err) = verify_code(&state, &user_id, &form.code).await {
+            if let Err(err) = Lockout::record_failure(&state.pool, &user_id).await {
+                error!("Failed to record failed login: {}", err);
+            }
+            if attempts_left == 0 {
+                session.remove("pending_login");
+            }
+            if !matches!(err, PodelError::UserError(_)) {
+                error!("Failed to verify 2FA code: {}", err);
+            }
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+
+        session.remove("pending_login");
+        let Some(pending) = PendingLogin::finish(&nonce) else {
+            return StatusCode::UNAUTHORIZED.into_response();
+        };
+        if let Err(err) = Lockout::clear(&state.pool, &pending.user_id).await {
+            error!("Failed to clear lockout: {}", err);
+        }
+
+        let Ok(mut user) = User::find_by_id(&pending.user_id, &state.pool).await else {
+            return StatusCode::UNAUTHORIZED.into_response();
+        };
+        if !user.is_active {
+            return StatusCode::FORBIDDEN.into_response();
+        }
+
+        if let Some(password_hash) = pending.rehash {
+            if let Err(err) = user.set_password_hash(&state.pool, password_hash).await {
+                error!("Failed to rehash password: {}", err);
+            }
+        }
+
+        let user_agent = headers.get(poem::http::header::USER_AGENT).and_then(|value| value.to_str().ok());
+        if let Err(err) = user
+            .create_session(&state.pool, session, ip.map(|ip| ip.to_string()), user_agent, pending.enforce_ip)
+            .await
+        {
+            error!("{}", err);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+
+        StatusCode::OK.into_response()
+    }
+
+    /// Starts the enrolment and shows the secret to scan.
+    #[handler]
+    pub(crate) async fn enrol(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AuthUser(user): AuthUser,
+    ) -> Response {
+        let totp = match Totp::begin_enrolment(&state.pool, &user.id).await {
+            Ok(totp) => totp,
+            Err(PodelError::UserError(_)) => return StatusCode::CONFLICT.into_response(),
+            Err(err) => {
+                error!("Failed to start 2FA enrolment: {}", err);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+
+        let qr_code = qr_code(&totp.otpauth_uri(state.title, &user.name));
+        let template = TwoFactorEnrolTemplate::from_app_state(state, session, qr_code, totp.secret_base32());
+        crate::render(&template).into_response()
+    }
+
+    /// Confirms the enrolment and shows the recovery codes once.
+    #[handler]
+    pub(crate) async fn enable(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AuthUser(user): AuthUser,
+        Form(form): Form<TwoFactorForm>,
+    ) -> Response {
+        let Ok(Some(totp)) = Totp::find(&state.pool, &user.id).await else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+
+        match totp.enable(&state.pool, &form.code).await {
+            Ok(codes) => {
+                SessionData::refresh_user(&user.id);
+                let template = RecoveryCodesTemplate::from_app_state(state, session, codes);
+                crate::render(&template).into_response()
+            }
+            Err(PodelError::UserError(_)) => StatusCode::BAD_REQUEST.into_response(),
+            Err(err) => {
+                error!("Failed to enable 2FA: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+
+    /// Replaces the recovery codes, the old ones stop working.
+    #[handler]
+    pub(crate) async fn recovery_codes(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        AuthUser(user): AuthUser,
+        Form(form): Form<TwoFactorForm>,
+    ) -> Response {
+        if verify_code(&state, &user.id, &form.code).await.is_err() {
+            return StatusCode::FORBIDDEN.into_response();
+        }
+
+        match RecoveryCode::regenerate(&state.pool, &user.id).await {
+            Ok(codes) => {
+                let template = RecoveryCodesTemplate::from_app_state(state, session, codes);
+                crate::render(&template).into_response()
+            }
+            Err(err) => {
+                error!("Failed to regenerate recovery codes: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+
+    /// Makes 2FA mandatory for admins, or optional again.
+    #[handler]
+    pub(crate) async fn require(
+        state: Data<&Arc<AppState>>,
+        AdminUser(user): AdminUser,
+        ClientIp(ip): ClientIp,
+        Form(form): Form<RequireTwoFactorForm>,
+    ) -> StatusCode {
+        // Don't let an admin lock themselves out of the setting.
+        if form.required && !Totp::is_enabled(&state.pool, &user.id).await.unwrap_or(false) {
+            return StatusCode::CONFLICT;
+        }
+
+        match Setting::set_bool(&state.pool, &Actor::new(&user, ip), Setting::REQUIRE_ADMIN_TWO_FACTOR, form.required).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to change 2FA requirement: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Turns 2FA off, confirmed by a current code.
+    #[handler]
+    pub(crate) async fn disable(
+        state: Data<&Arc<AppState>>,
+        AuthUser(user): AuthUser,
+        Form(form): Form<TwoFactorForm>,
+    ) -> StatusCode {
+        if verify_code(&state, &user.id, &form.code).await.is_err() {
+            return StatusCode::FORBIDDEN;
+        }
+
+        match Totp::disable(&state.pool, &user.id).await {
+            Ok(()) => {
+                SessionData::refresh_user(&user.id);
+                StatusCode::OK
+            }
+            Err(err) => {
+                error!("Failed to disable 2FA: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_id() -> String {
+        random_nonce()
+    }
+
+    #[test]
+    fn unknown_nonce_has_no_attempts() {
+        assert!(PendingLogin::take_attempt("made-up").is_none());
+    }
+
+    #[test]
+    fn nonce_resolves_to_its_user() {
+        let user_id = user_id();
+        let nonce = PendingLogin::begin(&user_id, true, None);
+
+        assert_eq!(PendingLogin::take_attempt(&nonce), Some((user_id, MAX_ATTEMPTS - 1)));
+        assert!(PendingLogin::finish(&nonce).is_some_and(|login| login.enforce_ip));
+        assert!(PendingLogin::take_attempt(&nonce).is_none());
+    }
+
+    #[test]
+    fn attempts_run_out() {
+        let nonce = PendingLogin::begin(user_id(), false, None);
+        for _ in 0..MAX_ATTEMPTS {
+            assert!(PendingLogin::take_attempt(&nonce).is_some());
+        }
+
+        assert!(PendingLogin::take_attempt(&nonce).is_none());
+    }
+
+    #[test]
+    fn new_login_replaces_the_old_one_and_keeps_its_attempts() {
+        let user_id = user_id();
+        let first = PendingLogin::begin(&user_id, false, None);
+        PendingLogin::take_attempt(&first);
+        PendingLogin::take_attempt(&first);
+        let second = PendingLogin::begin(&user_id, false, Some("hash".into()));
+
+        assert!(PendingLogin::take_attempt(&first).is_none());
+        assert_eq!(PendingLogin::take_attempt(&second), Some((user_id, MAX_ATTEMPTS - 3)));
+        assert_eq!(PendingLogin::finish(&second).and_then(|login| login.rehash), Some("hash".into()));
+    }
+}
@@ -0,0 +1,82 @@
+use crate::extend_with_app_state;
+use crate::mail::send_link;
+use common::database::user::User;
+use common::signing::SignedToken;
+use common::PodelError;
+use poem::{handler, web::Path};
+
+const PURPOSE: &str = "verify-email";
+const LIFETIME_HOURS: i64 = 48;
+
+extend_with_app_state! {
+    #[template(path = "user/verified.html")]
+    struct VerifiedTemplate {
+        verified: bool,
+    };
+}
+
+/// Mails the user a link confirming their e-mail address. The link stops working once the address changes.
+pub async fn send_verification(state: &AppState, user: &User) -> Result<(), PodelError> {
+    let Some(email) = &user.email else {
+        return Err(PodelError::UserError("User has no e-mail address.".into()));
+    };
+
+    let token = SignedToken::new(PURPOSE, &user.id, chrono::Duration::hours(LIFETIME_HOURS))
+        .encode(&state.secret_key, email)?;
+    let link = format!("{}/auth/verify/{token}", state.public_url);
+
+    send_link(state, user, "verify-email-subject", "verify-email-body", link).await
+}
+
+pub(crate) mod get {
+    use log::warn;
+    use super::*;
+    use crate::PoemResult;
+
+    #[handler]
+    pub(crate) async fn verify(
+        state: Data<&Arc<AppState>>,
+        session: &Session,
+        Path(token): Path<String>,
+    ) -> PoemResult {
+        let result: Result<(), PodelError> = async {
+            let token = SignedToken::decode(PURPOSE, &token)?;
+            let mut user = User::find_by_id(token.subject(), &state.pool).await?;
+            token.verify(&state.secret_key, user.email.as_deref().unwrap_or_default())?;
+            user.mark_verified(&state.pool).await
+        }.await;
+
+        if let Err(err) = &result {
+            warn!("E-mail verification failed: {}", err);
+        }
+
+        let template = VerifiedTemplate::from_app_state(state, session, result.is_ok());
+        crate::render(&template)
+    }
+}
+
+pub(crate) mod post {
+    use log::error;
+    use poem::http::StatusCode;
+    use super::*;
+    use crate::auth::AuthUser;
+
+    /// Sends the verification link again.
+    #[handler]
+    pub(crate) async fn resend(
+        state: Data<&Arc<AppState>>,
+        AuthUser(user): AuthUser,
+    ) -> StatusCode {
+        if user.is_verified {
+            return StatusCode::CONFLICT;
+        }
+
+        match send_verification(&state, &user).await {
+            Ok(()) => StatusCode::OK,
+            Err(err) => {
+                error!("Failed to send verification e-mail: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
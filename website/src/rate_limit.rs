@@ -0,0 +1,137 @@
+use crate::auth::TokenAuth;
+use crate::client_ip::client_ip;
+use common::database::user::User;
+use common::rate_limit::Quota;
+use common::AppState;
+use log::error;
+use poem::http::{header, StatusCode};
+use poem::session::Session;
+use poem::{Endpoint, IntoResponse, Middleware, Request, Response, Result};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Login and registration attempts from a single address.
+pub const AUTH_PER_IP: Quota = Quota::new(20, Duration::from_secs(10 * 60));
+/// Login attempts against a single account, from anywhere.
+pub const LOGIN_PER_ACCOUNT: Quota = Quota::new(10, Duration::from_secs(15 * 60));
+/// Accounts registered from a single address.
+pub const REGISTER_PER_IP: Quota = Quota::new(3, Duration::from_secs(60 * 60));
+/// Comments and replies posted by a single user.
+pub const COMMENT_PER_USER: Quota = Quota::new(10, Duration::from_secs(10 * 60));
+/// Comments and replies posted from a single address.
+pub const COMMENT_PER_IP: Quota = Quota::new(30, Duration::from_secs(10 * 60));
+/// Reports filed by a single user.
+pub const REPORT_PER_USER: Quota = Quota::new(20, Duration::from_secs(60 * 60));
+/// Organization claims filed by a single user.
+pub const CLAIM_PER_USER: Quota = Quota::new(5, Duration::from_secs(24 * 60 * 60));
+/// Website ownership checks run by a single user, each one queries DNS and the website.
+pub const VERIFICATION_PER_USER: Quota = Quota::new(10, Duration::from_secs(10 * 60));
+
+/// Takes a token from the bucket `name` of `key`. Returns the response to send if the bucket is empty.
+/// Store failures are logged and let the request through, an outage must not lock everyone out.
+pub async fn check(state: &AppState, name: &str, key: &str, quota: Quota) -> Option<Response> {
+    match state.rate_limiter.acquire(&format!("{name}:{key}"), quota).await {
+        Ok(None) => None,
+        Ok(Some(retry_after)) => Some(too_many_requests(retry_after)),
+        Err(err) => {
+            error!("Failed to check rate limit: {}", err);
+            None
+        }
+    }
+}
+
+/// `429 Too Many Requests` telling the client when to try again.
+pub fn too_many_requests(retry_after: Duration) -> Response {
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(header::RETRY_AFTER, retry_after.as_secs().max(1))
+        .finish()
+}
+
+/// `429 Too Many Requests` until an account lockout ends.
+pub fn locked_out(locked_until: chrono::DateTime<chrono::Utc>) -> Response {
+    too_many_requests((locked_until - chrono::Utc::now()).to_std().unwrap_or_default())
+}
+
+/// Limits how often an endpoint can be called per client address and per logged-in user.
+pub struct RateLimit {
+    name: &'static str,
+    per_ip: Option<Quota>,
+    per_user: Option<Quota>,
+}
+
+impl RateLimit {
+    pub const fn new(name: &'static str) -> Self {
+        Self { name, per_ip: None, per_user: None }
+    }
+
+    pub const fn per_ip(mut self, quota: Quota) -> Self {
+        self.per_ip = Some(quota);
+        self
+    }
+
+    /// Anonymous requests are only limited per address.
+    pub const fn per_user(mut self, quota: Quota) -> Self {
+        self.per_user = Some(quota);
+        self
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for RateLimit {
+    type Output = RateLimitEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RateLimitEndpoint {
+            inner: ep,
+            name: self.name,
+            per_ip: self.per_ip,
+            per_user: self.per_user,
+        }
+    }
+}
+
+pub struct RateLimitEndpoint<E> {
+    inner: E,
+    name: &'static str,
+    per_ip: Option<Quota>,
+    per_user: Option<Quota>,
+}
+
+impl<E: Endpoint> Endpoint for RateLimitEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let Some(state) = req.extensions().get::<Arc<AppState>>().cloned() else {
+            return self.inner.call(req).await.map(IntoResponse::into_response);
+        };
+
+        if let Some(quota) = self.per_ip {
+            if let Some(ip) = client_ip(&req, &state.trusted_proxies) {
+                if let Some(response) = check(&state, &format!("{}-ip", self.name), &ip.to_string(), quota).await {
+                    return Ok(response);
+                }
+            }
+        }
+
+        if let Some(quota) = self.per_user {
+            let user_id = req
+                .extensions()
+                .get::<TokenAuth>()
+                .map(|auth| auth.user.id.clone())
+                .or_else(|| {
+                    req.extensions()
+                        .get::<Session>()
+                        .and_then(|session| User::from_session(session).ok())
+                        .map(|user| user.id.clone())
+                });
+
+            if let Some(user_id) = user_id {
+                if let Some(response) = check(&state, &format!("{}-user", self.name), &user_id, quota).await {
+                    return Ok(response);
+                }
+            }
+        }
+
+        self.inner.call(req).await.map(IntoResponse::into_response)
+    }
+}